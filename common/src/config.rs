@@ -1,5 +1,6 @@
 use config::Config;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AppSettings {
@@ -18,6 +19,260 @@ pub struct IndexerSettings {
     pub port: String,
     pub index_directory_name: String,
     pub db_file_name: String,
+    #[serde(default)]
+    pub file_types: FileTypeSettings,
+    #[serde(default)]
+    pub conversion_worker: ConversionWorkerSettings,
+    #[serde(default)]
+    pub conversion_cache: ConversionCacheSettings,
+    #[serde(default)]
+    pub embedded_files: EmbeddedFileSettings,
+    #[serde(default)]
+    pub search: SearchSettings,
+    #[serde(default)]
+    pub ranking: RankingSettings,
+}
+
+/// Extends or overrides the built-in file types, e.g.
+///
+/// ```toml
+/// [indexer.file_types.extensions]
+/// vue = "text/x-vue"
+/// log = "text/plain"
+///
+/// [indexer.file_types.mime_types."text/x-vue"]
+/// conversion = "clear_text"
+///
+/// [indexer.file_types.mime_types."application/pdf"]
+/// max_file_size = 104857600
+///
+/// [indexer.file_types.mime_types."image/png"]
+/// enabled = false
+/// ```
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FileTypeSettings {
+    /// Maps file extensions without the leading dot to a mime type.
+    #[serde(default)]
+    pub extensions: HashMap<String, String>,
+    #[serde(default)]
+    pub mime_types: HashMap<String, MimeTypeSettings>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MimeTypeSettings {
+    /// The name of the conversion used for files of this type, e.g. `clear_text` or `pdf`.
+    pub conversion: Option<String>,
+    pub enabled: Option<bool>,
+    /// Files of this type that are larger than this amount of bytes are not indexed.
+    pub max_file_size: Option<u64>,
+}
+
+/// Conversions run in a separate worker process, which is restarted when a file exceeds these limits.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConversionWorkerSettings {
+    /// Runs the conversions inside the indexer process instead, which is only useful for debugging.
+    #[serde(default = "default_conversion_worker_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_conversion_worker_timeout_in_seconds")]
+    pub timeout_in_seconds: u64,
+    /// The amount of memory in bytes the operating system lets the worker use, `0` disables the limit.
+    #[serde(default = "default_conversion_worker_memory_limit")]
+    pub memory_limit: u64,
+}
+
+impl Default for ConversionWorkerSettings {
+    fn default() -> Self {
+        return Self {
+            enabled: default_conversion_worker_enabled(),
+            timeout_in_seconds: default_conversion_worker_timeout_in_seconds(),
+            memory_limit: default_conversion_worker_memory_limit(),
+        };
+    }
+}
+
+fn default_conversion_worker_enabled() -> bool {
+    return true;
+}
+
+fn default_conversion_worker_timeout_in_seconds() -> u64 {
+    return 60;
+}
+
+fn default_conversion_worker_memory_limit() -> u64 {
+    return 2 * 1024 * 1024 * 1024;
+}
+
+/// Caches the converted text of files by their hash, so identical files are only converted once.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConversionCacheSettings {
+    #[serde(default = "default_conversion_cache_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_conversion_cache_db_file_name")]
+    pub db_file_name: String,
+    /// The least recently used entries are evicted when the cache grows beyond this amount of bytes.
+    #[serde(default = "default_conversion_cache_max_size")]
+    pub max_size: u64,
+}
+
+impl Default for ConversionCacheSettings {
+    fn default() -> Self {
+        return Self {
+            enabled: default_conversion_cache_enabled(),
+            db_file_name: default_conversion_cache_db_file_name(),
+            max_size: default_conversion_cache_max_size(),
+        };
+    }
+}
+
+fn default_conversion_cache_enabled() -> bool {
+    return true;
+}
+
+fn default_conversion_cache_db_file_name() -> String {
+    return "conversion_cache.sqlite".to_string();
+}
+
+fn default_conversion_cache_max_size() -> u64 {
+    return 1024 * 1024 * 1024;
+}
+
+/// Files inside of other files, like the attachments of a mail or the entries of an archive, are indexed with a
+/// path like `backup.zip!/docs/report.pdf`. The limits apply to a file and everything nested in it, which defuses
+/// zip bombs.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmbeddedFileSettings {
+    /// How deep embedded files are followed, e.g. a mail attached to a mail in an mbox has a depth of 3.
+    #[serde(default = "default_embedded_files_max_depth")]
+    pub max_depth: u32,
+    /// The amount of files embedded in a single file, including the ones nested in them. The messages of an mbox
+    /// don't count, only their attachments.
+    #[serde(default = "default_embedded_files_max_count")]
+    pub max_count: u64,
+    /// The amount of uncompressed bytes of all embedded files together, again without the messages of an mbox.
+    #[serde(default = "default_embedded_files_max_total_size")]
+    pub max_total_size: u64,
+    #[serde(default = "default_embedded_files_index_mail_attachments")]
+    pub index_mail_attachments: bool,
+}
+
+impl Default for EmbeddedFileSettings {
+    fn default() -> Self {
+        return Self {
+            max_depth: default_embedded_files_max_depth(),
+            max_count: default_embedded_files_max_count(),
+            max_total_size: default_embedded_files_max_total_size(),
+            index_mail_attachments: default_embedded_files_index_mail_attachments(),
+        };
+    }
+}
+
+fn default_embedded_files_max_depth() -> u32 {
+    return 5;
+}
+
+fn default_embedded_files_max_count() -> u64 {
+    return 10000;
+}
+
+fn default_embedded_files_max_total_size() -> u64 {
+    return 512 * 1024 * 1024;
+}
+
+fn default_embedded_files_index_mail_attachments() -> bool {
+    return true;
+}
+
+/// Limits the work of a single search, substring and regex searches have to check the contents of every candidate.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchSettings {
+    /// The amount of files a search returns at most.
+    #[serde(default = "default_search_max_hits")]
+    pub max_hits: u64,
+    /// Substring and regex searches return the files found so far once this time is up.
+    #[serde(default = "default_search_time_budget_in_milliseconds")]
+    pub time_budget_in_milliseconds: u64,
+}
+
+impl Default for SearchSettings {
+    fn default() -> Self {
+        return Self {
+            max_hits: default_search_max_hits(),
+            time_budget_in_milliseconds: default_search_time_budget_in_milliseconds(),
+        };
+    }
+}
+
+fn default_search_max_hits() -> u64 {
+    return 1000;
+}
+
+fn default_search_time_budget_in_milliseconds() -> u64 {
+    return 2000;
+}
+
+/// Weights of the ranking on top of the relevance of the contents. A weight of 0, or a factor of 1, turns its part off.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RankingSettings {
+    /// Files modified just now score up to `1 + recency_weight` times as high, this boost halves every half life.
+    #[serde(default = "default_ranking_recency_weight")]
+    pub recency_weight: f32,
+    #[serde(default = "default_ranking_recency_half_life_in_days")]
+    pub recency_half_life_in_days: f32,
+    /// Matches in the file name count this many times as much as matches in the contents.
+    #[serde(default = "default_ranking_file_name_boost")]
+    pub file_name_boost: f32,
+    /// Files score `1 / (1 + path_depth_weight * n)` times as high, where n is the amount of directories in their path.
+    #[serde(default = "default_ranking_path_depth_weight")]
+    pub path_depth_weight: f32,
+    /// Files in directories that look like archives or backups score this many times as high.
+    #[serde(default = "default_ranking_demoted_directory_factor")]
+    pub demoted_directory_factor: f32,
+    /// Directories whose name contains one of these words, ignoring case, look like archives or backups, e.g.
+    /// `Backup 2019` or `old_projects`. Only applies to files indexed after a change.
+    #[serde(default = "default_ranking_demoted_directory_words")]
+    pub demoted_directory_words: Vec<String>,
+}
+
+impl Default for RankingSettings {
+    fn default() -> Self {
+        return Self {
+            recency_weight: default_ranking_recency_weight(),
+            recency_half_life_in_days: default_ranking_recency_half_life_in_days(),
+            file_name_boost: default_ranking_file_name_boost(),
+            path_depth_weight: default_ranking_path_depth_weight(),
+            demoted_directory_factor: default_ranking_demoted_directory_factor(),
+            demoted_directory_words: default_ranking_demoted_directory_words(),
+        };
+    }
+}
+
+fn default_ranking_recency_weight() -> f32 {
+    return 0.5;
+}
+
+fn default_ranking_recency_half_life_in_days() -> f32 {
+    return 90.0;
+}
+
+fn default_ranking_file_name_boost() -> f32 {
+    return 2.0;
+}
+
+fn default_ranking_path_depth_weight() -> f32 {
+    return 0.05;
+}
+
+fn default_ranking_demoted_directory_factor() -> f32 {
+    return 0.5;
+}
+
+fn default_ranking_demoted_directory_words() -> Vec<String> {
+    return [
+        "archive", "archived", "archives", "backup", "backups", "bak", "old", "trash",
+    ]
+    .iter()
+    .map(|v| v.to_string())
+    .collect();
 }
 
 pub fn init_config() -> AppSettings {
@@ -5,7 +5,7 @@ mod blocking_client;
 
 use crate::blocking_client::BlockingClient;
 use crate::file_indexer_service::{
-    DuplicatedFile, FindDuplicatedFilesQuery, SearchFileByContentsQuery,
+    DuplicatedFile, FindDuplicatedFilesQuery, SearchFileByContentsQuery, SearchMode, SearchScope,
 };
 use log::{error, info};
 use tonic::Request;
@@ -17,8 +17,9 @@ pub mod proto_utils {
     tonic::include_proto!("proto_utils");
 }
 
+/// Searches every indexed file, or only the files below the directory `within`, e.g. a project.
 #[tauri::command(rename_all = "camelCase")]
-fn search_for_file(query: &str) -> Vec<String> {
+fn search_for_file(query: &str, within: Option<String>) -> Vec<String> {
     let mut client = match BlockingClient::connect("http://127.0.0.1:50051") {
         Ok(v) => v,
         Err(e) => {
@@ -32,6 +33,14 @@ fn search_for_file(query: &str) -> Vec<String> {
 
     let response = match client.search_file_by_contents(Request::new(SearchFileByContentsQuery {
         query: query.to_string(),
+        max_matching_lines: None,
+        mode: SearchMode::Terms as i32,
+        fuzzy_distance: None,
+        scope: within.map(|v| SearchScope {
+            include: vec![v],
+            exclude: Vec::new(),
+        }),
+        sort_by: None,
     })) {
         Ok(v) => v,
         Err(e) => {
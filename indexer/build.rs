@@ -19,4 +19,10 @@ fn main() {
         .build_client(false)
         .compile(&["../proto/file_indexer.proto"], &["../proto"])
         .unwrap();
+
+    tonic_build::configure()
+        .build_server(false)
+        .build_client(false)
+        .compile(&["../proto/conversion_worker.proto"], &["../proto"])
+        .unwrap();
 }
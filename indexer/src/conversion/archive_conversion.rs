@@ -0,0 +1,393 @@
+use crate::conversion::{Conversion, ConvertedDocument, EmbeddedFile};
+use anyhow::{anyhow, Error};
+use dscvr_common::config::EmbeddedFileSettings;
+use flate2::read::MultiGzDecoder;
+use log::{debug, warn};
+use std::io::{self, Cursor, Read, Write};
+use std::path::{Component, Path};
+
+enum ArchiveFormat {
+    Zip,
+    Tar,
+    Gzip,
+    Xz,
+}
+
+/// Lists the entries of an archive as its contents and extracts them, so they are indexed as files of their own.
+/// Extraction stops once the limits for embedded files are reached.
+pub(crate) struct ArchiveConversion {
+    format: ArchiveFormat,
+    max_count: u64,
+    max_total_size: u64,
+}
+
+impl ArchiveConversion {
+    pub(crate) fn zip(settings: &EmbeddedFileSettings) -> Self {
+        return Self::new(ArchiveFormat::Zip, settings);
+    }
+
+    pub(crate) fn tar(settings: &EmbeddedFileSettings) -> Self {
+        return Self::new(ArchiveFormat::Tar, settings);
+    }
+
+    /// Compressed tarballs are extracted, any other compressed file becomes a single entry.
+    pub(crate) fn gzip(settings: &EmbeddedFileSettings) -> Self {
+        return Self::new(ArchiveFormat::Gzip, settings);
+    }
+
+    /// See [`ArchiveConversion::gzip`].
+    pub(crate) fn xz(settings: &EmbeddedFileSettings) -> Self {
+        return Self::new(ArchiveFormat::Xz, settings);
+    }
+
+    fn new(format: ArchiveFormat, settings: &EmbeddedFileSettings) -> Self {
+        return Self {
+            format,
+            max_count: settings.max_count,
+            max_total_size: settings.max_total_size,
+        };
+    }
+}
+
+impl Conversion for ArchiveConversion {
+    fn convert(&self, buf: Vec<u8>) -> Result<ConvertedDocument, Error> {
+        let mut entries = ArchiveEntries {
+            files: Vec::new(),
+            remaining_count: self.max_count,
+            remaining_size: self.max_total_size,
+        };
+
+        match self.format {
+            ArchiveFormat::Zip => read_zip(buf, &mut entries)?,
+            ArchiveFormat::Tar => read_tar(buf.as_slice(), &mut entries),
+            ArchiveFormat::Gzip => {
+                let mut decoder = MultiGzDecoder::new(buf.as_slice());
+                let (decompressed, is_complete) = read_limited(&mut decoder, self.max_total_size)?;
+                let name = decoder
+                    .header()
+                    .and_then(|v| v.filename())
+                    .map(|v| String::from_utf8_lossy(v).into_owned());
+
+                read_decompressed(decompressed, is_complete, name, &mut entries)?;
+            }
+            ArchiveFormat::Xz => {
+                let mut writer = LimitedWriter {
+                    buf: Vec::new(),
+                    limit: self.max_total_size,
+                };
+                let result = lzma_rs::xz_decompress(&mut buf.as_slice(), &mut writer);
+                let is_complete = result.is_ok();
+                if let Err(e) = result {
+                    if (writer.buf.len() as u64) < self.max_total_size {
+                        return Err(anyhow!(
+                            "There was an error while trying to decompress the xz file. {:?}",
+                            e
+                        ));
+                    }
+                }
+
+                read_decompressed(writer.buf, is_complete, None, &mut entries)?;
+            }
+        }
+
+        let contents = entries
+            .files
+            .iter()
+            .map(|v| v.name.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let mut converted_document = ConvertedDocument::new(contents);
+        converted_document.embedded_files = entries.files;
+
+        return Ok(converted_document);
+    }
+}
+
+struct ArchiveEntries {
+    files: Vec<EmbeddedFile>,
+    remaining_count: u64,
+    remaining_size: u64,
+}
+
+impl ArchiveEntries {
+    /// Returns false once a limit is reached, so the remaining entries are skipped.
+    fn push(&mut self, name: String, reader: &mut impl Read) -> Result<bool, io::Error> {
+        if self.remaining_count == 0 {
+            warn!(
+                "Skipping the entry {:?} and all following ones, because the archive contains too many files.",
+                name
+            );
+            return Ok(false);
+        }
+
+        let (contents, is_complete) = read_limited(reader, self.remaining_size)?;
+        if !is_complete {
+            warn!(
+                "Skipping the entry {:?} and all following ones, because the uncompressed size of the archive is too large.",
+                name
+            );
+            return Ok(false);
+        }
+
+        self.remaining_count -= 1;
+        self.remaining_size -= contents.len() as u64;
+        self.files.push(EmbeddedFile { name, contents });
+
+        return Ok(true);
+    }
+}
+
+fn read_zip(buf: Vec<u8>, entries: &mut ArchiveEntries) -> Result<(), Error> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(buf))?;
+
+    for i in 0..archive.len() {
+        let mut file = match archive.by_index(i) {
+            Ok(v) => v,
+            Err(e) => {
+                // E.g. encrypted entries or unsupported compression methods.
+                debug!("Skipping the entry {} of the zip file: {:?}", i, e);
+                continue;
+            }
+        };
+
+        if file.is_dir() {
+            continue;
+        }
+
+        let name = match normalize_entry_name(&file.name().replace('\\', "/")) {
+            Some(v) => v,
+            None => continue,
+        };
+
+        match entries.push(name, &mut file) {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(e) => {
+                warn!(
+                    "There was an error while trying to extract the entry {:?} of the zip file: {:?}",
+                    file.name(),
+                    e
+                );
+            }
+        }
+    }
+
+    return Ok(());
+}
+
+/// A damaged or truncated tar file keeps the entries that were read up to that point.
+fn read_tar(reader: impl Read, entries: &mut ArchiveEntries) {
+    let mut archive = tar::Archive::new(reader);
+    let tar_entries = match archive.entries() {
+        Ok(v) => v,
+        Err(e) => {
+            warn!(
+                "There was an error while trying to read the tar file: {:?}",
+                e
+            );
+            return;
+        }
+    };
+
+    for entry in tar_entries {
+        let mut entry = match entry {
+            Ok(v) => v,
+            Err(e) => {
+                warn!(
+                    "There was an error while trying to read an entry of the tar file: {:?}",
+                    e
+                );
+                return;
+            }
+        };
+
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+
+        let name = match entry
+            .path()
+            .ok()
+            .and_then(|v| normalize_entry_name(&v.to_string_lossy()))
+        {
+            Some(v) => v,
+            None => continue,
+        };
+
+        match entries.push(name, &mut entry) {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(e) => {
+                warn!(
+                    "There was an error while trying to extract an entry of the tar file: {:?}",
+                    e
+                );
+                return;
+            }
+        }
+    }
+}
+
+fn read_decompressed(
+    decompressed: Vec<u8>,
+    is_complete: bool,
+    name: Option<String>,
+    entries: &mut ArchiveEntries,
+) -> Result<(), Error> {
+    if decompressed.get(257..262) == Some(b"ustar") {
+        read_tar(decompressed.as_slice(), entries);
+        return Ok(());
+    }
+
+    if !is_complete {
+        warn!("Skipping the compressed file, because its uncompressed size is too large.");
+        return Ok(());
+    }
+
+    entries.push(
+        name.unwrap_or_else(|| "decompressed".to_string()),
+        &mut decompressed.as_slice(),
+    )?;
+
+    return Ok(());
+}
+
+/// Entry names become part of a path, so absolute paths and names leaving the archive with `..` are rejected.
+fn normalize_entry_name(name: &str) -> Option<String> {
+    let mut components = Vec::new();
+    for component in Path::new(name).components() {
+        match component {
+            Component::Normal(v) => components.push(v.to_string_lossy()),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+
+    if components.is_empty() {
+        return None;
+    }
+
+    return Some(components.join("/"));
+}
+
+/// Returns the read bytes and whether the reader ended before the limit was exceeded.
+fn read_limited(reader: &mut impl Read, limit: u64) -> Result<(Vec<u8>, bool), io::Error> {
+    let mut buf = Vec::new();
+    reader.take(limit.saturating_add(1)).read_to_end(&mut buf)?;
+
+    if buf.len() as u64 > limit {
+        buf.truncate(limit as usize);
+        return Ok((buf, false));
+    }
+
+    return Ok((buf, true));
+}
+
+/// Fails as soon as more than the limit is written, which aborts the decompression.
+struct LimitedWriter {
+    buf: Vec<u8>,
+    limit: u64,
+}
+
+impl Write for LimitedWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let remaining = self.limit.saturating_sub(self.buf.len() as u64);
+        if data.len() as u64 > remaining {
+            self.buf.extend_from_slice(&data[..remaining as usize]);
+            return Err(io::Error::other("The decompressed data exceeds the limit."));
+        }
+
+        self.buf.extend_from_slice(data);
+
+        return Ok(data.len());
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use zip::write::FileOptions;
+
+    fn create_settings(max_count: u64, max_total_size: u64) -> EmbeddedFileSettings {
+        return EmbeddedFileSettings {
+            max_count,
+            max_total_size,
+            ..EmbeddedFileSettings::default()
+        };
+    }
+
+    fn create_zip(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .add_directory("docs/", FileOptions::default())
+            .unwrap();
+        for (name, contents) in entries {
+            writer.start_file(*name, FileOptions::default()).unwrap();
+            writer.write_all(contents).unwrap();
+        }
+
+        return writer.finish().unwrap().into_inner();
+    }
+
+    #[test]
+    fn test_if_zip_entries_are_extracted_within_the_limits() {
+        let zip = create_zip(&[
+            ("docs/report.txt", b"Quarterly report"),
+            ("../outside.txt", b"Outside"),
+            ("notes.txt", b"Notes"),
+        ]);
+
+        let result = ArchiveConversion::zip(&create_settings(10, 1024))
+            .convert(zip.clone())
+            .unwrap();
+
+        assert_eq!(result.contents, "docs/report.txt\nnotes.txt");
+        assert_eq!(result.embedded_files[0].contents, b"Quarterly report");
+
+        let result = ArchiveConversion::zip(&create_settings(1, 1024))
+            .convert(zip.clone())
+            .unwrap();
+        assert_eq!(result.contents, "docs/report.txt");
+
+        let result = ArchiveConversion::zip(&create_settings(10, 20))
+            .convert(zip)
+            .unwrap();
+        assert_eq!(result.contents, "docs/report.txt");
+    }
+
+    #[test]
+    fn test_if_compressed_tarball_is_extracted() {
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_ustar();
+        header.set_size(5);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "backup/notes.txt", &b"Notes"[..])
+            .unwrap();
+        let tar = builder.into_inner().unwrap();
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&tar).unwrap();
+        let tar_gz = encoder.finish().unwrap();
+
+        let result = ArchiveConversion::gzip(&create_settings(10, 1024 * 1024))
+            .convert(tar_gz)
+            .unwrap();
+
+        assert_eq!(
+            result.embedded_files,
+            vec![EmbeddedFile {
+                name: "backup/notes.txt".to_string(),
+                contents: b"Notes".to_vec(),
+            }]
+        );
+    }
+}
@@ -1,5 +1,8 @@
-use crate::conversion::Conversion;
+use crate::conversion::{Conversion, ConvertedDocument};
 use anyhow::Error;
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+use log::debug;
 
 pub(crate) struct ClearTextConversion;
 
@@ -10,9 +13,123 @@ impl ClearTextConversion {
 }
 
 impl Conversion for ClearTextConversion {
-    fn convert(&self, buf: Vec<u8>) -> Result<String, Error> {
-        let contents = String::from_utf8(buf)?; // TODO: Handle non UTF-8 Files
+    fn convert(&self, buf: Vec<u8>) -> Result<ConvertedDocument, Error> {
+        let (contents, encoding) = decode_text(&buf);
 
-        return Ok(contents);
+        return Ok(
+            ConvertedDocument::new(contents).with_metadata("encoding", encoding.name().to_string())
+        );
+    }
+}
+
+/// Decodes text of an unknown encoding to UTF-8. Invalid sequences are replaced instead of failing.
+pub(crate) fn decode_text(buf: &[u8]) -> (String, &'static Encoding) {
+    let (encoding, bom_length) = detect_encoding(buf);
+
+    let (contents, had_errors) = encoding.decode_without_bom_handling(&buf[bom_length..]);
+    if had_errors {
+        debug!(
+            "The text contained invalid sequences for the encoding {}, they were replaced.",
+            encoding.name()
+        );
+    }
+
+    return (contents.into_owned(), encoding);
+}
+
+/// Returns the encoding of the text and the length of its byte order mark.
+pub(crate) fn detect_encoding(buf: &[u8]) -> (&'static Encoding, usize) {
+    if let Some(v) = Encoding::for_bom(buf) {
+        return v;
+    }
+
+    if let Some(v) = detect_utf16_without_bom(buf) {
+        return (v, 0);
+    }
+
+    if std::str::from_utf8(buf).is_ok() {
+        return (UTF_8, 0);
+    }
+
+    let mut detector = EncodingDetector::new();
+    detector.feed(buf, true);
+
+    return (detector.guess(None, true), 0);
+}
+
+/// UTF-16 text without a BOM is recognized by the zero bytes of ASCII characters, which the statistical detector doesn't do.
+fn detect_utf16_without_bom(buf: &[u8]) -> Option<&'static Encoding> {
+    let sample = &buf[..buf.len().min(4096) & !1];
+    if sample.is_empty() {
+        return None;
+    }
+
+    let pair_count = sample.len() / 2;
+    let zeros_at_even = sample.iter().step_by(2).filter(|&&b| b == 0).count();
+    let zeros_at_odd = sample
+        .iter()
+        .skip(1)
+        .step_by(2)
+        .filter(|&&b| b == 0)
+        .count();
+
+    if zeros_at_odd > pair_count * 2 / 5 && zeros_at_even < pair_count / 20 {
+        return Some(UTF_16LE);
+    }
+    if zeros_at_even > pair_count * 2 / 5 && zeros_at_odd < pair_count / 20 {
+        return Some(UTF_16BE);
+    }
+
+    return None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_if_utf16_with_bom_is_decoded() {
+        let mut buf = vec![0xFF, 0xFE];
+        buf.extend(
+            "Windows Registry Editor"
+                .encode_utf16()
+                .flat_map(|c| c.to_le_bytes()),
+        );
+
+        let result = ClearTextConversion::new().convert(buf).unwrap();
+
+        assert_eq!(result.contents, "Windows Registry Editor");
+        assert_eq!(
+            result.metadata,
+            vec![("encoding".to_string(), "UTF-16LE".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_if_utf16_without_bom_is_decoded() {
+        let buf = "2024-01-01 service started"
+            .encode_utf16()
+            .flat_map(|c| c.to_le_bytes())
+            .collect::<Vec<_>>();
+
+        let (contents, encoding) = decode_text(&buf);
+
+        assert_eq!(contents, "2024-01-01 service started");
+        assert_eq!(encoding, UTF_16LE);
+    }
+
+    #[test]
+    fn test_if_windows_1252_umlauts_are_decoded() {
+        let buf =
+            b"Sch\xf6ne Gr\xfc\xdfe aus M\xfcnchen, die H\xe4user sind gr\xf6\xdfer als gedacht."
+                .to_vec();
+
+        let (contents, encoding) = decode_text(&buf);
+
+        assert_eq!(
+            contents,
+            "Schöne Grüße aus München, die Häuser sind größer als gedacht."
+        );
+        assert_eq!(encoding, encoding_rs::WINDOWS_1252);
     }
 }
@@ -0,0 +1,315 @@
+use crate::conversion::archive_conversion::ArchiveConversion;
+use crate::conversion::clear_text_conversion::ClearTextConversion;
+use crate::conversion::convert_to_clear_text_strategy::MimeType;
+use crate::conversion::epub_conversion::EpubConversion;
+use crate::conversion::image_conversion::ImageConversion;
+use crate::conversion::legacy_office::ms_excel_conversion::MsExcelConversion;
+use crate::conversion::legacy_office::ms_powerpoint_conversion::MsPowerpointConversion;
+use crate::conversion::legacy_office::ms_word_conversion::MsWordConversion;
+use crate::conversion::mail_conversion::{MailConversion, MboxConversion};
+use crate::conversion::markup_conversion::MarkupConversion;
+use crate::conversion::media::flac_conversion::FlacConversion;
+use crate::conversion::media::matroska_conversion::MatroskaConversion;
+use crate::conversion::media::mp3_conversion::Mp3Conversion;
+use crate::conversion::media::mp4_conversion::Mp4Conversion;
+use crate::conversion::media::ogg_conversion::OggConversion;
+use crate::conversion::no_op_conversion::NoOpConversion;
+use crate::conversion::notebook_conversion::NotebookConversion;
+use crate::conversion::pdf_conversion::PdfConversion;
+use crate::conversion::rtf_conversion::RtfConversion;
+use crate::conversion::sandbox::sandboxed_conversion::{ConversionWorker, SandboxedConversion};
+use crate::conversion::source_code_conversion::{ProgrammingLanguage, SourceCodeConversion};
+use crate::conversion::structured_data_conversion::StructuredDataConversion;
+use crate::conversion::Conversion;
+use anyhow::anyhow;
+use dscvr_common::config::IndexerSettings;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+const NO_OP_CONVERSION: &str = "no_op";
+const CLEAR_TEXT_CONVERSION: &str = "clear_text";
+
+/// The built-in mapping of mime types to the names of their conversions, which can be overridden in the settings.
+const DEFAULT_MIME_TYPE_CONVERSIONS: &[(&str, &str)] = &[
+    ("text/plain", CLEAR_TEXT_CONVERSION),
+    ("application/pdf", "pdf"),
+    ("application/msword", "ms_word"),
+    ("application/vnd.ms-excel", "ms_excel"),
+    ("application/vnd.ms-powerpoint", "ms_powerpoint"),
+    ("application/rtf", "rtf"),
+    ("text/html", "html"),
+    ("application/xhtml+xml", "html"),
+    ("application/xml", "xml"),
+    ("message/rfc822", "mail"),
+    ("application/mbox", "mbox"),
+    (
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        NO_OP_CONVERSION,
+    ),
+    (
+        "application/vnd.oasis.opendocument.presentation",
+        NO_OP_CONVERSION,
+    ),
+    (
+        "application/vnd.oasis.opendocument.spreadsheet",
+        NO_OP_CONVERSION,
+    ),
+    ("application/vnd.oasis.opendocument.text", NO_OP_CONVERSION),
+    (
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+        NO_OP_CONVERSION,
+    ),
+    (
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        NO_OP_CONVERSION,
+    ),
+    ("image/avif", NO_OP_CONVERSION),
+    ("image/bmp", NO_OP_CONVERSION),
+    ("image/gif", NO_OP_CONVERSION),
+    ("image/vnd.microsoft.icon", NO_OP_CONVERSION),
+    ("image/jpeg", "image"),
+    ("image/png", "image"),
+    ("image/svg+xml", NO_OP_CONVERSION),
+    ("image/tiff", "image"),
+    ("image/webp", "image"),
+    ("application/zip", "zip"),
+    ("application/gzip", "gzip"),
+    ("application/x-tar", "tar"),
+    ("application/x-bzip2", NO_OP_CONVERSION),
+    ("application/x-xz", "xz"),
+    ("application/x-7z-compressed", NO_OP_CONVERSION),
+    ("application/vnd.rar", NO_OP_CONVERSION),
+    ("application/zstd", NO_OP_CONVERSION),
+    ("application/epub+zip", "epub"),
+    ("application/x-ipynb+json", "notebook"),
+    ("text/csv", "csv"),
+    ("text/tab-separated-values", "csv"),
+    ("application/json", "json"),
+    ("application/yaml", "yaml"),
+    ("application/toml", "toml"),
+    ("audio/mpeg", "mp3"),
+    ("audio/flac", "flac"),
+    ("audio/ogg", "ogg"),
+    ("audio/mp4", "mp4"),
+    ("video/mp4", "mp4"),
+    ("audio/x-matroska", "matroska"),
+    ("video/x-matroska", "matroska"),
+    ("video/webm", "matroska"),
+    ("text/x-rust", "rust"),
+    ("text/x-typescript", "typescript"),
+    ("text/javascript", "javascript"),
+    ("text/x-python", "python"),
+    ("text/x-java", "java"),
+    ("text/x-csharp", "csharp"),
+    ("text/x-c", "c"),
+    ("text/x-c++", "cpp"),
+    ("text/x-php", "php"),
+    ("text/x-groovy", "groovy"),
+];
+
+pub(crate) struct RegisteredConversion {
+    pub name: String,
+    pub conversion: Arc<dyn Conversion>,
+}
+
+impl RegisteredConversion {
+    /// Identifies the output of the conversion in the conversion cache, `None` if it isn't worth caching.
+    pub(crate) fn get_cache_key(&self) -> Option<String> {
+        if self.name == NO_OP_CONVERSION {
+            return None;
+        }
+
+        return Some(format!("{}@{}", self.name, self.conversion.get_version()));
+    }
+}
+
+/// Knows which conversion is used for a mime type and whether files of that type are indexed at all.
+pub(crate) struct ConversionRegistry {
+    conversions: HashMap<MimeType, RegisteredConversion>,
+    disabled_mime_types: HashSet<MimeType>,
+    max_file_sizes: HashMap<MimeType, u64>,
+}
+
+impl ConversionRegistry {
+    pub(crate) fn build_with_settings(settings: &IndexerSettings) -> Result<Self, anyhow::Error> {
+        let available_conversions = Self::get_available_conversions(settings);
+        let conversion_worker = if settings.conversion_worker.enabled {
+            Some(Arc::new(ConversionWorker::build_with_settings(
+                &settings.conversion_worker,
+            )))
+        } else {
+            None
+        };
+        let settings = &settings.file_types;
+
+        let mut conversion_names = DEFAULT_MIME_TYPE_CONVERSIONS
+            .iter()
+            .map(|(mime_type, conversion)| (MimeType::from(*mime_type), conversion.to_string()))
+            .collect::<HashMap<_, _>>();
+
+        // Custom text types are read as clear text, unless configured otherwise.
+        for mime_type in settings.extensions.values() {
+            let mime_type = MimeType::from(mime_type.as_str());
+            if mime_type.as_str().starts_with("text/") {
+                conversion_names
+                    .entry(mime_type)
+                    .or_insert(CLEAR_TEXT_CONVERSION.to_string());
+            }
+        }
+
+        let mut disabled_mime_types = HashSet::new();
+        let mut max_file_sizes = HashMap::new();
+        for (mime_type, mime_type_settings) in &settings.mime_types {
+            let mime_type = MimeType::from(mime_type.as_str());
+
+            if let Some(conversion) = &mime_type_settings.conversion {
+                conversion_names.insert(mime_type.clone(), conversion.to_lowercase());
+            }
+            if mime_type_settings.enabled == Some(false) {
+                disabled_mime_types.insert(mime_type.clone());
+            }
+            if let Some(max_file_size) = mime_type_settings.max_file_size {
+                max_file_sizes.insert(mime_type, max_file_size);
+            }
+        }
+
+        let mut conversions = HashMap::new();
+        for (mime_type, conversion_name) in conversion_names {
+            let conversion = match available_conversions.get(conversion_name.as_str()) {
+                Some(v) => Arc::clone(v),
+                None => {
+                    return Err(anyhow!(
+                        "The conversion {:?} configured for the mime type {:?} doesn't exist. Available conversions are {:?}.",
+                        conversion_name,
+                        mime_type.as_str(),
+                        available_conversions.keys().collect::<Vec<_>>()
+                    ));
+                }
+            };
+
+            let conversion: Arc<dyn Conversion> = match &conversion_worker {
+                Some(worker) if conversion_name != NO_OP_CONVERSION => {
+                    Arc::new(SandboxedConversion::new(
+                        &conversion_name,
+                        conversion.get_version(),
+                        Arc::clone(worker),
+                    ))
+                }
+                _ => conversion,
+            };
+
+            conversions.insert(
+                mime_type,
+                RegisteredConversion {
+                    name: conversion_name,
+                    conversion,
+                },
+            );
+        }
+
+        return Ok(Self {
+            conversions,
+            disabled_mime_types,
+            max_file_sizes,
+        });
+    }
+
+    pub(crate) fn get_available_conversions(
+        settings: &IndexerSettings,
+    ) -> HashMap<&'static str, Arc<dyn Conversion>> {
+        let mut map = HashMap::<&'static str, Arc<dyn Conversion>>::new();
+        map.insert(NO_OP_CONVERSION, Arc::new(NoOpConversion {}));
+        map.insert(CLEAR_TEXT_CONVERSION, Arc::new(ClearTextConversion::new()));
+        map.insert("pdf", Arc::new(PdfConversion::new()));
+        map.insert("ms_word", Arc::new(MsWordConversion::new()));
+        map.insert("ms_excel", Arc::new(MsExcelConversion::new()));
+        map.insert("ms_powerpoint", Arc::new(MsPowerpointConversion::new()));
+        map.insert("rtf", Arc::new(RtfConversion::new()));
+        map.insert("html", Arc::new(MarkupConversion::html()));
+        map.insert("xml", Arc::new(MarkupConversion::xml()));
+        map.insert("image", Arc::new(ImageConversion::new()));
+        map.insert("mail", Arc::new(MailConversion::new()));
+        map.insert("mbox", Arc::new(MboxConversion::new()));
+        map.insert("epub", Arc::new(EpubConversion::new()));
+        map.insert("notebook", Arc::new(NotebookConversion::new()));
+        map.insert("csv", Arc::new(StructuredDataConversion::csv()));
+        map.insert("json", Arc::new(StructuredDataConversion::json()));
+        map.insert("yaml", Arc::new(StructuredDataConversion::yaml()));
+        map.insert("toml", Arc::new(StructuredDataConversion::toml()));
+        map.insert("mp3", Arc::new(Mp3Conversion::new()));
+        map.insert("flac", Arc::new(FlacConversion::new()));
+        map.insert("ogg", Arc::new(OggConversion::new()));
+        map.insert("mp4", Arc::new(Mp4Conversion::new()));
+        map.insert("matroska", Arc::new(MatroskaConversion::new()));
+        map.insert(
+            "rust",
+            Arc::new(SourceCodeConversion::new(ProgrammingLanguage::Rust)),
+        );
+        map.insert(
+            "typescript",
+            Arc::new(SourceCodeConversion::new(ProgrammingLanguage::TypeScript)),
+        );
+        map.insert(
+            "javascript",
+            Arc::new(SourceCodeConversion::new(ProgrammingLanguage::JavaScript)),
+        );
+        map.insert(
+            "python",
+            Arc::new(SourceCodeConversion::new(ProgrammingLanguage::Python)),
+        );
+        map.insert(
+            "java",
+            Arc::new(SourceCodeConversion::new(ProgrammingLanguage::Java)),
+        );
+        map.insert(
+            "csharp",
+            Arc::new(SourceCodeConversion::new(ProgrammingLanguage::CSharp)),
+        );
+        map.insert(
+            "c",
+            Arc::new(SourceCodeConversion::new(ProgrammingLanguage::C)),
+        );
+        map.insert(
+            "cpp",
+            Arc::new(SourceCodeConversion::new(ProgrammingLanguage::Cpp)),
+        );
+        map.insert(
+            "php",
+            Arc::new(SourceCodeConversion::new(ProgrammingLanguage::Php)),
+        );
+        map.insert(
+            "groovy",
+            Arc::new(SourceCodeConversion::new(ProgrammingLanguage::Groovy)),
+        );
+        map.insert(
+            "zip",
+            Arc::new(ArchiveConversion::zip(&settings.embedded_files)),
+        );
+        map.insert(
+            "tar",
+            Arc::new(ArchiveConversion::tar(&settings.embedded_files)),
+        );
+        map.insert(
+            "gzip",
+            Arc::new(ArchiveConversion::gzip(&settings.embedded_files)),
+        );
+        map.insert(
+            "xz",
+            Arc::new(ArchiveConversion::xz(&settings.embedded_files)),
+        );
+
+        return map;
+    }
+
+    pub(crate) fn get(&self, mime_type: &MimeType) -> Option<&RegisteredConversion> {
+        return self.conversions.get(mime_type);
+    }
+
+    pub(crate) fn is_enabled(&self, mime_type: &MimeType) -> bool {
+        return !self.disabled_mime_types.contains(mime_type);
+    }
+
+    pub(crate) fn get_max_file_size(&self, mime_type: &MimeType) -> Option<u64> {
+        return self.max_file_sizes.get(mime_type).copied();
+    }
+}
@@ -21,4 +21,206 @@ pub(crate) enum MimeType {
     ImageTiff,
     ImageWebp,
     TextPlain,
+    TextHtml,
+    ApplicationXml,
+    ApplicationXhtmlXml,
+    MessageRfc822,
+    ApplicationMbox,
+    ApplicationZip,
+    ApplicationGzip,
+    ApplicationXTar,
+    ApplicationXBzip2,
+    ApplicationXXz,
+    ApplicationX7zCompressed,
+    ApplicationVndRar,
+    ApplicationZstd,
+    ApplicationEpubZip,
+    ApplicationXIpynbJson,
+    TextCsv,
+    TextTabSeparatedValues,
+    ApplicationJson,
+    ApplicationYaml,
+    ApplicationToml,
+    AudioMpeg,
+    AudioFlac,
+    AudioOgg,
+    AudioMp4,
+    AudioXMatroska,
+    VideoMp4,
+    VideoXMatroska,
+    VideoWebm,
+    TextXRust,
+    TextXTypescript,
+    TextJavascript,
+    TextXPython,
+    TextXJava,
+    TextXCsharp,
+    TextXC,
+    TextXCpp,
+    TextXPhp,
+    TextXGroovy,
+    /// Any other type that was configured in the settings, e.g. `text/x-vue`.
+    Custom(String),
+}
+
+impl MimeType {
+    pub(crate) fn as_str(&self) -> &str {
+        return match self {
+            MimeType::ApplicationPdf => "application/pdf",
+            MimeType::ApplicationMsWord => "application/msword",
+            MimeType::ApplicationVndOpenxmlformatsOfficedocumentWordprocessingmlDocument => {
+                "application/vnd.openxmlformats-officedocument.wordprocessingml.document"
+            }
+            MimeType::ApplicationVndOasisOpendocumentPresentation => {
+                "application/vnd.oasis.opendocument.presentation"
+            }
+            MimeType::ApplicationVndOasisOpendocumentSpreadsheet => {
+                "application/vnd.oasis.opendocument.spreadsheet"
+            }
+            MimeType::ApplicationVndOasisOpendocumentText => {
+                "application/vnd.oasis.opendocument.text"
+            }
+            MimeType::ApplicationVndMsPowerpoint => "application/vnd.ms-powerpoint",
+            MimeType::ApplicationVndOpenxmlformatsOfficedocumentPresentationmlPresentation => {
+                "application/vnd.openxmlformats-officedocument.presentationml.presentation"
+            }
+            MimeType::ApplicationRtf => "application/rtf",
+            MimeType::ApplicationVndMsExcel => "application/vnd.ms-excel",
+            MimeType::ApplicationVndOpenxmlformatsOfficedocumentSpreadsheetmlSheet => {
+                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"
+            }
+            MimeType::ImageAvif => "image/avif",
+            MimeType::ImageBmp => "image/bmp",
+            MimeType::ImageGif => "image/gif",
+            MimeType::ImageIcon => "image/vnd.microsoft.icon",
+            MimeType::ImageJpeg => "image/jpeg",
+            MimeType::ImagePng => "image/png",
+            MimeType::ImageSvgXml => "image/svg+xml",
+            MimeType::ImageTiff => "image/tiff",
+            MimeType::ImageWebp => "image/webp",
+            MimeType::TextPlain => "text/plain",
+            MimeType::TextHtml => "text/html",
+            MimeType::ApplicationXml => "application/xml",
+            MimeType::ApplicationXhtmlXml => "application/xhtml+xml",
+            MimeType::MessageRfc822 => "message/rfc822",
+            MimeType::ApplicationMbox => "application/mbox",
+            MimeType::ApplicationZip => "application/zip",
+            MimeType::ApplicationGzip => "application/gzip",
+            MimeType::ApplicationXTar => "application/x-tar",
+            MimeType::ApplicationXBzip2 => "application/x-bzip2",
+            MimeType::ApplicationXXz => "application/x-xz",
+            MimeType::ApplicationX7zCompressed => "application/x-7z-compressed",
+            MimeType::ApplicationVndRar => "application/vnd.rar",
+            MimeType::ApplicationZstd => "application/zstd",
+            MimeType::ApplicationEpubZip => "application/epub+zip",
+            MimeType::ApplicationXIpynbJson => "application/x-ipynb+json",
+            MimeType::TextCsv => "text/csv",
+            MimeType::TextTabSeparatedValues => "text/tab-separated-values",
+            MimeType::ApplicationJson => "application/json",
+            MimeType::ApplicationYaml => "application/yaml",
+            MimeType::ApplicationToml => "application/toml",
+            MimeType::AudioMpeg => "audio/mpeg",
+            MimeType::AudioFlac => "audio/flac",
+            MimeType::AudioOgg => "audio/ogg",
+            MimeType::AudioMp4 => "audio/mp4",
+            MimeType::AudioXMatroska => "audio/x-matroska",
+            MimeType::VideoMp4 => "video/mp4",
+            MimeType::VideoXMatroska => "video/x-matroska",
+            MimeType::VideoWebm => "video/webm",
+            MimeType::TextXRust => "text/x-rust",
+            MimeType::TextXTypescript => "text/x-typescript",
+            MimeType::TextJavascript => "text/javascript",
+            MimeType::TextXPython => "text/x-python",
+            MimeType::TextXJava => "text/x-java",
+            MimeType::TextXCsharp => "text/x-csharp",
+            MimeType::TextXC => "text/x-c",
+            MimeType::TextXCpp => "text/x-c++",
+            MimeType::TextXPhp => "text/x-php",
+            MimeType::TextXGroovy => "text/x-groovy",
+            MimeType::Custom(v) => v,
+        };
+    }
+}
+
+impl From<&str> for MimeType {
+    fn from(value: &str) -> Self {
+        let value = value.trim().to_lowercase();
+
+        return match value.as_str() {
+            "application/pdf" => MimeType::ApplicationPdf,
+            "application/msword" => MimeType::ApplicationMsWord,
+            "application/vnd.openxmlformats-officedocument.wordprocessingml.document" => {
+                MimeType::ApplicationVndOpenxmlformatsOfficedocumentWordprocessingmlDocument
+            }
+            "application/vnd.oasis.opendocument.presentation" => {
+                MimeType::ApplicationVndOasisOpendocumentPresentation
+            }
+            "application/vnd.oasis.opendocument.spreadsheet" => {
+                MimeType::ApplicationVndOasisOpendocumentSpreadsheet
+            }
+            "application/vnd.oasis.opendocument.text" => {
+                MimeType::ApplicationVndOasisOpendocumentText
+            }
+            "application/vnd.ms-powerpoint" => MimeType::ApplicationVndMsPowerpoint,
+            "application/vnd.openxmlformats-officedocument.presentationml.presentation" => {
+                MimeType::ApplicationVndOpenxmlformatsOfficedocumentPresentationmlPresentation
+            }
+            "application/rtf" => MimeType::ApplicationRtf,
+            "application/vnd.ms-excel" => MimeType::ApplicationVndMsExcel,
+            "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet" => {
+                MimeType::ApplicationVndOpenxmlformatsOfficedocumentSpreadsheetmlSheet
+            }
+            "image/avif" => MimeType::ImageAvif,
+            "image/bmp" => MimeType::ImageBmp,
+            "image/gif" => MimeType::ImageGif,
+            "image/vnd.microsoft.icon" => MimeType::ImageIcon,
+            "image/jpeg" => MimeType::ImageJpeg,
+            "image/png" => MimeType::ImagePng,
+            "image/svg+xml" => MimeType::ImageSvgXml,
+            "image/tiff" => MimeType::ImageTiff,
+            "image/webp" => MimeType::ImageWebp,
+            "text/plain" => MimeType::TextPlain,
+            "text/html" => MimeType::TextHtml,
+            "application/xml" | "text/xml" => MimeType::ApplicationXml,
+            "application/xhtml+xml" => MimeType::ApplicationXhtmlXml,
+            "message/rfc822" => MimeType::MessageRfc822,
+            "application/mbox" => MimeType::ApplicationMbox,
+            "application/zip" => MimeType::ApplicationZip,
+            "application/gzip" => MimeType::ApplicationGzip,
+            "application/x-tar" => MimeType::ApplicationXTar,
+            "application/x-bzip2" => MimeType::ApplicationXBzip2,
+            "application/x-xz" => MimeType::ApplicationXXz,
+            "application/x-7z-compressed" => MimeType::ApplicationX7zCompressed,
+            "application/vnd.rar" => MimeType::ApplicationVndRar,
+            "application/zstd" => MimeType::ApplicationZstd,
+            "application/epub+zip" => MimeType::ApplicationEpubZip,
+            "application/x-ipynb+json" => MimeType::ApplicationXIpynbJson,
+            "text/csv" => MimeType::TextCsv,
+            "text/tab-separated-values" => MimeType::TextTabSeparatedValues,
+            "application/json" => MimeType::ApplicationJson,
+            "application/yaml" | "application/x-yaml" | "text/yaml" => MimeType::ApplicationYaml,
+            "application/toml" => MimeType::ApplicationToml,
+            "audio/mpeg" | "audio/mp3" => MimeType::AudioMpeg,
+            "audio/flac" | "audio/x-flac" => MimeType::AudioFlac,
+            "audio/ogg" | "audio/opus" => MimeType::AudioOgg,
+            "audio/mp4" | "audio/x-m4a" => MimeType::AudioMp4,
+            "audio/x-matroska" => MimeType::AudioXMatroska,
+            "video/mp4" => MimeType::VideoMp4,
+            "video/x-matroska" => MimeType::VideoXMatroska,
+            "video/webm" => MimeType::VideoWebm,
+            "text/x-rust" => MimeType::TextXRust,
+            "text/x-typescript" | "application/typescript" => MimeType::TextXTypescript,
+            "text/javascript" | "application/javascript" | "application/x-javascript" => {
+                MimeType::TextJavascript
+            }
+            "text/x-python" | "text/x-script.python" => MimeType::TextXPython,
+            "text/x-java" | "text/x-java-source" => MimeType::TextXJava,
+            "text/x-csharp" => MimeType::TextXCsharp,
+            "text/x-c" | "text/x-csrc" | "text/x-chdr" => MimeType::TextXC,
+            "text/x-c++" | "text/x-c++src" | "text/x-c++hdr" => MimeType::TextXCpp,
+            "text/x-php" | "application/x-php" => MimeType::TextXPhp,
+            "text/x-groovy" => MimeType::TextXGroovy,
+            _ => MimeType::Custom(value),
+        };
+    }
 }
@@ -1,6 +1,7 @@
 use crate::conversion::convert_to_clear_text_strategy::MimeType;
 use crate::conversion::determine_file_type::DetermineFileTypeStrategy;
 use crate::file_indexer::ScannedFile;
+use dscvr_common::config::FileTypeSettings;
 use std::collections::HashMap;
 use std::path::Path;
 
@@ -18,7 +19,11 @@ impl DetermineFileTypeByExtension {
 
 impl DetermineFileTypeStrategy for DetermineFileTypeByExtension {
     fn determine(&self, file: &ScannedFile) -> Option<MimeType> {
-        let extension = Path::new(&file.path).extension()?.to_str()?.to_string();
+        return self.determine_embedded(&file.path, &[]);
+    }
+
+    fn determine_embedded(&self, name: &str, _: &[u8]) -> Option<MimeType> {
+        let extension = Path::new(name).extension()?.to_str()?.to_lowercase();
 
         let mime_type = self.file_extension_mime_type_map.get(&extension)?.clone();
 
@@ -26,109 +31,130 @@ impl DetermineFileTypeStrategy for DetermineFileTypeByExtension {
     }
 }
 
+/// The built-in mapping of file extensions to mime types, which can be extended in the settings.
+const DEFAULT_FILE_EXTENSION_MIME_TYPES: &[(&str, &str)] = &[
+    ("txt", "text/plain"),
+    ("xml", "application/xml"),
+    ("json", "application/json"),
+    ("md", "text/plain"),
+    ("ascii", "text/plain"),
+    ("env", "text/plain"),
+    ("gitignore", "text/plain"),
+    ("taurignore", "text/plain"),
+    ("lock", "text/plain"),
+    ("toml", "application/toml"),
+    ("yaml", "application/yaml"),
+    ("yml", "application/yaml"),
+    ("rs", "text/x-rust"),
+    ("js", "text/javascript"),
+    ("ts", "text/x-typescript"),
+    ("c", "text/x-c"),
+    ("cpp", "text/x-c++"),
+    ("cs", "text/x-csharp"),
+    ("php", "text/x-php"),
+    ("html", "text/html"),
+    ("htm", "text/html"),
+    ("xhtml", "application/xhtml+xml"),
+    ("eml", "message/rfc822"),
+    ("mbox", "application/mbox"),
+    ("css", "text/plain"),
+    ("csv", "text/csv"),
+    ("tsv", "text/tab-separated-values"),
+    ("iml", "text/plain"),
+    ("cmd", "text/plain"),
+    ("ps1", "text/plain"),
+    ("sh", "text/plain"),
+    ("bash", "text/plain"),
+    ("fish", "text/plain"),
+    ("java", "text/x-java"),
+    ("bat", "text/plain"),
+    ("py", "text/x-python"),
+    ("tsx", "text/x-typescript"),
+    ("jsx", "text/javascript"),
+    ("gradle", "text/x-groovy"),
+    ("properties", "text/plain"),
+    ("groovy", "text/x-groovy"),
+    ("mjs", "text/javascript"),
+    ("cjs", "text/javascript"),
+    ("mts", "text/x-typescript"),
+    ("pyw", "text/x-python"),
+    ("h", "text/x-c"),
+    ("cc", "text/x-c++"),
+    ("cxx", "text/x-c++"),
+    ("hpp", "text/x-c++"),
+    ("hh", "text/x-c++"),
+    ("avif", "image/avif"),
+    ("bmp", "image/bmp"),
+    ("doc", "application/msword"),
+    (
+        "docx",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+    ),
+    ("gif", "image/gif"),
+    ("ico", "image/vnd.microsoft.icon"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("odp", "application/vnd.oasis.opendocument.presentation"),
+    ("ods", "application/vnd.oasis.opendocument.spreadsheet"),
+    ("odt", "application/vnd.oasis.opendocument.text"),
+    ("png", "image/png"),
+    ("pdf", "application/pdf"),
+    ("ppt", "application/vnd.ms-powerpoint"),
+    (
+        "pptx",
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+    ),
+    ("rtf", "application/rtf"),
+    ("svg", "image/svg+xml"),
+    ("tif", "image/tiff"),
+    ("tiff", "image/tiff"),
+    ("xls", "application/vnd.ms-excel"),
+    (
+        "xlsx",
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    ),
+    ("zip", "application/zip"),
+    ("gz", "application/gzip"),
+    ("tgz", "application/gzip"),
+    ("tar", "application/x-tar"),
+    ("bz2", "application/x-bzip2"),
+    ("xz", "application/x-xz"),
+    ("7z", "application/x-7z-compressed"),
+    ("rar", "application/vnd.rar"),
+    ("zst", "application/zstd"),
+    ("epub", "application/epub+zip"),
+    ("ipynb", "application/x-ipynb+json"),
+    ("mp3", "audio/mpeg"),
+    ("flac", "audio/flac"),
+    ("ogg", "audio/ogg"),
+    ("oga", "audio/ogg"),
+    ("opus", "audio/ogg"),
+    ("m4a", "audio/mp4"),
+    ("m4b", "audio/mp4"),
+    ("mp4", "video/mp4"),
+    ("m4v", "video/mp4"),
+    ("mka", "audio/x-matroska"),
+    ("mkv", "video/x-matroska"),
+    ("webm", "video/webm"),
+];
+
 pub(crate) struct DetermineFileTypeByExtensionFactory;
 
 impl DetermineFileTypeByExtensionFactory {
-    pub fn create() -> DetermineFileTypeByExtension {
-        return DetermineFileTypeByExtension::new(Self::get_file_type_mime_type_map());
+    pub fn create_with_settings(settings: &FileTypeSettings) -> DetermineFileTypeByExtension {
+        return DetermineFileTypeByExtension::new(Self::get_file_type_mime_type_map(settings));
     }
 
-    pub fn get_file_type_mime_type_map() -> HashMap<String, MimeType> {
-        let mut map = HashMap::new();
-        // TODO: Find a better way to add even more types
-        map.insert("txt".to_owned(), MimeType::TextPlain);
-        map.insert("xml".to_owned(), MimeType::TextPlain);
-        map.insert("json".to_owned(), MimeType::TextPlain);
-        map.insert("md".to_owned(), MimeType::TextPlain);
-        map.insert("ascii".to_owned(), MimeType::TextPlain);
-        map.insert("env".to_owned(), MimeType::TextPlain);
-        map.insert("gitignore".to_owned(), MimeType::TextPlain);
-        map.insert("taurignore".to_owned(), MimeType::TextPlain);
-        map.insert("lock".to_owned(), MimeType::TextPlain);
-        map.insert("toml".to_owned(), MimeType::TextPlain);
-        map.insert("yaml".to_owned(), MimeType::TextPlain);
-        map.insert("rs".to_owned(), MimeType::TextPlain);
-        map.insert("js".to_owned(), MimeType::TextPlain);
-        map.insert("ts".to_owned(), MimeType::TextPlain);
-        map.insert("c".to_owned(), MimeType::TextPlain);
-        map.insert("cpp".to_owned(), MimeType::TextPlain);
-        map.insert("cs".to_owned(), MimeType::TextPlain);
-        map.insert("php".to_owned(), MimeType::TextPlain);
-        map.insert("html".to_owned(), MimeType::TextPlain);
-        map.insert("css".to_owned(), MimeType::TextPlain);
-        map.insert("csv".to_owned(), MimeType::TextPlain);
-        map.insert("iml".to_owned(), MimeType::TextPlain);
-        map.insert("cmd".to_owned(), MimeType::TextPlain);
-        map.insert("ps1".to_owned(), MimeType::TextPlain);
-        map.insert("sh".to_owned(), MimeType::TextPlain);
-        map.insert("bash".to_owned(), MimeType::TextPlain);
-        map.insert("fish".to_owned(), MimeType::TextPlain);
-        map.insert("java".to_owned(), MimeType::TextPlain);
-        map.insert("bat".to_owned(), MimeType::TextPlain);
-        map.insert("py".to_owned(), MimeType::TextPlain);
-        map.insert("tsx".to_owned(), MimeType::TextPlain);
-        map.insert("jsx".to_owned(), MimeType::TextPlain);
-        map.insert("gradle".to_owned(), MimeType::TextPlain);
-        map.insert("properties".to_owned(), MimeType::TextPlain);
-        map.insert("groovy".to_owned(), MimeType::TextPlain);
-
-        map.insert("avif".to_string(), MimeType::ImageAvif);
-
-        map.insert("bmp".to_string(), MimeType::ImageBmp);
-
-        map.insert("doc".to_string(), MimeType::ApplicationMsWord);
-
-        map.insert(
-            "docx".to_string(),
-            MimeType::ApplicationVndOpenxmlformatsOfficedocumentWordprocessingmlDocument,
-        );
-
-        map.insert("gif".to_string(), MimeType::ImageGif);
-
-        map.insert("ico".to_string(), MimeType::ImageIcon);
-
-        map.insert("jpg".to_string(), MimeType::ImageJpeg);
-        map.insert("jpeg".to_string(), MimeType::ImageJpeg);
-
-        map.insert(
-            "odp".to_string(),
-            MimeType::ApplicationVndOasisOpendocumentPresentation,
-        );
-
-        map.insert(
-            "ods".to_string(),
-            MimeType::ApplicationVndOasisOpendocumentSpreadsheet,
-        );
-
-        map.insert(
-            "odt".to_string(),
-            MimeType::ApplicationVndOasisOpendocumentText,
-        );
-
-        map.insert("png".to_string(), MimeType::ImagePng);
-
-        map.insert("pdf".to_string(), MimeType::ApplicationPdf);
-
-        map.insert("ppt".to_string(), MimeType::ApplicationVndMsPowerpoint);
-
-        map.insert(
-            "pptx".to_string(),
-            MimeType::ApplicationVndOpenxmlformatsOfficedocumentPresentationmlPresentation,
-        );
-
-        map.insert("rtf".to_string(), MimeType::ApplicationRtf);
-
-        map.insert("svg".to_string(), MimeType::ImageSvgXml);
-
-        map.insert("tif".to_string(), MimeType::ImageTiff);
-        map.insert("tiff".to_string(), MimeType::ImageTiff);
-
-        map.insert("xls".to_string(), MimeType::ApplicationVndMsExcel);
-
-        map.insert(
-            "xlsx".to_string(),
-            MimeType::ApplicationVndOpenxmlformatsOfficedocumentSpreadsheetmlSheet,
-        );
+    pub fn get_file_type_mime_type_map(settings: &FileTypeSettings) -> HashMap<String, MimeType> {
+        let mut map = DEFAULT_FILE_EXTENSION_MIME_TYPES
+            .iter()
+            .map(|(extension, mime_type)| (extension.to_string(), MimeType::from(*mime_type)))
+            .collect::<HashMap<_, _>>();
+
+        for (extension, mime_type) in &settings.extensions {
+            let extension = extension.trim_start_matches('.').to_lowercase();
+            map.insert(extension, MimeType::from(mime_type.as_str()));
+        }
 
         return map;
     }
@@ -143,7 +169,8 @@ mod tests {
         let file = ScannedFile {
             path: "/home/user/documents/important-pdf.pdf".to_string(),
         };
-        let strategy_under_test = DetermineFileTypeByExtensionFactory::create();
+        let strategy_under_test =
+            DetermineFileTypeByExtensionFactory::create_with_settings(&FileTypeSettings::default());
 
         let result = strategy_under_test.determine(&file);
 
@@ -156,10 +183,28 @@ mod tests {
         let file = ScannedFile {
             path: "/home/user/.local/bin/important-script".to_string(),
         };
-        let strategy_under_test = DetermineFileTypeByExtensionFactory::create();
+        let strategy_under_test =
+            DetermineFileTypeByExtensionFactory::create_with_settings(&FileTypeSettings::default());
 
         let result = strategy_under_test.determine(&file);
 
         assert!(result.is_none());
     }
+
+    #[test]
+    fn test_if_configured_extension_overrides_the_default() {
+        let file = ScannedFile {
+            path: "C:\\Users\\user\\projects\\App.VUE".to_string(),
+        };
+        let mut settings = FileTypeSettings::default();
+        settings
+            .extensions
+            .insert(".vue".to_string(), "text/x-vue".to_string());
+        let strategy_under_test =
+            DetermineFileTypeByExtensionFactory::create_with_settings(&settings);
+
+        let result = strategy_under_test.determine(&file);
+
+        assert_eq!(result, Some(MimeType::Custom("text/x-vue".to_string())));
+    }
 }
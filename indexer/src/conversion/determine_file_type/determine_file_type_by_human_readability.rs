@@ -1,36 +1,63 @@
+use crate::conversion::clear_text_conversion::detect_encoding;
 use crate::conversion::convert_to_clear_text_strategy::MimeType;
-use crate::conversion::determine_file_type::DetermineFileTypeStrategy;
+use crate::conversion::determine_file_type::{read_header, DetermineFileTypeStrategy};
 use crate::file_indexer::ScannedFile;
-use memmap2::Mmap;
-use std::fs::File;
+use encoding_rs::{UTF_16BE, UTF_16LE, UTF_8};
 use std::path::Path;
 
+const HEADER_SIZE: usize = 8 * 1024;
+
 pub(crate) struct DetermineFileTypeByHumanReadability;
 
 impl DetermineFileTypeByHumanReadability {
     pub fn new() -> Self {
         return Self {};
     }
-}
 
-impl DetermineFileTypeStrategy for DetermineFileTypeByHumanReadability {
-    fn determine(&self, file: &ScannedFile) -> Option<MimeType> {
-        let path_to_file = Path::new(&file.path);
-        let file = File::open(path_to_file).ok()?; // TODO: logging
+    /// The share of characters that are printable or whitespace.
+    fn readable_ratio(header: &[u8]) -> f32 {
+        let (encoding, bom_length) = detect_encoding(header);
+
+        if encoding == UTF_8 || encoding == UTF_16LE || encoding == UTF_16BE {
+            let (text, _) = encoding.decode_without_bom_handling(&header[bom_length..]);
+            let char_count = text.chars().count();
+            let readable_chars = text
+                .chars()
+                .filter(|&c| !c.is_control() || c.is_whitespace())
+                .filter(|&c| c != char::REPLACEMENT_CHARACTER)
+                .count();
 
-        let memory_file = unsafe { Mmap::map(&file).ok()? }; // TODO: logging
+            return readable_chars as f32 / char_count as f32;
+        }
 
-        let readable_chars = memory_file
+        let readable_chars = header
             .iter()
             .filter(|&&c| c.is_ascii_graphic() || c.is_ascii_whitespace())
             .count();
 
-        if readable_chars as f32 / memory_file.len() as f32 > 0.7 {
+        return readable_chars as f32 / header.len() as f32;
+    }
+}
+
+impl DetermineFileTypeStrategy for DetermineFileTypeByHumanReadability {
+    fn determine(&self, file: &ScannedFile) -> Option<MimeType> {
+        let path_to_file = Path::new(&file.path);
+        let header = read_header(path_to_file, HEADER_SIZE).ok()?; // TODO: logging
+
+        if Self::readable_ratio(&header) > 0.7 {
             return Some(MimeType::TextPlain);
         } else {
             return None;
         }
     }
+
+    fn determine_embedded(&self, _: &str, buf: &[u8]) -> Option<MimeType> {
+        if Self::readable_ratio(&buf[..buf.len().min(HEADER_SIZE)]) > 0.7 {
+            return Some(MimeType::TextPlain);
+        }
+
+        return None;
+    }
 }
 
 pub(crate) struct DetermineFileTypeByHumanReadabilityFactory;
@@ -89,6 +116,20 @@ mod tests {
         assert!(result.is_none());
     }
 
+    #[test]
+    fn test_if_utf16_text_is_readable() {
+        let mut header = vec![0xFF, 0xFE];
+        header.extend(
+            "Windows Registry Editor Version 5.00"
+                .encode_utf16()
+                .flat_map(|c| c.to_le_bytes()),
+        );
+
+        let result = DetermineFileTypeByHumanReadability::readable_ratio(&header);
+
+        assert!(result > 0.7);
+    }
+
     fn get_test_data_directory() -> PathBuf {
         let current_dir_path_buf = current_dir().unwrap();
 
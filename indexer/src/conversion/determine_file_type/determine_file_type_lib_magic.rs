@@ -1,18 +1,528 @@
 use crate::conversion::convert_to_clear_text_strategy::MimeType;
-use crate::conversion::determine_file_type::DetermineFileTypeStrategy;
+use crate::conversion::determine_file_type::{read_header, DetermineFileTypeStrategy};
+use crate::conversion::media::mp3_conversion::is_mpeg_audio_frame;
 use crate::file_indexer::ScannedFile;
+use cfb::CompoundFile;
+use flate2::read::DeflateDecoder;
+use log::debug;
+use std::fs::File;
+use std::io::{self, Cursor, Read, Seek};
+use std::path::Path;
 
+/// Enough to contain the local headers of the first entries of a ZIP file.
+const HEADER_SIZE: usize = 32 * 1024;
+
+const OLE2_SIGNATURE: &[u8] = &[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+const ZIP_LOCAL_FILE_HEADER_SIGNATURE: &[u8] = b"PK\x03\x04";
+const PDF_SIGNATURE: &[u8] = b"%PDF-";
+
+/// PDF readers accept the signature anywhere in the first kilobyte.
+const PDF_SIGNATURE_SEARCH_SIZE: usize = 1024;
+
+/// Determines the type of a file by the magic numbers at its start instead of trusting its name.
 pub(crate) struct DetermineFileTypeLibMagic;
 
 impl DetermineFileTypeLibMagic {
     pub fn new() -> Self {
         return Self {};
     }
+
+    fn determine_compound_file_type<F: Read + Seek>(
+        file: io::Result<F>,
+        name: &str,
+    ) -> Option<MimeType> {
+        let compound_file = match file.and_then(CompoundFile::open) {
+            Ok(v) => v,
+            Err(e) => {
+                debug!(
+                    "There was an error while trying to read the compound file {:?}: {:?}",
+                    name, e
+                );
+                return None;
+            }
+        };
+
+        if compound_file.is_stream("/WordDocument") {
+            return Some(MimeType::ApplicationMsWord);
+        }
+        if compound_file.is_stream("/Workbook") || compound_file.is_stream("/Book") {
+            return Some(MimeType::ApplicationVndMsExcel);
+        }
+        if compound_file.is_stream("/PowerPoint Document") {
+            return Some(MimeType::ApplicationVndMsPowerpoint);
+        }
+
+        return None;
+    }
 }
 
 impl DetermineFileTypeStrategy for DetermineFileTypeLibMagic {
-    fn determine(&self, _file: &ScannedFile) -> Option<MimeType> {
-        // todo!()
+    fn determine(&self, file: &ScannedFile) -> Option<MimeType> {
+        let path = Path::new(&file.path);
+        let header = match read_header(path, HEADER_SIZE) {
+            Ok(v) => v,
+            Err(e) => {
+                debug!(
+                    "There was an error while trying to read the header of the file at path {:?}: {:?}",
+                    path, e
+                );
+                return None;
+            }
+        };
+
+        if header.starts_with(OLE2_SIGNATURE) {
+            return Self::determine_compound_file_type(File::open(path), &file.path);
+        }
+
+        return determine_by_magic_number(&header);
+    }
+
+    fn determine_embedded(&self, name: &str, buf: &[u8]) -> Option<MimeType> {
+        if buf.starts_with(OLE2_SIGNATURE) {
+            return Self::determine_compound_file_type(Ok(Cursor::new(buf)), name);
+        }
+
+        return determine_by_magic_number(&buf[..buf.len().min(HEADER_SIZE)]);
+    }
+}
+
+/// Finds the PDF signature after other data at the start of a file, e.g. a mail header that was saved with it. Other
+/// files, e.g. text files about PDFs, contain the signature as well, so this runs after the file extension was checked.
+pub(crate) struct DetermineFileTypeByLooseSignature;
+
+impl DetermineFileTypeByLooseSignature {
+    pub fn new() -> Self {
+        return Self {};
+    }
+}
+
+impl DetermineFileTypeStrategy for DetermineFileTypeByLooseSignature {
+    fn determine(&self, file: &ScannedFile) -> Option<MimeType> {
+        let path = Path::new(&file.path);
+        let header = match read_header(path, PDF_SIGNATURE_SEARCH_SIZE) {
+            Ok(v) => v,
+            Err(e) => {
+                debug!(
+                    "There was an error while trying to read the header of the file at path {:?}: {:?}",
+                    path, e
+                );
+                return None;
+            }
+        };
+
+        return determine_by_loose_signature(&header);
+    }
+
+    fn determine_embedded(&self, _name: &str, buf: &[u8]) -> Option<MimeType> {
+        return determine_by_loose_signature(buf);
+    }
+}
+
+fn determine_by_loose_signature(header: &[u8]) -> Option<MimeType> {
+    if find(
+        &header[..header.len().min(PDF_SIGNATURE_SEARCH_SIZE)],
+        PDF_SIGNATURE,
+    )
+    .is_some()
+    {
+        return Some(MimeType::ApplicationPdf);
+    }
+
+    return None;
+}
+
+pub(crate) fn determine_by_magic_number(header: &[u8]) -> Option<MimeType> {
+    if skip_whitespace(header).starts_with(PDF_SIGNATURE) {
+        return Some(MimeType::ApplicationPdf);
+    }
+
+    if header.starts_with(ZIP_LOCAL_FILE_HEADER_SIGNATURE) {
+        return Some(determine_zip_file_type(header));
+    }
+
+    let mime_type = if header.starts_with(b"\xFF\xD8\xFF") {
+        MimeType::ImageJpeg
+    } else if header.starts_with(b"\x89PNG\r\n\x1A\n") {
+        MimeType::ImagePng
+    } else if header.starts_with(b"GIF87a") || header.starts_with(b"GIF89a") {
+        MimeType::ImageGif
+    } else if header.starts_with(b"II*\x00") || header.starts_with(b"MM\x00*") {
+        MimeType::ImageTiff
+    } else if header.starts_with(b"RIFF") && header.get(8..12) == Some(b"WEBP") {
+        MimeType::ImageWebp
+    } else if header.get(4..8) == Some(b"ftyp")
+        && matches!(header.get(8..12), Some(b"avif") | Some(b"avis"))
+    {
+        MimeType::ImageAvif
+    } else if header.get(4..8) == Some(b"ftyp") {
+        determine_mp4_file_type(header)
+    } else if header.starts_with(b"fLaC") {
+        MimeType::AudioFlac
+    } else if header.starts_with(b"OggS") {
+        MimeType::AudioOgg
+    } else if header.starts_with(b"\x1A\x45\xDF\xA3") {
+        determine_matroska_file_type(header)
+    } else if header.starts_with(b"ID3")
+        // The byte order mark of UTF-16 text looks like a frame header of MPEG-1 layer I.
+        || (is_mpeg_audio_frame(header) && !header.starts_with(b"\xFF\xFE"))
+    {
+        MimeType::AudioMpeg
+    } else if header.starts_with(b"BM") && header.get(14..15).map_or(false, |v| v[0] >= 12) {
+        MimeType::ImageBmp
+    } else if header.starts_with(b"\x00\x00\x01\x00") {
+        MimeType::ImageIcon
+    } else if header.starts_with(b"{\\rtf") {
+        MimeType::ApplicationRtf
+    } else if is_mbox(header) {
+        MimeType::ApplicationMbox
+    } else if header.starts_with(b"#!") {
+        determine_script_file_type(header)
+    } else if header.starts_with(b"\x1F\x8B") {
+        MimeType::ApplicationGzip
+    } else if header.starts_with(b"\xFD7zXZ\x00") {
+        MimeType::ApplicationXXz
+    } else if header.starts_with(b"BZh") {
+        MimeType::ApplicationXBzip2
+    } else if header.starts_with(b"7z\xBC\xAF\x27\x1C") {
+        MimeType::ApplicationX7zCompressed
+    } else if header.starts_with(b"Rar!\x1A\x07") {
+        MimeType::ApplicationVndRar
+    } else if header.starts_with(b"\x28\xB5\x2F\xFD") {
+        MimeType::ApplicationZstd
+    } else if header.get(257..262) == Some(b"ustar") {
+        MimeType::ApplicationXTar
+    } else if is_svg(header) {
+        MimeType::ImageSvgXml
+    } else if is_html(header) {
+        MimeType::TextHtml
+    } else if skip_whitespace(header).starts_with(b"<?xml") {
+        MimeType::ApplicationXml
+    } else {
         return None;
+    };
+
+    return Some(mime_type);
+}
+
+/// The major brand of MP4 files tells audio books and music apart from videos.
+fn determine_mp4_file_type(header: &[u8]) -> MimeType {
+    return match header.get(8..12) {
+        Some(b"M4A ") | Some(b"M4B ") | Some(b"M4P ") => MimeType::AudioMp4,
+        _ => MimeType::VideoMp4,
+    };
+}
+
+/// Scripts are told apart by the interpreter in their shebang, e.g. `#!/usr/bin/env python3`. Scripts of other
+/// interpreters, e.g. shells, are plain text.
+fn determine_script_file_type(header: &[u8]) -> MimeType {
+    let shebang = header.split(|v| *v == b'\n').next().unwrap_or_default();
+    let shebang = String::from_utf8_lossy(shebang);
+    let interpreter = shebang
+        .split_whitespace()
+        .map(|v| v.rsplit('/').next().unwrap_or(v))
+        .find(|v| *v != "#!" && *v != "env" && !v.starts_with('-'))
+        .unwrap_or_default()
+        .trim_start_matches("#!");
+
+    return match interpreter.trim_end_matches(|v: char| v.is_ascii_digit() || v == '.') {
+        "python" => MimeType::TextXPython,
+        "node" | "deno" => MimeType::TextJavascript,
+        "php" => MimeType::TextXPhp,
+        "groovy" => MimeType::TextXGroovy,
+        _ => MimeType::TextPlain,
+    };
+}
+
+/// WebM files are Matroska files, whose EBML header has the document type `webm`.
+fn determine_matroska_file_type(header: &[u8]) -> MimeType {
+    let ebml_header = &header[..header.len().min(64)];
+    if find(ebml_header, b"webm").is_some() {
+        return MimeType::VideoWebm;
+    }
+
+    return MimeType::VideoXMatroska;
+}
+
+/// Office Open XML and OpenDocument files are ZIP files, which are told apart by the entries at their start.
+fn determine_zip_file_type(header: &[u8]) -> MimeType {
+    let mut entry_names = Vec::new();
+    let mut offset = 0;
+
+    while let Some(position) = find(&header[offset..], ZIP_LOCAL_FILE_HEADER_SIGNATURE) {
+        let entry = &header[offset + position..];
+        offset += position + ZIP_LOCAL_FILE_HEADER_SIGNATURE.len();
+
+        let (compression_method, compressed_size, name, data) = match parse_local_file_header(entry)
+        {
+            Some(v) => v,
+            None => continue,
+        };
+
+        if name == b"mimetype" && compression_method == 0 {
+            let mime_type = &data[..data.len().min(compressed_size)];
+            if let Some(v) = determine_open_document_type(mime_type) {
+                return v;
+            }
+        }
+
+        if name == b"[Content_Types].xml" {
+            let content_types = if compression_method == 8 {
+                inflate_partially(data)
+            } else {
+                data.to_vec()
+            };
+            if let Some(v) = determine_office_open_xml_type(&content_types) {
+                return v;
+            }
+        }
+
+        entry_names.push(name.to_vec());
+    }
+
+    for name in entry_names {
+        if name.starts_with(b"word/") {
+            return MimeType::ApplicationVndOpenxmlformatsOfficedocumentWordprocessingmlDocument;
+        }
+        if name.starts_with(b"xl/") {
+            return MimeType::ApplicationVndOpenxmlformatsOfficedocumentSpreadsheetmlSheet;
+        }
+        if name.starts_with(b"ppt/") {
+            return MimeType::ApplicationVndOpenxmlformatsOfficedocumentPresentationmlPresentation;
+        }
+    }
+
+    return MimeType::ApplicationZip;
+}
+
+/// Returns the compression method, the compressed size, the name and the data following the local file header.
+fn parse_local_file_header(entry: &[u8]) -> Option<(u16, usize, &[u8], &[u8])> {
+    let compression_method = u16::from_le_bytes(entry.get(8..10)?.try_into().ok()?);
+    let compressed_size = u32::from_le_bytes(entry.get(18..22)?.try_into().ok()?) as usize;
+    let name_length = u16::from_le_bytes(entry.get(26..28)?.try_into().ok()?) as usize;
+    let extra_length = u16::from_le_bytes(entry.get(28..30)?.try_into().ok()?) as usize;
+    let name = entry.get(30..30 + name_length)?;
+    let data = entry.get(30 + name_length + extra_length..)?;
+
+    return Some((compression_method, compressed_size, name, data));
+}
+
+fn inflate_partially(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    // The entry may be cut off by the end of the header, so whatever could be inflated is used.
+    let _ = DeflateDecoder::new(data)
+        .take(HEADER_SIZE as u64)
+        .read_to_end(&mut out);
+
+    return out;
+}
+
+fn determine_open_document_type(mime_type: &[u8]) -> Option<MimeType> {
+    let mime_type = match mime_type {
+        b"application/vnd.oasis.opendocument.text" => MimeType::ApplicationVndOasisOpendocumentText,
+        b"application/vnd.oasis.opendocument.spreadsheet" => {
+            MimeType::ApplicationVndOasisOpendocumentSpreadsheet
+        }
+        b"application/vnd.oasis.opendocument.presentation" => {
+            MimeType::ApplicationVndOasisOpendocumentPresentation
+        }
+        // EPUB files start with the same uncompressed mimetype entry.
+        b"application/epub+zip" => MimeType::ApplicationEpubZip,
+        _ => return None,
+    };
+
+    return Some(mime_type);
+}
+
+fn determine_office_open_xml_type(content_types: &[u8]) -> Option<MimeType> {
+    if find(content_types, b"wordprocessingml.document.main+xml").is_some()
+        || find(
+            content_types,
+            b"application/vnd.ms-word.document.macroEnabled.main+xml",
+        )
+        .is_some()
+    {
+        return Some(MimeType::ApplicationVndOpenxmlformatsOfficedocumentWordprocessingmlDocument);
+    }
+    if find(content_types, b"spreadsheetml.sheet.main+xml").is_some()
+        || find(
+            content_types,
+            b"application/vnd.ms-excel.sheet.macroEnabled.main+xml",
+        )
+        .is_some()
+    {
+        return Some(MimeType::ApplicationVndOpenxmlformatsOfficedocumentSpreadsheetmlSheet);
+    }
+    if find(content_types, b"presentationml.presentation.main+xml").is_some()
+        || find(
+            content_types,
+            b"application/vnd.ms-powerpoint.presentation.macroEnabled.main+xml",
+        )
+        .is_some()
+    {
+        return Some(
+            MimeType::ApplicationVndOpenxmlformatsOfficedocumentPresentationmlPresentation,
+        );
+    }
+
+    return None;
+}
+
+fn is_svg(header: &[u8]) -> bool {
+    let start = &header[..header.len().min(1024)];
+    let trimmed = skip_whitespace(start);
+
+    return (trimmed.starts_with(b"<?xml") || trimmed.starts_with(b"<svg"))
+        && find(start, b"<svg").is_some();
+}
+
+fn is_html(header: &[u8]) -> bool {
+    let start = header[..header.len().min(1024)].to_ascii_lowercase();
+    let trimmed = skip_whitespace(&start);
+
+    return trimmed.starts_with(b"<!doctype html")
+        || trimmed.starts_with(b"<html")
+        || (trimmed.starts_with(b"<?xml") && find(&start, b"<html").is_some());
+}
+
+/// Every message of an mbox starts with a `From ` line, followed by the headers of the message.
+fn is_mbox(header: &[u8]) -> bool {
+    if !header.starts_with(b"From ") {
+        return false;
+    }
+
+    let second_line = match find(header, b"\n") {
+        Some(v) => &header[v + 1..],
+        None => return false,
+    };
+    let colon = second_line.iter().take(80).position(|&b| b == b':');
+
+    return colon.map_or(false, |v| {
+        v > 0
+            && second_line[..v]
+                .iter()
+                .all(|b| b.is_ascii_alphanumeric() || *b == b'-')
+    });
+}
+
+/// Skips a UTF-8 byte order mark and leading whitespace.
+fn skip_whitespace(buf: &[u8]) -> &[u8] {
+    let buf = buf.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(buf);
+
+    return buf
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .map_or(&buf[0..0], |v| &buf[v..]);
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    return haystack
+        .windows(needle.len())
+        .position(|window| window == needle);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env::current_dir;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_if_docx_without_content_types_at_start_is_determined_by_entries() {
+        let test_file_path_buf = get_test_data_directory()
+            .join("01")
+            .join("this_is_a_random_google_docs_file.docx");
+
+        let file = ScannedFile {
+            path: test_file_path_buf.to_str().unwrap().to_string(),
+        };
+
+        let result = DetermineFileTypeLibMagic::new().determine(&file);
+
+        assert_eq!(
+            result,
+            Some(MimeType::ApplicationVndOpenxmlformatsOfficedocumentWordprocessingmlDocument)
+        );
+    }
+
+    #[test]
+    fn test_if_text_file_is_not_determined() {
+        let test_file_path_buf = get_test_data_directory()
+            .join("01")
+            .join("this_is_an_important_file_for_testing.txt");
+
+        let file = ScannedFile {
+            path: test_file_path_buf.to_str().unwrap().to_string(),
+        };
+
+        let result = DetermineFileTypeLibMagic::new().determine(&file);
+
+        assert!(result.is_none());
+    }
+
+    #[test]
+    fn test_if_magic_numbers_are_determined() {
+        let mut tar = vec![0u8; 512];
+        tar[257..262].copy_from_slice(b"ustar");
+
+        assert_eq!(
+            determine_by_magic_number(b"%PDF-1.7\n"),
+            Some(MimeType::ApplicationPdf)
+        );
+        assert_eq!(
+            determine_by_magic_number(b"\x89PNG\r\n\x1A\n\x00\x00"),
+            Some(MimeType::ImagePng)
+        );
+        assert_eq!(
+            determine_by_magic_number(b"{\\rtf1\\ansi"),
+            Some(MimeType::ApplicationRtf)
+        );
+        assert_eq!(
+            determine_by_magic_number(b"#!/usr/bin/env bash\n"),
+            Some(MimeType::TextPlain)
+        );
+        assert_eq!(
+            determine_by_magic_number(b"#!/usr/bin/python3.11 -u\n"),
+            Some(MimeType::TextXPython)
+        );
+        assert_eq!(
+            determine_by_magic_number(b"\n<!DOCTYPE html>\n<html>"),
+            Some(MimeType::TextHtml)
+        );
+        assert_eq!(
+            determine_by_magic_number(&tar),
+            Some(MimeType::ApplicationXTar)
+        );
+    }
+
+    #[test]
+    fn test_if_pdf_signature_is_only_determined_at_start() {
+        assert_eq!(
+            determine_by_magic_number(b"\xEF\xBB\xBF\r\n%PDF-1.4\n"),
+            Some(MimeType::ApplicationPdf)
+        );
+        assert_eq!(
+            determine_by_magic_number(b"The header %PDF- marks a PDF file.\n"),
+            None
+        );
+        assert_eq!(
+            DetermineFileTypeByLooseSignature::new()
+                .determine_embedded("scan", b"Content-Type: application/pdf\r\n\r\n%PDF-1.4\n"),
+            Some(MimeType::ApplicationPdf)
+        );
+        assert_eq!(
+            DetermineFileTypeByLooseSignature::new().determine_embedded("scan", b"%PDF"),
+            None
+        );
+    }
+
+    fn get_test_data_directory() -> PathBuf {
+        let current_dir_path_buf = current_dir().unwrap();
+
+        let current_path = Path::new(current_dir_path_buf.to_str().unwrap());
+        let mut ancestor_iter = current_path.ancestors();
+        ancestor_iter.next();
+        let base_path = ancestor_iter.next().unwrap();
+
+        return base_path.to_path_buf().join("test-data");
     }
 }
@@ -5,12 +5,22 @@ pub(crate) mod determine_file_type_lib_magic;
 use crate::conversion::convert_to_clear_text_strategy::MimeType;
 use crate::conversion::determine_file_type::determine_file_type_by_extension::DetermineFileTypeByExtensionFactory;
 use crate::conversion::determine_file_type::determine_file_type_by_human_readability::DetermineFileTypeByHumanReadabilityFactory;
-use crate::conversion::determine_file_type::determine_file_type_lib_magic::DetermineFileTypeLibMagic;
+use crate::conversion::determine_file_type::determine_file_type_lib_magic::{
+    DetermineFileTypeByLooseSignature, DetermineFileTypeLibMagic,
+};
 use crate::file_indexer::ScannedFile;
+use dscvr_common::config::FileTypeSettings;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::path::Path;
 use std::sync::Arc;
 
 pub(crate) trait DetermineFileTypeStrategy: Send + Sync {
     fn determine(&self, file: &ScannedFile) -> Option<MimeType>;
+
+    /// Determines the type of a file that is embedded in another one, e.g. an attachment of a mail.
+    fn determine_embedded(&self, name: &str, buf: &[u8]) -> Option<MimeType>;
 }
 
 pub(crate) struct DefaultDetermineFileType {
@@ -36,23 +46,51 @@ impl DetermineFileTypeStrategy for DefaultDetermineFileType {
 
         return None;
     }
+
+    fn determine_embedded(&self, name: &str, buf: &[u8]) -> Option<MimeType> {
+        for strategy in &self.strategies {
+            let res = strategy.determine_embedded(name, buf);
+
+            if res.is_some() {
+                return res;
+            }
+        }
+
+        return None;
+    }
 }
 
 pub(crate) struct DefaultDetermineFileTypeFactory;
 
 impl DefaultDetermineFileTypeFactory {
-    pub fn create() -> DefaultDetermineFileType {
-        let by_extension_strategy = Arc::new(DetermineFileTypeByExtensionFactory::create());
+    pub fn create_with_settings(settings: &FileTypeSettings) -> DefaultDetermineFileType {
+        let by_extension_strategy = Arc::new(
+            DetermineFileTypeByExtensionFactory::create_with_settings(settings),
+        );
         let by_human_readability_strategy =
             Arc::new(DetermineFileTypeByHumanReadabilityFactory::create());
         let by_lib_magic_strategy = Arc::new(DetermineFileTypeLibMagic::new());
+        let by_loose_signature_strategy = Arc::new(DetermineFileTypeByLooseSignature::new());
 
+        // The content is checked first, so that misnamed files aren't converted with the wrong conversion. Signatures
+        // that aren't at the start are only trusted if the extension doesn't tell the type.
         let strategies: Vec<Arc<dyn DetermineFileTypeStrategy>> = vec![
+            by_lib_magic_strategy,
             by_extension_strategy,
+            by_loose_signature_strategy,
             by_human_readability_strategy,
-            by_lib_magic_strategy,
         ];
 
         return DefaultDetermineFileType::new(strategies);
     }
 }
+
+/// Reads at most `len` bytes from the start of the file.
+pub(crate) fn read_header(path: &Path, len: usize) -> io::Result<Vec<u8>> {
+    let mut header = Vec::with_capacity(len);
+    File::open(path)?
+        .take(len as u64)
+        .read_to_end(&mut header)?;
+
+    return Ok(header);
+}
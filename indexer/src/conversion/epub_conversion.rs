@@ -0,0 +1,334 @@
+use crate::conversion::clear_text_conversion::decode_text;
+use crate::conversion::markup_conversion::{
+    collapse_whitespace, convert_html_to_text, decode_entities, find_tag_end, get_attribute,
+};
+use crate::conversion::{Conversion, ConvertedDocument};
+use anyhow::{anyhow, Error};
+use log::warn;
+use std::io::{Cursor, Read, Seek};
+use zip::ZipArchive;
+
+const CONTAINER_PATH: &str = "META-INF/container.xml";
+
+/// Chapters are single XHTML files, anything larger than this is most likely not a chapter.
+const MAX_CHAPTER_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Extracts the chapters of EPUB files in reading order, as listed in the spine of the package document, and the
+/// Dublin Core metadata of the book.
+pub(crate) struct EpubConversion;
+
+impl EpubConversion {
+    pub(crate) fn new() -> Self {
+        return Self {};
+    }
+}
+
+impl Conversion for EpubConversion {
+    fn convert(&self, buf: Vec<u8>) -> Result<ConvertedDocument, Error> {
+        let mut archive = ZipArchive::new(Cursor::new(buf))?;
+
+        let package_path = find_package_path(&mut archive)
+            .ok_or_else(|| anyhow!("The EPUB file doesn't contain a package document."))?;
+        let package = read_entry(&mut archive, &package_path)?;
+        let package = read_package(&package);
+
+        let base_directory = match package_path.rfind('/') {
+            Some(v) => &package_path[..v],
+            None => "",
+        };
+
+        let mut chapters = Vec::new();
+        for href in &package.chapter_hrefs {
+            let path = match resolve_href(base_directory, href) {
+                Some(v) => v,
+                None => continue,
+            };
+
+            match read_entry(&mut archive, &path) {
+                Ok(v) => {
+                    let chapter = convert_html_to_text(&v);
+                    if !chapter.is_empty() {
+                        chapters.push(chapter);
+                    }
+                }
+                Err(e) => {
+                    warn!(
+                        "There was an error while trying to read the chapter {:?} of the EPUB file: {:?}",
+                        path, e
+                    );
+                }
+            }
+        }
+
+        let mut converted_document = ConvertedDocument::new(chapters.join("\n\n"));
+        if let Some(title) = package.title {
+            converted_document = converted_document.with_metadata("title", title);
+        }
+        for creator in package.creators {
+            converted_document = converted_document.with_metadata("author", creator);
+        }
+        if let Some(language) = package.language {
+            converted_document = converted_document.with_metadata("language", language);
+        }
+
+        return Ok(converted_document);
+    }
+}
+
+/// The container lists the package document, older or sloppy files are searched for one instead.
+fn find_package_path<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Option<String> {
+    let from_container = read_entry(archive, CONTAINER_PATH)
+        .ok()
+        .and_then(|container| {
+            return read_xml_elements(&container)
+                .into_iter()
+                .find(|v| v.name == "rootfile")
+                .and_then(|v| get_attribute(v.attributes, "full-path"));
+        });
+    if from_container.is_some() {
+        return from_container;
+    }
+
+    return archive
+        .file_names()
+        .find(|v| v.to_lowercase().ends_with(".opf"))
+        .map(|v| v.to_string());
+}
+
+fn read_entry<R: Read + Seek>(archive: &mut ZipArchive<R>, path: &str) -> Result<String, Error> {
+    let mut buf = Vec::new();
+    archive
+        .by_name(path)?
+        .take(MAX_CHAPTER_SIZE)
+        .read_to_end(&mut buf)?;
+
+    return Ok(decode_text(&buf).0);
+}
+
+struct Package {
+    title: Option<String>,
+    creators: Vec<String>,
+    /// The primary language subtag, e.g. `en` for `en-US`.
+    language: Option<String>,
+    /// The hrefs of the XHTML documents in the spine, relative to the package document.
+    chapter_hrefs: Vec<String>,
+}
+
+fn read_package(package: &str) -> Package {
+    let elements = read_xml_elements(package);
+    let get_text = |name: &str| {
+        return elements
+            .iter()
+            .filter(|v| v.name == name)
+            .map(|v| collapse_whitespace(&decode_entities(v.text)))
+            .filter(|v| !v.is_empty())
+            .collect::<Vec<_>>();
+    };
+
+    let manifest = elements
+        .iter()
+        .filter(|v| v.name == "item")
+        .filter_map(|v| {
+            let id = get_attribute(v.attributes, "id")?;
+            let href = get_attribute(v.attributes, "href")?;
+            let media_type = get_attribute(v.attributes, "media-type").unwrap_or_default();
+            return Some((id, href, media_type));
+        })
+        .collect::<Vec<_>>();
+
+    let chapter_hrefs = elements
+        .iter()
+        .filter(|v| v.name == "itemref")
+        .filter_map(|v| get_attribute(v.attributes, "idref"))
+        .filter_map(|idref| manifest.iter().find(|(id, _, _)| *id == idref))
+        // The spine may also reference images or other content, which isn't text.
+        .filter(|(_, _, media_type)| {
+            matches!(
+                media_type.as_str(),
+                "application/xhtml+xml" | "text/html" | ""
+            )
+        })
+        .map(|(_, href, _)| href.clone())
+        .collect();
+
+    return Package {
+        title: get_text("title").into_iter().next(),
+        creators: get_text("creator"),
+        language: get_text("language").into_iter().next().map(|v| {
+            v.split(['-', '_'])
+                .next()
+                .unwrap_or_default()
+                .to_lowercase()
+        }),
+        chapter_hrefs,
+    };
+}
+
+/// An element of an XML document with its local name and the text up to the next tag, which is enough for the flat
+/// structure of container and package documents.
+struct XmlElement<'a> {
+    name: String,
+    attributes: &'a str,
+    text: &'a str,
+}
+
+fn read_xml_elements(xml: &str) -> Vec<XmlElement<'_>> {
+    let mut elements = Vec::new();
+    let mut pos = 0;
+
+    while let Some(start) = xml[pos..].find('<').map(|v| v + pos) {
+        let end = match find_tag_end(&xml[start..]) {
+            Some(v) => start + v,
+            None => break,
+        };
+        let tag = &xml[start + 1..end];
+        pos = end + 1;
+
+        if tag.starts_with(['/', '?', '!']) {
+            continue;
+        }
+
+        let name_end = tag
+            .find(|c: char| c.is_whitespace() || c == '/')
+            .unwrap_or(tag.len());
+        let name = &tag[..name_end];
+        let text = match tag.ends_with('/') {
+            true => "",
+            false => &xml[pos..xml[pos..].find('<').map_or(xml.len(), |v| v + pos)],
+        };
+
+        elements.push(XmlElement {
+            name: name.rsplit(':').next().unwrap_or(name).to_string(),
+            attributes: &tag[name_end..],
+            text,
+        });
+    }
+
+    return elements;
+}
+
+/// Resolves an href of the package document to the path of the entry, hrefs are URLs and may be percent-encoded.
+fn resolve_href(base_directory: &str, href: &str) -> Option<String> {
+    let href = percent_decode(href.split('#').next()?);
+    if href.is_empty() || href.contains("://") {
+        return None;
+    }
+
+    let mut components: Vec<&str> = Vec::new();
+    let base_components = match href.starts_with('/') {
+        true => "",
+        false => base_directory,
+    };
+    for component in base_components.split('/').chain(href.split('/')) {
+        match component {
+            "" | "." => {}
+            ".." => {
+                components.pop()?;
+            }
+            _ => components.push(component),
+        }
+    }
+
+    return Some(components.join("/"));
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|v| std::str::from_utf8(v).ok())
+            .and_then(|v| u8::from_str_radix(v, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(v)) => {
+                decoded.push(v);
+                i += 3;
+            }
+            (v, _) => {
+                decoded.push(v);
+                i += 1;
+            }
+        }
+    }
+
+    return String::from_utf8_lossy(&decoded).to_string();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::FileOptions;
+
+    #[test]
+    fn test_if_chapters_are_extracted_in_reading_order() {
+        let entries: &[(&str, &str)] = &[
+            ("mimetype", "application/epub+zip"),
+            (
+                "META-INF/container.xml",
+                r#"<?xml version="1.0"?>
+                <container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+                  <rootfiles>
+                    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+                  </rootfiles>
+                </container>"#,
+            ),
+            (
+                "OEBPS/content.opf",
+                r#"<?xml version="1.0"?>
+                <package xmlns="http://www.idpf.org/2007/opf" version="3.0">
+                  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+                    <dc:title>Rust &amp; Friends</dc:title>
+                    <dc:creator>Jane Doe</dc:creator>
+                    <dc:creator>John Doe</dc:creator>
+                    <dc:language>en-US</dc:language>
+                  </metadata>
+                  <manifest>
+                    <item id="cover" href="cover.jpg" media-type="image/jpeg"/>
+                    <item id="ch2" href="Text/chapter%202.xhtml" media-type="application/xhtml+xml"/>
+                    <item id="ch1" href="Text/chapter1.xhtml" media-type="application/xhtml+xml"/>
+                  </manifest>
+                  <spine>
+                    <itemref idref="cover"/>
+                    <itemref idref="ch1"/>
+                    <itemref idref="ch2"/>
+                  </spine>
+                </package>"#,
+            ),
+            (
+                "OEBPS/Text/chapter1.xhtml",
+                "<html><body><h1>Ownership</h1><p>Every value has an owner.</p></body></html>",
+            ),
+            (
+                "OEBPS/Text/chapter 2.xhtml",
+                "<html><body><h1>Borrowing</h1><p>References borrow values.</p></body></html>",
+            ),
+        ];
+
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, contents) in entries {
+            writer.start_file(*name, FileOptions::default()).unwrap();
+            writer.write_all(contents.as_bytes()).unwrap();
+        }
+        let epub = writer.finish().unwrap().into_inner();
+
+        let result = EpubConversion::new().convert(epub).unwrap();
+
+        assert_eq!(
+            result.contents,
+            "Ownership\nEvery value has an owner.\n\nBorrowing\nReferences borrow values."
+        );
+        assert_eq!(
+            result.metadata,
+            vec![
+                ("title".to_string(), "Rust & Friends".to_string()),
+                ("author".to_string(), "Jane Doe".to_string()),
+                ("author".to_string(), "John Doe".to_string()),
+                ("language".to_string(), "en".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,468 @@
+use crate::conversion::markup_conversion::decode_entities;
+use crate::conversion::{Conversion, ConvertedDocument};
+use anyhow::Error;
+use chrono::{FixedOffset, NaiveDate, NaiveDateTime, TimeZone};
+use exif::{Exif, In, Tag, Value};
+use std::io::Cursor;
+
+/// Extracts the Exif and XMP metadata of JPEG, TIFF, PNG and WebP images, so photos can be found by camera, lens,
+/// capture date and location. Exif is preferred, XMP fills in what is missing.
+pub(crate) struct ImageConversion;
+
+impl ImageConversion {
+    pub(crate) fn new() -> Self {
+        return Self {};
+    }
+}
+
+impl Conversion for ImageConversion {
+    fn convert(&self, buf: Vec<u8>) -> Result<ConvertedDocument, Error> {
+        // Images without any metadata are still indexed by their path.
+        let exif = exif::Reader::new()
+            .read_from_container(&mut Cursor::new(&buf))
+            .ok();
+        let xmp = find_xmp_packet(&buf);
+        let xmp = xmp.as_deref().unwrap_or("");
+
+        let camera_model = exif
+            .as_ref()
+            .and_then(get_camera_model)
+            .or_else(|| get_xmp_value(xmp, "tiff:Model"));
+        let lens = exif
+            .as_ref()
+            .and_then(|v| get_exif_string(v, Tag::LensModel))
+            .or_else(|| get_xmp_value(xmp, "exifEX:LensModel"))
+            .or_else(|| get_xmp_value(xmp, "aux:Lens"));
+        let captured_at = exif
+            .as_ref()
+            .and_then(get_exif_capture_date)
+            .or_else(|| get_xmp_value(xmp, "exif:DateTimeOriginal").and_then(parse_xmp_date))
+            .or_else(|| get_xmp_value(xmp, "xmp:CreateDate").and_then(parse_xmp_date));
+        let orientation = exif
+            .as_ref()
+            .and_then(|v| get_exif_uint(v, Tag::Orientation))
+            .or_else(|| get_xmp_value(xmp, "tiff:Orientation").and_then(|v| v.parse().ok()));
+        let dimensions = read_dimensions(&buf).or_else(|| {
+            let exif = exif.as_ref()?;
+            return Some((
+                get_exif_uint(exif, Tag::PixelXDimension)
+                    .or_else(|| get_exif_uint(exif, Tag::ImageWidth))?,
+                get_exif_uint(exif, Tag::PixelYDimension)
+                    .or_else(|| get_exif_uint(exif, Tag::ImageLength))?,
+            ));
+        });
+        let gps_latitude = exif
+            .as_ref()
+            .and_then(|v| get_exif_gps_coordinate(v, Tag::GPSLatitude, Tag::GPSLatitudeRef))
+            .or_else(|| get_xmp_value(xmp, "exif:GPSLatitude").and_then(parse_xmp_gps_coordinate));
+        let gps_longitude = exif
+            .as_ref()
+            .and_then(|v| get_exif_gps_coordinate(v, Tag::GPSLongitude, Tag::GPSLongitudeRef))
+            .or_else(|| get_xmp_value(xmp, "exif:GPSLongitude").and_then(parse_xmp_gps_coordinate));
+
+        let title = get_xmp_value(xmp, "dc:title");
+        let description = get_xmp_value(xmp, "dc:description");
+        let keywords = get_xmp_values(xmp, "dc:subject");
+
+        // The camera and lens are part of the contents, so a plain search for them finds the photos as well.
+        let contents = [&camera_model, &lens, &title, &description]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .chain(keywords.iter().cloned())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let mut converted_document = ConvertedDocument::new(contents);
+        let text_metadata = [
+            ("camera_model", camera_model),
+            ("lens", lens),
+            ("captured_at", captured_at),
+            ("orientation", orientation.map(|v| v.to_string())),
+            ("width", dimensions.map(|(v, _)| v.to_string())),
+            ("height", dimensions.map(|(_, v)| v.to_string())),
+            ("gps_latitude", gps_latitude.map(|v| v.to_string())),
+            ("gps_longitude", gps_longitude.map(|v| v.to_string())),
+            ("title", title),
+            ("description", description),
+        ];
+        for (field_name, value) in text_metadata {
+            if let Some(value) = value {
+                converted_document = converted_document.with_metadata(field_name, value);
+            }
+        }
+        for keyword in keywords {
+            converted_document = converted_document.with_metadata("keywords", keyword);
+        }
+
+        return Ok(converted_document);
+    }
+}
+
+fn get_exif_string(exif: &Exif, tag: Tag) -> Option<String> {
+    let value = match &exif.get_field(tag, In::PRIMARY)?.value {
+        Value::Ascii(v) => String::from_utf8_lossy(v.first()?).into_owned(),
+        _ => return None,
+    };
+
+    let value = value.trim_matches(char::from(0)).trim();
+    if value.is_empty() {
+        return None;
+    }
+
+    return Some(value.to_string());
+}
+
+fn get_exif_uint(exif: &Exif, tag: Tag) -> Option<u32> {
+    return exif.get_field(tag, In::PRIMARY)?.value.get_uint(0);
+}
+
+/// Most cameras repeat the make in the model, e.g. `Canon` and `Canon EOS R5`, but some don't, e.g. `FUJIFILM` and
+/// `X100V`.
+fn get_camera_model(exif: &Exif) -> Option<String> {
+    let model = get_exif_string(exif, Tag::Model)?;
+    let make = match get_exif_string(exif, Tag::Make) {
+        Some(v) => v,
+        None => return Some(model),
+    };
+
+    let make_prefix = make
+        .split_whitespace()
+        .next()
+        .unwrap_or(&make)
+        .to_lowercase();
+    if model.to_lowercase().starts_with(&make_prefix) {
+        return Some(model);
+    }
+
+    return Some(format!("{} {}", make, model));
+}
+
+/// Capture dates are local times and only come with an offset in newer cameras, otherwise they are taken as UTC.
+fn get_exif_capture_date(exif: &Exif) -> Option<String> {
+    let field = exif
+        .get_field(Tag::DateTimeOriginal, In::PRIMARY)
+        .or_else(|| exif.get_field(Tag::DateTime, In::PRIMARY))?;
+    let mut date_time = match &field.value {
+        Value::Ascii(v) => exif::DateTime::from_ascii(v.first()?).ok()?,
+        _ => return None,
+    };
+
+    if let Some(Value::Ascii(v)) = exif
+        .get_field(Tag::OffsetTimeOriginal, In::PRIMARY)
+        .map(|v| &v.value)
+    {
+        if let Some(offset) = v.first() {
+            let _ = date_time.parse_offset(offset);
+        }
+    }
+
+    let naive_date_time = NaiveDate::from_ymd_opt(
+        date_time.year as i32,
+        date_time.month as u32,
+        date_time.day as u32,
+    )?
+    .and_hms_opt(
+        date_time.hour as u32,
+        date_time.minute as u32,
+        date_time.second as u32,
+    )?;
+    let offset = FixedOffset::east_opt(date_time.offset.unwrap_or(0) as i32 * 60)?;
+
+    return Some(
+        offset
+            .from_local_datetime(&naive_date_time)
+            .single()?
+            .to_rfc3339(),
+    );
+}
+
+/// Coordinates are stored as degrees, minutes and seconds, and are negative in the south and west.
+fn get_exif_gps_coordinate(exif: &Exif, tag: Tag, reference_tag: Tag) -> Option<f64> {
+    let degrees_minutes_seconds = match &exif.get_field(tag, In::PRIMARY)?.value {
+        Value::Rational(v) if !v.is_empty() => v
+            .iter()
+            .map(|v| v.to_f64())
+            .chain([0.0, 0.0])
+            .take(3)
+            .collect::<Vec<_>>(),
+        _ => return None,
+    };
+
+    let coordinate = degrees_minutes_seconds[0]
+        + degrees_minutes_seconds[1] / 60.0
+        + degrees_minutes_seconds[2] / 3600.0;
+    if !coordinate.is_finite() {
+        return None;
+    }
+
+    return match get_exif_string(exif, reference_tag).as_deref() {
+        Some("S") | Some("W") => Some(-coordinate),
+        _ => Some(coordinate),
+    };
+}
+
+/// XMP packets are stored uncompressed in all supported formats, so they can be found without parsing the container.
+fn find_xmp_packet(buf: &[u8]) -> Option<String> {
+    let start = find(buf, b"<x:xmpmeta")?;
+    let end = find(&buf[start..], b"</x:xmpmeta>")? + start;
+
+    return Some(String::from_utf8_lossy(&buf[start..end]).into_owned());
+}
+
+fn get_xmp_value(xmp: &str, name: &str) -> Option<String> {
+    return get_xmp_values(xmp, name).into_iter().next();
+}
+
+/// Returns the values of a property, which is either an attribute like `tiff:Model="X100V"` or an element, which
+/// may contain a list of `rdf:li` elements, e.g. for the keywords in `dc:subject`.
+fn get_xmp_values(xmp: &str, name: &str) -> Vec<String> {
+    for quote in ['"', '\''] {
+        let attribute = format!("{}={}", name, quote);
+        if let Some(start) = xmp.find(&attribute).map(|v| v + attribute.len()) {
+            let end = xmp[start..].find(quote).map_or(xmp.len(), |v| v + start);
+            return clean_xmp_value(&xmp[start..end]).into_iter().collect();
+        }
+    }
+
+    let open_tag = format!("<{}>", name);
+    let close_tag = format!("</{}>", name);
+    let inner = match xmp.find(&open_tag).and_then(|start| {
+        let start = start + open_tag.len();
+        let end = xmp[start..].find(&close_tag)? + start;
+        return Some(&xmp[start..end]);
+    }) {
+        Some(v) => v,
+        None => return Vec::new(),
+    };
+
+    if !inner.contains("<rdf:li") {
+        return clean_xmp_value(inner).into_iter().collect();
+    }
+
+    return inner
+        .split("<rdf:li")
+        .skip(1)
+        .filter_map(|v| {
+            let start = v.find('>')? + 1;
+            let end = v.find("</rdf:li>").unwrap_or(v.len());
+            return clean_xmp_value(v.get(start..end)?);
+        })
+        .collect();
+}
+
+fn clean_xmp_value(value: &str) -> Option<String> {
+    let value = decode_entities(value.trim());
+    if value.is_empty() || value.contains('<') {
+        return None;
+    }
+
+    return Some(value);
+}
+
+/// Converts dates like `2023-04-15T09:30:00+02:00`, which may lack the offset, the time or even the day.
+fn parse_xmp_date(value: String) -> Option<String> {
+    if let Ok(v) = chrono::DateTime::parse_from_rfc3339(&value) {
+        return Some(v.to_rfc3339());
+    }
+
+    let naive_date_time = NaiveDateTime::parse_from_str(&value, "%Y-%m-%dT%H:%M:%S%.f")
+        .or_else(|_| NaiveDateTime::parse_from_str(&value, "%Y-%m-%dT%H:%M"))
+        .ok()
+        .or_else(|| {
+            let date = NaiveDate::parse_from_str(&value, "%Y-%m-%d")
+                .or_else(|_| NaiveDate::parse_from_str(&format!("{}-01", value), "%Y-%m-%d"))
+                .ok()?;
+            return date.and_hms_opt(0, 0, 0);
+        })?;
+
+    return Some(naive_date_time.and_utc().fixed_offset().to_rfc3339());
+}
+
+/// Converts coordinates like `51,30.123N` or `51,30,7.38N` to degrees.
+fn parse_xmp_gps_coordinate(value: String) -> Option<f64> {
+    let direction = value.chars().last()?;
+    let parts = value[..value.len() - direction.len_utf8()]
+        .split(',')
+        .map(|v| v.trim().parse::<f64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+
+    let coordinate = parts
+        .iter()
+        .zip([1.0, 60.0, 3600.0])
+        .map(|(v, divisor)| v / divisor)
+        .sum::<f64>();
+
+    return match direction {
+        'N' | 'E' => Some(coordinate),
+        'S' | 'W' => Some(-coordinate),
+        _ => None,
+    };
+}
+
+/// Reads the width and height from the header of JPEG, PNG and WebP images.
+fn read_dimensions(buf: &[u8]) -> Option<(u32, u32)> {
+    let read_u16_be = |offset: usize| -> Option<u32> {
+        return Some(u16::from_be_bytes(buf.get(offset..offset + 2)?.try_into().ok()?) as u32);
+    };
+    let read_u16_le = |offset: usize| -> Option<u32> {
+        return Some(u16::from_le_bytes(buf.get(offset..offset + 2)?.try_into().ok()?) as u32);
+    };
+    let read_u24_le = |offset: usize| -> Option<u32> {
+        let bytes = buf.get(offset..offset + 3)?;
+        return Some(bytes[0] as u32 | (bytes[1] as u32) << 8 | (bytes[2] as u32) << 16);
+    };
+
+    if buf.starts_with(b"\x89PNG\r\n\x1A\n") && buf.get(12..16) == Some(b"IHDR") {
+        return Some((
+            u32::from_be_bytes(buf.get(16..20)?.try_into().ok()?),
+            u32::from_be_bytes(buf.get(20..24)?.try_into().ok()?),
+        ));
+    }
+
+    if buf.starts_with(b"RIFF") && buf.get(8..12) == Some(b"WEBP") {
+        return match buf.get(12..16)? {
+            b"VP8X" => Some((read_u24_le(24)? + 1, read_u24_le(27)? + 1)),
+            b"VP8 " => Some((read_u16_le(26)? & 0x3FFF, read_u16_le(28)? & 0x3FFF)),
+            b"VP8L" => {
+                let bits = u32::from_le_bytes(buf.get(21..25)?.try_into().ok()?);
+                Some(((bits & 0x3FFF) + 1, ((bits >> 14) & 0x3FFF) + 1))
+            }
+            _ => None,
+        };
+    }
+
+    if buf.starts_with(b"\xFF\xD8") {
+        let mut offset = 2;
+        while buf.get(offset) == Some(&0xFF) {
+            let marker = *buf.get(offset + 1)?;
+            if marker == 0xFF {
+                offset += 1;
+                continue;
+            }
+            if marker == 0x01 || (0xD0..=0xD9).contains(&marker) {
+                offset += 2;
+                continue;
+            }
+
+            // All start of frame markers except the ones for huffman tables, arithmetic coding and extensions.
+            if (0xC0..=0xCF).contains(&marker) && ![0xC4, 0xC8, 0xCC].contains(&marker) {
+                return Some((read_u16_be(offset + 7)?, read_u16_be(offset + 5)?));
+            }
+
+            offset += 2 + read_u16_be(offset + 2)? as usize;
+        }
+    }
+
+    return None;
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    return haystack
+        .windows(needle.len())
+        .position(|window| window == needle);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exif::experimental::Writer;
+    use exif::{Field, Rational};
+
+    fn create_field(tag: Tag, value: Value) -> Field {
+        return Field {
+            tag,
+            ifd_num: In::PRIMARY,
+            value,
+        };
+    }
+
+    #[test]
+    fn test_if_exif_metadata_of_jpeg_is_extracted() {
+        let fields = [
+            create_field(Tag::Make, Value::Ascii(vec![b"FUJIFILM".to_vec()])),
+            create_field(Tag::Model, Value::Ascii(vec![b"X100V".to_vec()])),
+            create_field(Tag::Orientation, Value::Short(vec![6])),
+            create_field(
+                Tag::DateTimeOriginal,
+                Value::Ascii(vec![b"2023:04:15 09:30:00".to_vec()]),
+            ),
+            create_field(
+                Tag::OffsetTimeOriginal,
+                Value::Ascii(vec![b"+02:00".to_vec()]),
+            ),
+            create_field(Tag::GPSLatitudeRef, Value::Ascii(vec![b"N".to_vec()])),
+            create_field(
+                Tag::GPSLatitude,
+                Value::Rational(vec![
+                    Rational::from((48, 1)),
+                    Rational::from((12, 1)),
+                    Rational::from((36, 1)),
+                ]),
+            ),
+            create_field(Tag::GPSLongitudeRef, Value::Ascii(vec![b"W".to_vec()])),
+            create_field(
+                Tag::GPSLongitude,
+                Value::Rational(vec![Rational::from((16, 1)), Rational::from((30, 1))]),
+            ),
+        ];
+        let mut writer = Writer::new();
+        for field in &fields {
+            writer.push_field(field);
+        }
+        let mut tiff = Cursor::new(Vec::new());
+        writer.write(&mut tiff, false).unwrap();
+        let tiff = tiff.into_inner();
+
+        let mut jpeg = b"\xFF\xD8\xFF\xE1".to_vec();
+        jpeg.extend_from_slice(&((tiff.len() + 8) as u16).to_be_bytes());
+        jpeg.extend_from_slice(b"Exif\x00\x00");
+        jpeg.extend_from_slice(&tiff);
+        jpeg.extend_from_slice(b"\xFF\xC0\x00\x11\x08\x0F\xA0\x17\x70\x03");
+        jpeg.extend_from_slice(&[0; 9]);
+        jpeg.extend_from_slice(b"\xFF\xD9");
+
+        let result = ImageConversion::new().convert(jpeg).unwrap();
+
+        assert_eq!(result.contents, "FUJIFILM X100V");
+        assert_eq!(
+            result.metadata,
+            vec![
+                ("camera_model".to_string(), "FUJIFILM X100V".to_string()),
+                (
+                    "captured_at".to_string(),
+                    "2023-04-15T09:30:00+02:00".to_string()
+                ),
+                ("orientation".to_string(), "6".to_string()),
+                ("width".to_string(), "6000".to_string()),
+                ("height".to_string(), "4000".to_string()),
+                ("gps_latitude".to_string(), "48.21".to_string()),
+                ("gps_longitude".to_string(), "-16.5".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_if_xmp_properties_are_read() {
+        let xmp = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/"><rdf:RDF><rdf:Description
+            tiff:Model="X-T5" exif:GPSLatitude="51,30.6N" xmp:CreateDate="2023-07-01T18:00:00">
+            <dc:title><rdf:Alt><rdf:li xml:lang="x-default">Sunset &amp; Sea</rdf:li></rdf:Alt></dc:title>
+            <dc:subject><rdf:Bag><rdf:li>beach</rdf:li><rdf:li>holiday</rdf:li></rdf:Bag></dc:subject>
+            </rdf:Description></rdf:RDF>"#;
+
+        assert_eq!(get_xmp_value(xmp, "tiff:Model"), Some("X-T5".to_string()));
+        assert_eq!(
+            get_xmp_value(xmp, "dc:title"),
+            Some("Sunset & Sea".to_string())
+        );
+        assert_eq!(get_xmp_values(xmp, "dc:subject"), vec!["beach", "holiday"]);
+        assert_eq!(
+            get_xmp_value(xmp, "exif:GPSLatitude").and_then(parse_xmp_gps_coordinate),
+            Some(51.51)
+        );
+        assert_eq!(
+            get_xmp_value(xmp, "xmp:CreateDate").and_then(parse_xmp_date),
+            Some("2023-07-01T18:00:00+00:00".to_string())
+        );
+    }
+}
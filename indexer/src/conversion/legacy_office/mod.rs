@@ -0,0 +1,111 @@
+pub(crate) mod ms_excel_conversion;
+pub(crate) mod ms_powerpoint_conversion;
+pub(crate) mod ms_word_conversion;
+
+use cfb::CompoundFile;
+use std::io::{Cursor, Read};
+use thiserror::Error;
+
+/// Errors that can occur while extracting text from an OLE2 compound document (.doc, .xls, .ppt).
+#[derive(Error, Debug)]
+pub(crate) enum LegacyOfficeError {
+    #[error("The document is encrypted and can't be converted without its password.")]
+    Encrypted,
+    #[error("The document is corrupt: {0}")]
+    Corrupt(String),
+}
+
+pub(crate) fn open_compound_file(
+    buf: Vec<u8>,
+) -> Result<CompoundFile<Cursor<Vec<u8>>>, LegacyOfficeError> {
+    let compound_file = match CompoundFile::open(Cursor::new(buf)) {
+        Ok(v) => v,
+        Err(e) => {
+            return Err(LegacyOfficeError::Corrupt(format!(
+                "The file is not a valid compound document. {:?}",
+                e
+            )));
+        }
+    };
+
+    // Password protected OOXML documents are stored as a compound document with an encrypted package.
+    if compound_file.is_stream("/EncryptionInfo") || compound_file.is_stream("/EncryptedPackage") {
+        return Err(LegacyOfficeError::Encrypted);
+    }
+
+    return Ok(compound_file);
+}
+
+pub(crate) fn read_stream(
+    compound_file: &mut CompoundFile<Cursor<Vec<u8>>>,
+    path: &str,
+) -> Result<Vec<u8>, LegacyOfficeError> {
+    let mut stream = match compound_file.open_stream(path) {
+        Ok(v) => v,
+        Err(e) => {
+            return Err(LegacyOfficeError::Corrupt(format!(
+                "There was an error while trying to open the stream {}. {:?}",
+                path, e
+            )));
+        }
+    };
+
+    let mut buf = Vec::new();
+    if let Err(e) = stream.read_to_end(&mut buf) {
+        return Err(LegacyOfficeError::Corrupt(format!(
+            "There was an error while trying to read the stream {}. {:?}",
+            path, e
+        )));
+    }
+
+    return Ok(buf);
+}
+
+pub(crate) fn read_u8(buf: &[u8], offset: usize) -> Result<u8, LegacyOfficeError> {
+    return buf
+        .get(offset)
+        .copied()
+        .ok_or_else(|| out_of_bounds(offset));
+}
+
+pub(crate) fn read_u16(buf: &[u8], offset: usize) -> Result<u16, LegacyOfficeError> {
+    let bytes = slice(buf, offset, 2)?;
+
+    return Ok(u16::from_le_bytes([bytes[0], bytes[1]]));
+}
+
+pub(crate) fn read_u32(buf: &[u8], offset: usize) -> Result<u32, LegacyOfficeError> {
+    let bytes = slice(buf, offset, 4)?;
+
+    return Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+}
+
+pub(crate) fn slice(buf: &[u8], offset: usize, len: usize) -> Result<&[u8], LegacyOfficeError> {
+    let end = offset
+        .checked_add(len)
+        .ok_or_else(|| out_of_bounds(offset))?;
+
+    return buf.get(offset..end).ok_or_else(|| out_of_bounds(end));
+}
+
+pub(crate) fn decode_utf16le(bytes: &[u8]) -> String {
+    let code_units = bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect::<Vec<_>>();
+
+    return String::from_utf16_lossy(&code_units);
+}
+
+pub(crate) fn decode_windows_1252(bytes: &[u8]) -> String {
+    let (decoded, _, _) = encoding_rs::WINDOWS_1252.decode(bytes);
+
+    return decoded.into_owned();
+}
+
+fn out_of_bounds(offset: usize) -> LegacyOfficeError {
+    return LegacyOfficeError::Corrupt(format!(
+        "A structure points outside of its stream (offset {}).",
+        offset
+    ));
+}
@@ -0,0 +1,462 @@
+use crate::conversion::legacy_office::{
+    decode_utf16le, decode_windows_1252, open_compound_file, read_stream, read_u16, read_u32,
+    read_u8, slice, LegacyOfficeError,
+};
+use crate::conversion::{Conversion, ConvertedDocument};
+use anyhow::Error;
+
+const RECORD_BOF: u16 = 0x0809;
+const RECORD_EOF: u16 = 0x000A;
+const RECORD_FILEPASS: u16 = 0x002F;
+const RECORD_CONTINUE: u16 = 0x003C;
+const RECORD_BOUNDSHEET: u16 = 0x0085;
+const RECORD_SST: u16 = 0x00FC;
+const RECORD_LABELSST: u16 = 0x00FD;
+const RECORD_LABEL: u16 = 0x0204;
+const RECORD_NUMBER: u16 = 0x0203;
+const RECORD_RK: u16 = 0x027E;
+const RECORD_MULRK: u16 = 0x00BD;
+const RECORD_FORMULA: u16 = 0x0006;
+const RECORD_STRING: u16 = 0x0207;
+
+const BIFF8_VERSION: u16 = 0x0600;
+
+/// Extracts sheet names and cell values of Excel 97-2003 (BIFF8) and Excel 5.0/95 (BIFF5) workbooks.
+pub(crate) struct MsExcelConversion;
+
+struct Record<'a> {
+    record_type: u16,
+    data: &'a [u8],
+    /// The data of the CONTINUE records directly following this record.
+    continues: Vec<&'a [u8]>,
+}
+
+impl MsExcelConversion {
+    pub(crate) fn new() -> Self {
+        return Self {};
+    }
+
+    fn extract_text(buf: Vec<u8>) -> Result<String, LegacyOfficeError> {
+        let mut compound_file = open_compound_file(buf)?;
+        let workbook = if compound_file.is_stream("/Workbook") {
+            read_stream(&mut compound_file, "/Workbook")?
+        } else {
+            read_stream(&mut compound_file, "/Book")?
+        };
+
+        let records = read_records(&workbook)?;
+        let mut is_biff8 = true;
+        let mut shared_strings = Vec::new();
+        let mut text = String::new();
+        let mut current_row = None;
+        let mut pending_formula_row = None;
+
+        for record in records {
+            match record.record_type {
+                RECORD_BOF => {
+                    is_biff8 = read_u16(record.data, 0)? == BIFF8_VERSION;
+                    current_row = None;
+                }
+                RECORD_FILEPASS => return Err(LegacyOfficeError::Encrypted),
+                RECORD_EOF => {
+                    if current_row.is_some() {
+                        text.push('\n');
+                        current_row = None;
+                    }
+                }
+                RECORD_BOUNDSHEET => {
+                    let name = if is_biff8 {
+                        read_short_unicode_string(slice(
+                            record.data,
+                            6,
+                            record.data.len().saturating_sub(6),
+                        )?)?
+                    } else {
+                        read_byte_string(
+                            slice(record.data, 6, record.data.len().saturating_sub(6))?,
+                            false,
+                        )?
+                    };
+                    text.push_str(&name);
+                    text.push('\n');
+                }
+                RECORD_SST => {
+                    shared_strings = read_shared_strings(&record)?;
+                }
+                RECORD_LABELSST => {
+                    let index = read_u32(record.data, 6)? as usize;
+                    if let Some(value) = shared_strings.get(index) {
+                        push_cell(
+                            &mut text,
+                            &mut current_row,
+                            read_u16(record.data, 0)?,
+                            value,
+                        );
+                    }
+                }
+                RECORD_LABEL => {
+                    let value = if is_biff8 {
+                        read_unicode_string(slice(
+                            record.data,
+                            6,
+                            record.data.len().saturating_sub(6),
+                        )?)?
+                    } else {
+                        read_byte_string(
+                            slice(record.data, 6, record.data.len().saturating_sub(6))?,
+                            true,
+                        )?
+                    };
+                    push_cell(
+                        &mut text,
+                        &mut current_row,
+                        read_u16(record.data, 0)?,
+                        &value,
+                    );
+                }
+                RECORD_FORMULA => {
+                    // A string result isn't stored in the formula itself but in the following STRING record.
+                    let result = slice(record.data, 6, 8)?;
+                    if result[0] == 0x00 && result[6..8] == [0xFF, 0xFF] {
+                        pending_formula_row = Some(read_u16(record.data, 0)?);
+                    } else if result[6..8] != [0xFF, 0xFF] {
+                        let value = f64::from_le_bytes(result.try_into().unwrap());
+                        push_cell(
+                            &mut text,
+                            &mut current_row,
+                            read_u16(record.data, 0)?,
+                            &format_number(value),
+                        );
+                    }
+                }
+                RECORD_STRING => {
+                    let value = if is_biff8 {
+                        read_unicode_string(record.data)?
+                    } else {
+                        read_byte_string(record.data, true)?
+                    };
+                    if let Some(row) = pending_formula_row.take() {
+                        push_cell(&mut text, &mut current_row, row, &value);
+                    }
+                }
+                RECORD_NUMBER => {
+                    let value = f64::from_le_bytes(slice(record.data, 6, 8)?.try_into().unwrap());
+                    push_cell(
+                        &mut text,
+                        &mut current_row,
+                        read_u16(record.data, 0)?,
+                        &format_number(value),
+                    );
+                }
+                RECORD_RK => {
+                    let value = decode_rk(read_u32(record.data, 6)?);
+                    push_cell(
+                        &mut text,
+                        &mut current_row,
+                        read_u16(record.data, 0)?,
+                        &format_number(value),
+                    );
+                }
+                RECORD_MULRK => {
+                    let row = read_u16(record.data, 0)?;
+                    // Every RkRec has 6 bytes, the record ends with the index of the last column.
+                    let rk_count = record.data.len().saturating_sub(6) / 6;
+                    for i in 0..rk_count {
+                        let value = decode_rk(read_u32(record.data, 4 + i * 6 + 2)?);
+                        push_cell(&mut text, &mut current_row, row, &format_number(value));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        return Ok(text);
+    }
+}
+
+impl Conversion for MsExcelConversion {
+    fn convert(&self, buf: Vec<u8>) -> Result<ConvertedDocument, Error> {
+        return Ok(ConvertedDocument::new(Self::extract_text(buf)?));
+    }
+}
+
+fn read_records(workbook: &[u8]) -> Result<Vec<Record<'_>>, LegacyOfficeError> {
+    let mut records: Vec<Record> = Vec::new();
+    let mut offset = 0;
+
+    while offset + 4 <= workbook.len() {
+        let record_type = read_u16(workbook, offset)?;
+        let len = read_u16(workbook, offset + 2)? as usize;
+        let data = slice(workbook, offset + 4, len)?;
+        offset += 4 + len;
+
+        if record_type == RECORD_CONTINUE {
+            if let Some(previous) = records.last_mut() {
+                previous.continues.push(data);
+            }
+            continue;
+        }
+
+        records.push(Record {
+            record_type,
+            data,
+            continues: Vec::new(),
+        });
+    }
+
+    return Ok(records);
+}
+
+fn push_cell(text: &mut String, current_row: &mut Option<u16>, row: u16, value: &str) {
+    match current_row {
+        Some(v) if *v == row => text.push('\t'),
+        Some(_) => text.push('\n'),
+        None => {}
+    }
+    *current_row = Some(row);
+
+    text.push_str(value);
+}
+
+fn decode_rk(rk: u32) -> f64 {
+    let value = if rk & 0x02 != 0 {
+        ((rk as i32) >> 2) as f64
+    } else {
+        f64::from_bits(((rk & 0xFFFF_FFFC) as u64) << 32)
+    };
+
+    return if rk & 0x01 != 0 { value / 100.0 } else { value };
+}
+
+fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        return format!("{}", value as i64);
+    }
+
+    return format!("{}", value);
+}
+
+/// Reads a ShortXLUnicodeString (8 bit character count).
+fn read_short_unicode_string(data: &[u8]) -> Result<String, LegacyOfficeError> {
+    let char_count = read_u8(data, 0)? as usize;
+    let is_high_byte = read_u8(data, 1)? & 0x01 != 0;
+
+    return Ok(decode_chars(
+        slice(data, 2, char_count * char_width(is_high_byte))?,
+        is_high_byte,
+    ));
+}
+
+/// Reads a XLUnicodeString (16 bit character count).
+fn read_unicode_string(data: &[u8]) -> Result<String, LegacyOfficeError> {
+    let char_count = read_u16(data, 0)? as usize;
+    let is_high_byte = read_u8(data, 2)? & 0x01 != 0;
+
+    return Ok(decode_chars(
+        slice(data, 3, char_count * char_width(is_high_byte))?,
+        is_high_byte,
+    ));
+}
+
+/// Reads the byte strings used by BIFF5, which are encoded in the code page of the workbook.
+fn read_byte_string(data: &[u8], has_16_bit_length: bool) -> Result<String, LegacyOfficeError> {
+    let (len, offset) = if has_16_bit_length {
+        (read_u16(data, 0)? as usize, 2)
+    } else {
+        (read_u8(data, 0)? as usize, 1)
+    };
+
+    return Ok(decode_windows_1252(slice(data, offset, len)?));
+}
+
+fn char_width(is_high_byte: bool) -> usize {
+    return if is_high_byte { 2 } else { 1 };
+}
+
+fn decode_chars(bytes: &[u8], is_high_byte: bool) -> String {
+    if is_high_byte {
+        return decode_utf16le(bytes);
+    }
+
+    // Compressed strings contain the low bytes of UTF-16 code units, i.e. Latin-1.
+    return bytes.iter().map(|&b| b as char).collect();
+}
+
+/// Reads the shared string table, whose strings may be split across CONTINUE records.
+fn read_shared_strings(record: &Record) -> Result<Vec<String>, LegacyOfficeError> {
+    let mut segments = vec![record.data];
+    segments.extend(record.continues.iter());
+    let mut reader = SegmentReader {
+        segments,
+        segment: 0,
+        offset: 0,
+    };
+
+    reader.skip(4)?;
+    let unique_count = reader.read_u32()? as usize;
+    let mut strings = Vec::with_capacity(unique_count.min(1 << 16));
+
+    for _ in 0..unique_count {
+        if reader.is_at_end() {
+            break;
+        }
+
+        let char_count = reader.read_u16()? as usize;
+        let flags = reader.read_u8()?;
+        let rich_text_run_count = if flags & 0x08 != 0 {
+            reader.read_u16()? as usize
+        } else {
+            0
+        };
+        let phonetic_size = if flags & 0x04 != 0 {
+            reader.read_u32()? as usize
+        } else {
+            0
+        };
+
+        strings.push(reader.read_chars(char_count, flags & 0x01 != 0)?);
+        reader.skip(rich_text_run_count * 4 + phonetic_size)?;
+    }
+
+    return Ok(strings);
+}
+
+struct SegmentReader<'a> {
+    segments: Vec<&'a [u8]>,
+    segment: usize,
+    offset: usize,
+}
+
+impl<'a> SegmentReader<'a> {
+    fn is_at_end(&mut self) -> bool {
+        self.advance_if_exhausted();
+
+        return self.segment >= self.segments.len();
+    }
+
+    fn advance_if_exhausted(&mut self) {
+        while self.segment < self.segments.len() && self.offset >= self.segments[self.segment].len()
+        {
+            self.segment += 1;
+            self.offset = 0;
+        }
+    }
+
+    fn read_u8(&mut self) -> Result<u8, LegacyOfficeError> {
+        self.advance_if_exhausted();
+        let segment = self
+            .segments
+            .get(self.segment)
+            .ok_or_else(Self::truncated)?;
+        let value = read_u8(segment, self.offset)?;
+        self.offset += 1;
+
+        return Ok(value);
+    }
+
+    fn read_u16(&mut self) -> Result<u16, LegacyOfficeError> {
+        return Ok(u16::from_le_bytes([self.read_u8()?, self.read_u8()?]));
+    }
+
+    fn read_u32(&mut self) -> Result<u32, LegacyOfficeError> {
+        return Ok(u32::from_le_bytes([
+            self.read_u8()?,
+            self.read_u8()?,
+            self.read_u8()?,
+            self.read_u8()?,
+        ]));
+    }
+
+    fn skip(&mut self, mut len: usize) -> Result<(), LegacyOfficeError> {
+        while len > 0 {
+            self.advance_if_exhausted();
+            let segment = self
+                .segments
+                .get(self.segment)
+                .ok_or_else(Self::truncated)?;
+            let step = len.min(segment.len() - self.offset);
+            self.offset += step;
+            len -= step;
+        }
+
+        return Ok(());
+    }
+
+    /// Reads characters, where every CONTINUE record in the middle of a string starts with a new flags byte.
+    fn read_chars(
+        &mut self,
+        mut char_count: usize,
+        mut is_high_byte: bool,
+    ) -> Result<String, LegacyOfficeError> {
+        let mut out = String::with_capacity(char_count);
+
+        while char_count > 0 {
+            if self.offset >= self.segments.get(self.segment).map_or(0, |s| s.len()) {
+                self.segment += 1;
+                self.offset = 0;
+                let segment = self
+                    .segments
+                    .get(self.segment)
+                    .ok_or_else(Self::truncated)?;
+                is_high_byte = read_u8(segment, 0)? & 0x01 != 0;
+                self.offset = 1;
+            }
+
+            let segment = self.segments[self.segment];
+            let available = (segment.len() - self.offset) / char_width(is_high_byte);
+            if available == 0 {
+                return Err(Self::truncated());
+            }
+
+            let count = char_count.min(available);
+            let len = count * char_width(is_high_byte);
+            out.push_str(&decode_chars(
+                slice(segment, self.offset, len)?,
+                is_high_byte,
+            ));
+            self.offset += len;
+            char_count -= count;
+        }
+
+        return Ok(out);
+    }
+
+    fn truncated() -> LegacyOfficeError {
+        return LegacyOfficeError::Corrupt("The shared string table is truncated.".to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_if_shared_strings_split_across_continue_records_are_read() {
+        let mut sst = Vec::new();
+        sst.extend(2u32.to_le_bytes());
+        sst.extend(2u32.to_le_bytes());
+        sst.extend(5u16.to_le_bytes());
+        sst.push(0x00);
+        sst.extend(b"Hel");
+        let mut continue_data = vec![0x01];
+        continue_data.extend("lo".encode_utf16().flat_map(|c| c.to_le_bytes()));
+        continue_data.extend(3u16.to_le_bytes());
+        continue_data.push(0x00);
+        continue_data.extend(b"B\xe4r");
+        let record = Record {
+            record_type: RECORD_SST,
+            data: &sst,
+            continues: vec![&continue_data],
+        };
+
+        let result = read_shared_strings(&record).unwrap();
+
+        assert_eq!(result, vec!["Hello".to_string(), "Bär".to_string()]);
+    }
+
+    #[test]
+    fn test_if_rk_values_are_decoded() {
+        assert_eq!(decode_rk((42 << 2) | 0x02), 42.0);
+        assert_eq!(decode_rk((1234 << 2) | 0x03), 12.34);
+    }
+}
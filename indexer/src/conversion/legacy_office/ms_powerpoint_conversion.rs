@@ -0,0 +1,176 @@
+use crate::conversion::legacy_office::{
+    decode_utf16le, open_compound_file, read_stream, read_u16, read_u32, slice, LegacyOfficeError,
+};
+use crate::conversion::{Conversion, ConvertedDocument};
+use anyhow::Error;
+
+const RECORD_MAIN_MASTER: u16 = 0x03F8;
+const RECORD_TEXT_CHARS_ATOM: u16 = 0x0FA0;
+const RECORD_TEXT_BYTES_ATOM: u16 = 0x0FA8;
+
+const ENCRYPTED_HEADER_TOKEN: u32 = 0xF3D1_C4DF;
+
+/// Extracts the text atoms of PowerPoint 97-2003 presentations.
+pub(crate) struct MsPowerpointConversion;
+
+impl MsPowerpointConversion {
+    pub(crate) fn new() -> Self {
+        return Self {};
+    }
+
+    fn extract_text(buf: Vec<u8>) -> Result<String, LegacyOfficeError> {
+        let mut compound_file = open_compound_file(buf)?;
+
+        if compound_file.is_stream("/EncryptedSummary") {
+            return Err(LegacyOfficeError::Encrypted);
+        }
+
+        if compound_file.is_stream("/Current User") {
+            let current_user = read_stream(&mut compound_file, "/Current User")?;
+            if read_u32(&current_user, 12)? == ENCRYPTED_HEADER_TOKEN {
+                return Err(LegacyOfficeError::Encrypted);
+            }
+        }
+
+        let document = read_stream(&mut compound_file, "/PowerPoint Document")?;
+
+        let mut text = String::new();
+        collect_text(&document, &mut text, 0)?;
+
+        return Ok(text);
+    }
+}
+
+impl Conversion for MsPowerpointConversion {
+    fn convert(&self, buf: Vec<u8>) -> Result<ConvertedDocument, Error> {
+        return Ok(ConvertedDocument::new(Self::extract_text(buf)?));
+    }
+}
+
+/// Walks the records and their containers, skipping the masters whose placeholders aren't content.
+fn collect_text(records: &[u8], text: &mut String, depth: usize) -> Result<(), LegacyOfficeError> {
+    if depth > 32 {
+        return Err(LegacyOfficeError::Corrupt(
+            "The records are nested too deeply.".to_string(),
+        ));
+    }
+
+    let mut offset = 0;
+    while offset + 8 <= records.len() {
+        let version = read_u16(records, offset)? & 0x000F;
+        let record_type = read_u16(records, offset + 2)?;
+        let len = read_u32(records, offset + 4)? as usize;
+        // Some writers produce a last record that is longer than the stream, so it's cut off instead of failing.
+        let data = slice(records, offset + 8, len.min(records.len() - offset - 8))?;
+        offset += 8 + data.len();
+
+        match record_type {
+            RECORD_MAIN_MASTER => {}
+            RECORD_TEXT_CHARS_ATOM => push_paragraphs(text, &decode_utf16le(data)),
+            RECORD_TEXT_BYTES_ATOM => {
+                push_paragraphs(text, &data.iter().map(|&b| b as char).collect::<String>())
+            }
+            _ if version == 0x000F => collect_text(data, text, depth + 1)?,
+            _ => {}
+        }
+    }
+
+    return Ok(());
+}
+
+fn push_paragraphs(text: &mut String, atom: &str) {
+    text.extend(atom.chars().map(|c| match c {
+        '\r' | '\u{0B}' => '\n',
+        _ => c,
+    }));
+    text.push('\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cfb::CompoundFile;
+    use std::io::{Cursor, Write};
+
+    const RECORD_DOCUMENT: u16 = 0x03E8;
+    const RECORD_SLIDE: u16 = 0x03EE;
+
+    #[test]
+    fn test_if_text_of_chars_and_bytes_atoms_is_extracted() {
+        let chars_atom = create_record(
+            0,
+            RECORD_TEXT_CHARS_ATOM,
+            &"Übersicht\rZiele"
+                .encode_utf16()
+                .flat_map(|c| c.to_le_bytes())
+                .collect::<Vec<_>>(),
+        );
+        let bytes_atom = create_record(0, RECORD_TEXT_BYTES_ATOM, b"Quarterly report");
+        let master_atom = create_record(0, RECORD_TEXT_BYTES_ATOM, b"Click to edit title");
+        let slide = create_record(0x000F, RECORD_SLIDE, &[chars_atom, bytes_atom].concat());
+        let main_master = create_record(0x000F, RECORD_MAIN_MASTER, &master_atom);
+        let document = create_record(0x000F, RECORD_DOCUMENT, &[main_master, slide].concat());
+        let buf = create_powerpoint_document(&document, None);
+
+        let result = MsPowerpointConversion::new().convert(buf).unwrap().contents;
+
+        assert_eq!(result, "Übersicht\nZiele\nQuarterly report\n");
+    }
+
+    #[test]
+    fn test_if_encrypted_document_returns_encrypted_error() {
+        let mut current_user = vec![0u8; 20];
+        current_user[12..16].copy_from_slice(&ENCRYPTED_HEADER_TOKEN.to_le_bytes());
+        let document = create_record(0, RECORD_TEXT_BYTES_ATOM, b"secret");
+        let buf = create_powerpoint_document(&document, Some(&current_user));
+
+        let result = MsPowerpointConversion::new().convert(buf);
+
+        let error = result.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<LegacyOfficeError>(),
+            Some(LegacyOfficeError::Encrypted)
+        ));
+    }
+
+    #[test]
+    fn test_if_garbage_returns_corrupt_error() {
+        let result =
+            MsPowerpointConversion::new().convert(b"definitely not a powerpoint file".to_vec());
+
+        let error = result.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<LegacyOfficeError>(),
+            Some(LegacyOfficeError::Corrupt(_))
+        ));
+    }
+
+    fn create_record(version: u16, record_type: u16, data: &[u8]) -> Vec<u8> {
+        let mut record = Vec::new();
+        record.extend(version.to_le_bytes());
+        record.extend(record_type.to_le_bytes());
+        record.extend((data.len() as u32).to_le_bytes());
+        record.extend(data);
+
+        return record;
+    }
+
+    fn create_powerpoint_document(document: &[u8], current_user: Option<&[u8]>) -> Vec<u8> {
+        let mut compound_file = CompoundFile::create(Cursor::new(Vec::new())).unwrap();
+        compound_file
+            .create_stream("/PowerPoint Document")
+            .unwrap()
+            .write_all(document)
+            .unwrap();
+        if let Some(current_user) = current_user {
+            compound_file
+                .create_stream("/Current User")
+                .unwrap()
+                .write_all(current_user)
+                .unwrap();
+        }
+        compound_file.flush().unwrap();
+
+        return compound_file.into_inner().into_inner();
+    }
+}
@@ -0,0 +1,260 @@
+use crate::conversion::legacy_office::{
+    decode_utf16le, decode_windows_1252, open_compound_file, read_stream, read_u16, read_u32,
+    read_u8, slice, LegacyOfficeError,
+};
+use crate::conversion::{Conversion, ConvertedDocument};
+use anyhow::Error;
+
+const WORD_IDENT: u16 = 0xA5EC;
+const FIRST_WORD_97_FIB_VERSION: u16 = 0xC1;
+const FLAG_ENCRYPTED: u16 = 0x0100;
+const FLAG_WHICH_TABLE_STREAM: u16 = 0x0200;
+const FC_CLX_INDEX: usize = 33;
+
+/// Extracts the text of Word 97-2003 documents by following the piece table in the table stream.
+pub(crate) struct MsWordConversion;
+
+impl MsWordConversion {
+    pub(crate) fn new() -> Self {
+        return Self {};
+    }
+
+    fn extract_text(buf: Vec<u8>) -> Result<String, LegacyOfficeError> {
+        let mut compound_file = open_compound_file(buf)?;
+        let word_document = read_stream(&mut compound_file, "/WordDocument")?;
+
+        if read_u16(&word_document, 0x00)? != WORD_IDENT {
+            return Err(LegacyOfficeError::Corrupt(
+                "The WordDocument stream doesn't start with a valid file information block."
+                    .to_string(),
+            ));
+        }
+
+        let fib_version = read_u16(&word_document, 0x02)?;
+        let flags = read_u16(&word_document, 0x0A)?;
+
+        if flags & FLAG_ENCRYPTED != 0 {
+            return Err(LegacyOfficeError::Encrypted);
+        }
+
+        if fib_version < FIRST_WORD_97_FIB_VERSION {
+            return Self::extract_text_without_piece_table(&word_document);
+        }
+
+        let table_stream_name = if flags & FLAG_WHICH_TABLE_STREAM != 0 {
+            "/1Table"
+        } else {
+            "/0Table"
+        };
+        let table_stream = read_stream(&mut compound_file, table_stream_name)?;
+
+        let (fc_clx, lcb_clx) = Self::read_clx_location(&word_document)?;
+        let clx = slice(&table_stream, fc_clx as usize, lcb_clx as usize)?;
+        let piece_table = Self::find_piece_table(clx)?;
+
+        let mut text = String::new();
+        let piece_count = (piece_table.len() - 4) / 12;
+        for i in 0..piece_count {
+            let cp_start = read_u32(piece_table, i * 4)? as usize;
+            let cp_end = read_u32(piece_table, (i + 1) * 4)? as usize;
+            let char_count = cp_end.saturating_sub(cp_start);
+
+            let piece_descriptor_offset = (piece_count + 1) * 4 + i * 8;
+            let fc_compressed = read_u32(piece_table, piece_descriptor_offset + 2)?;
+            let is_compressed = fc_compressed & 0x4000_0000 != 0;
+            let fc = (fc_compressed & 0x3FFF_FFFF) as usize;
+
+            if is_compressed {
+                text.push_str(&decode_windows_1252(slice(
+                    &word_document,
+                    fc / 2,
+                    char_count,
+                )?));
+            } else {
+                text.push_str(&decode_utf16le(slice(&word_document, fc, char_count * 2)?));
+            }
+        }
+
+        return Ok(clean_up_word_text(&text));
+    }
+
+    /// Word 6 and Word 95 documents store their text contiguously between `fcMin` and `fcMac`.
+    fn extract_text_without_piece_table(word_document: &[u8]) -> Result<String, LegacyOfficeError> {
+        let fc_min = read_u32(word_document, 0x18)? as usize;
+        let fc_mac = read_u32(word_document, 0x1C)? as usize;
+        let text = slice(word_document, fc_min, fc_mac.saturating_sub(fc_min))?;
+
+        return Ok(clean_up_word_text(&decode_windows_1252(text)));
+    }
+
+    fn read_clx_location(word_document: &[u8]) -> Result<(u32, u32), LegacyOfficeError> {
+        let mut offset = 32;
+        let csw = read_u16(word_document, offset)? as usize;
+        offset += 2 + csw * 2;
+        let cslw = read_u16(word_document, offset)? as usize;
+        offset += 2 + cslw * 4;
+        let cb_rg_fc_lcb = read_u16(word_document, offset)? as usize;
+        offset += 2;
+
+        if cb_rg_fc_lcb <= FC_CLX_INDEX {
+            return Err(LegacyOfficeError::Corrupt(
+                "The file information block doesn't contain the location of the piece table."
+                    .to_string(),
+            ));
+        }
+
+        let clx_offset = offset + FC_CLX_INDEX * 8;
+
+        return Ok((
+            read_u32(word_document, clx_offset)?,
+            read_u32(word_document, clx_offset + 4)?,
+        ));
+    }
+
+    /// Skips the property modifiers at the start of the Clx and returns the PlcPcd of the piece table.
+    fn find_piece_table(clx: &[u8]) -> Result<&[u8], LegacyOfficeError> {
+        let mut offset = 0;
+        loop {
+            match read_u8(clx, offset)? {
+                0x01 => {
+                    let cb_grpprl = read_u16(clx, offset + 1)? as usize;
+                    offset += 3 + cb_grpprl;
+                }
+                0x02 => {
+                    let lcb = read_u32(clx, offset + 1)? as usize;
+                    let piece_table = slice(clx, offset + 5, lcb)?;
+
+                    if piece_table.len() < 4 || (piece_table.len() - 4) % 12 != 0 {
+                        return Err(LegacyOfficeError::Corrupt(
+                            "The piece table has an invalid size.".to_string(),
+                        ));
+                    }
+
+                    return Ok(piece_table);
+                }
+                v => {
+                    return Err(LegacyOfficeError::Corrupt(format!(
+                        "Unexpected entry {:#04x} in the piece table.",
+                        v
+                    )));
+                }
+            }
+        }
+    }
+}
+
+impl Conversion for MsWordConversion {
+    fn convert(&self, buf: Vec<u8>) -> Result<ConvertedDocument, Error> {
+        return Ok(ConvertedDocument::new(Self::extract_text(buf)?));
+    }
+}
+
+/// Replaces Word's control characters and drops field instructions while keeping the field results.
+fn clean_up_word_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    // For every open field, whether its instructions are still being read.
+    let mut fields: Vec<bool> = Vec::new();
+
+    for c in text.chars() {
+        match c {
+            '\u{13}' => fields.push(true),
+            '\u{14}' => {
+                if let Some(in_instructions) = fields.last_mut() {
+                    *in_instructions = false;
+                }
+            }
+            '\u{15}' => {
+                fields.pop();
+            }
+            _ if fields.iter().any(|&in_instructions| in_instructions) => {}
+            '\r' | '\u{0B}' | '\u{0C}' => out.push('\n'),
+            '\u{07}' => out.push('\t'),
+            '\u{1E}' => out.push('-'),
+            '\u{A0}' => out.push(' '),
+            '\u{01}' | '\u{08}' | '\u{1F}' => {}
+            _ => out.push(c),
+        }
+    }
+
+    return out;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cfb::CompoundFile;
+    use std::io::{Cursor, Write};
+
+    #[test]
+    fn test_if_text_of_piece_table_is_extracted() {
+        let text = "Hello \u{13} HYPERLINK \"x\" \u{14}World\u{15}\r";
+        let buf = create_word_document(text, 0);
+
+        let result = MsWordConversion::new().convert(buf).unwrap().contents;
+
+        assert_eq!(result, "Hello World\n");
+    }
+
+    #[test]
+    fn test_if_encrypted_document_returns_encrypted_error() {
+        let buf = create_word_document("secret", FLAG_ENCRYPTED);
+
+        let result = MsWordConversion::new().convert(buf);
+
+        let error = result.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<LegacyOfficeError>(),
+            Some(LegacyOfficeError::Encrypted)
+        ));
+    }
+
+    #[test]
+    fn test_if_garbage_returns_corrupt_error() {
+        let result = MsWordConversion::new().convert(b"definitely not a word file".to_vec());
+
+        let error = result.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<LegacyOfficeError>(),
+            Some(LegacyOfficeError::Corrupt(_))
+        ));
+    }
+
+    fn create_word_document(text: &str, flags: u16) -> Vec<u8> {
+        let text_offset = 1024;
+        let mut word_document = vec![0u8; text_offset];
+        word_document[0..2].copy_from_slice(&WORD_IDENT.to_le_bytes());
+        word_document[2..4].copy_from_slice(&FIRST_WORD_97_FIB_VERSION.to_le_bytes());
+        word_document[0x0A..0x0C].copy_from_slice(&flags.to_le_bytes());
+        // csw = 0, cslw = 0, cbRgFcLcb = 34
+        word_document[36..38].copy_from_slice(&34u16.to_le_bytes());
+        let clx_offset = 38 + FC_CLX_INDEX * 8;
+        word_document.extend(text.encode_utf16().flat_map(|c| c.to_le_bytes()));
+
+        let mut clx = vec![0x02];
+        clx.extend(16u32.to_le_bytes());
+        clx.extend(0u32.to_le_bytes());
+        clx.extend((text.encode_utf16().count() as u32).to_le_bytes());
+        clx.extend(0u16.to_le_bytes());
+        clx.extend((text_offset as u32).to_le_bytes());
+        clx.extend(0u16.to_le_bytes());
+
+        word_document[clx_offset..clx_offset + 4].copy_from_slice(&0u32.to_le_bytes());
+        word_document[clx_offset + 4..clx_offset + 8]
+            .copy_from_slice(&(clx.len() as u32).to_le_bytes());
+
+        let mut compound_file = CompoundFile::create(Cursor::new(Vec::new())).unwrap();
+        compound_file
+            .create_stream("/WordDocument")
+            .unwrap()
+            .write_all(&word_document)
+            .unwrap();
+        compound_file
+            .create_stream("/0Table")
+            .unwrap()
+            .write_all(&clx)
+            .unwrap();
+        compound_file.flush().unwrap();
+
+        return compound_file.into_inner().into_inner();
+    }
+}
@@ -0,0 +1,639 @@
+use crate::conversion::markup_conversion::convert_html_to_text;
+use crate::conversion::{Conversion, ConvertedDocument};
+use anyhow::{anyhow, Error};
+use base64::alphabet::STANDARD;
+use base64::engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig};
+use base64::engine::DecodePaddingMode;
+use base64::Engine;
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+use std::collections::HashSet;
+
+/// Mails are often cut off or sloppily encoded, so the padding and trailing bits of base64 aren't checked.
+const BASE64: GeneralPurpose = GeneralPurpose::new(
+    &STANDARD,
+    GeneralPurposeConfig::new()
+        .with_decode_padding_mode(DecodePaddingMode::Indifferent)
+        .with_decode_allow_trailing_bits(true),
+);
+
+/// Multipart bodies are rarely nested more than a few levels, deeper parts are skipped so a crafted mail can't exhaust
+/// the stack.
+const MAX_PART_DEPTH: u32 = 16;
+
+/// Extracts the headers, the text and the attachments of a single mail in the Internet Message Format.
+pub(crate) struct MailConversion;
+
+impl MailConversion {
+    pub(crate) fn new() -> Self {
+        return Self {};
+    }
+}
+
+impl Conversion for MailConversion {
+    fn convert(&self, buf: Vec<u8>) -> Result<ConvertedDocument, Error> {
+        let (headers, _) = split_headers(&buf);
+        if headers.is_empty() {
+            return Err(anyhow!("The mail doesn't contain any headers."));
+        }
+
+        let mut mail = MailContents::default();
+        mail.read_part(&buf, 0);
+
+        let subject = get_header(&headers, "subject").map(|v| decode_header_value(v));
+
+        let mut contents = String::new();
+        if let Some(subject) = &subject {
+            contents.push_str(subject);
+            contents.push_str("\n\n");
+        }
+        contents.push_str(mail.text.trim());
+
+        let mut converted_document = ConvertedDocument::new(contents);
+        for (header_name, field_name) in [("from", "from"), ("to", "to"), ("cc", "to")] {
+            if let Some(value) = get_header(&headers, header_name) {
+                converted_document =
+                    converted_document.with_metadata(field_name, decode_header_value(value));
+            }
+        }
+        if let Some(subject) = subject {
+            converted_document = converted_document.with_metadata("subject", subject);
+        }
+        if let Some(sent_at) = get_header(&headers, "date").and_then(parse_mail_date) {
+            converted_document = converted_document.with_metadata("sent_at", sent_at);
+        }
+
+        for (name, contents) in mail.attachments {
+            converted_document = converted_document
+                .with_metadata("attachments", name.clone())
+                .with_embedded_file(name, contents);
+        }
+
+        return Ok(converted_document);
+    }
+}
+
+/// Splits an mbox into its messages, which are indexed as mails of their own.
+pub(crate) struct MboxConversion;
+
+impl MboxConversion {
+    pub(crate) fn new() -> Self {
+        return Self {};
+    }
+}
+
+impl Conversion for MboxConversion {
+    fn convert(&self, buf: Vec<u8>) -> Result<ConvertedDocument, Error> {
+        if !buf.starts_with(b"From ") {
+            return Err(anyhow!("The mbox doesn't start with a \"From \" line."));
+        }
+
+        let mut converted_document = ConvertedDocument::default();
+        for (i, message) in split_mbox(&buf).into_iter().enumerate() {
+            converted_document =
+                converted_document.with_embedded_file(format!("{}.eml", i + 1), message);
+        }
+
+        return Ok(converted_document);
+    }
+}
+
+#[derive(Default)]
+struct MailContents {
+    text: String,
+    attachments: Vec<(String, Vec<u8>)>,
+    attachment_names: HashSet<String>,
+}
+
+impl MailContents {
+    fn read_part(&mut self, part: &[u8], depth: u32) {
+        if depth > MAX_PART_DEPTH {
+            return;
+        }
+
+        let (headers, body) = split_headers(part);
+        let (mime_type, content_type_params) =
+            parse_header_params(get_header(&headers, "content-type").unwrap_or("text/plain"));
+        let (disposition, disposition_params) =
+            parse_header_params(get_header(&headers, "content-disposition").unwrap_or(""));
+        let transfer_encoding = get_header(&headers, "content-transfer-encoding")
+            .unwrap_or("")
+            .trim()
+            .to_lowercase();
+
+        if mime_type.starts_with("multipart/") {
+            let boundary = match get_param(&content_type_params, "boundary") {
+                Some(v) => v,
+                None => {
+                    self.push_text(&decode_charset(body, None));
+                    return;
+                }
+            };
+            let parts = split_multipart(body, &boundary);
+
+            if mime_type == "multipart/alternative" {
+                // Every part shows the same text, the plain one is preferred as it contains no markup.
+                let preferred = parts
+                    .iter()
+                    .find(|v| {
+                        let (headers, _) = split_headers(v);
+                        let (mime_type, _) = parse_header_params(
+                            get_header(&headers, "content-type").unwrap_or("text/plain"),
+                        );
+                        mime_type == "text/plain"
+                    })
+                    .or(parts.last());
+                if let Some(part) = preferred {
+                    self.read_part(part, depth + 1);
+                }
+            } else {
+                for part in parts {
+                    self.read_part(part, depth + 1);
+                }
+            }
+
+            return;
+        }
+
+        let file_name = get_param(&disposition_params, "filename")
+            .or_else(|| get_param(&content_type_params, "name"))
+            .map(|v| decode_header_value(&v));
+        let body = decode_transfer_encoding(body, &transfer_encoding);
+
+        let is_attachment = disposition == "attachment"
+            || mime_type == "message/rfc822"
+            || (file_name.is_some() && !mime_type.starts_with("text/"));
+
+        if is_attachment {
+            let default_name = if mime_type == "message/rfc822" {
+                "message.eml"
+            } else {
+                "attachment"
+            };
+            self.push_attachment(file_name.as_deref().unwrap_or(default_name), body);
+        } else if mime_type == "text/html" {
+            let charset = get_param(&content_type_params, "charset");
+            self.push_text(&convert_html_to_text(&decode_charset(
+                &body,
+                charset.as_deref(),
+            )));
+        } else if mime_type.starts_with("text/") {
+            let charset = get_param(&content_type_params, "charset");
+            self.push_text(&decode_charset(&body, charset.as_deref()));
+        }
+    }
+
+    fn push_text(&mut self, text: &str) {
+        if !self.text.is_empty() {
+            self.text.push_str("\n\n");
+        }
+        self.text.push_str(text.trim());
+    }
+
+    /// Names are made unique, because they become part of the path of the attachment.
+    fn push_attachment(&mut self, name: &str, contents: Vec<u8>) {
+        let name = name.replace(['/', '\\'], "_");
+        let mut unique_name = name.clone();
+        let mut i = 1;
+        while !self.attachment_names.insert(unique_name.clone()) {
+            i += 1;
+            unique_name = format!("{}_{}", i, name);
+        }
+
+        self.attachments.push((unique_name, contents));
+    }
+}
+
+/// Returns the unfolded headers and the body, which starts after the first empty line.
+fn split_headers(part: &[u8]) -> (Vec<(String, String)>, &[u8]) {
+    let mut headers: Vec<(String, String)> = Vec::new();
+    let mut pos = 0;
+
+    while pos < part.len() {
+        let line_end = part[pos..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(part.len(), |v| pos + v);
+        let next_line = (line_end + 1).min(part.len());
+        let line = decode_charset(&part[pos..line_end], None);
+        let line = line.trim_end_matches('\r');
+
+        if line.is_empty() {
+            return (headers, &part[next_line..]);
+        }
+
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_lowercase(), value.trim().to_string()));
+        } else if headers.is_empty() {
+            // Not a mail at all, so everything is treated as body.
+            return (headers, part);
+        }
+
+        pos = next_line;
+    }
+
+    return (headers, &part[part.len()..]);
+}
+
+fn get_header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    return headers
+        .iter()
+        .find(|(header_name, _)| header_name == name)
+        .map(|(_, value)| value.as_str());
+}
+
+/// Splits a header like `text/plain; charset="utf-8"` into its lowercased value and its parameters.
+fn parse_header_params(value: &str) -> (String, Vec<(String, String)>) {
+    let mut segments = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    for c in value.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ';' if !in_quotes => segments.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    segments.push(current);
+
+    let mut segments = segments.into_iter();
+    let main_value = segments.next().unwrap_or_default().trim().to_lowercase();
+    let params = segments
+        .filter_map(|v| {
+            let (key, value) = v.split_once('=')?;
+            let key = key.trim().to_lowercase();
+            let value = value.trim();
+
+            // Extended parameters like `filename*=UTF-8''Gr%C3%BC%C3%9Fe.pdf` are percent-encoded.
+            if let Some(key) = key.strip_suffix('*') {
+                let (charset, encoded) = match value.splitn(3, '\'').collect::<Vec<_>>()[..] {
+                    [charset, _, encoded] => (Some(charset), encoded),
+                    _ => (None, value),
+                };
+                return Some((
+                    key.to_string(),
+                    decode_charset(&decode_percent_encoding(encoded), charset),
+                ));
+            }
+
+            return Some((key, value.to_string()));
+        })
+        .collect();
+
+    return (main_value, params);
+}
+
+fn get_param(params: &[(String, String)], name: &str) -> Option<String> {
+    return params
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.clone());
+}
+
+fn split_multipart<'a>(body: &'a [u8], boundary: &str) -> Vec<&'a [u8]> {
+    let delimiter = format!("--{}", boundary);
+    let mut parts = Vec::new();
+    let mut part_start = None;
+    let mut pos = 0;
+
+    while pos < body.len() {
+        let line_end = body[pos..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(body.len(), |v| pos + v);
+        let line = &body[pos..line_end];
+
+        if line.starts_with(delimiter.as_bytes()) {
+            if let Some(start) = part_start {
+                // The line break before the delimiter belongs to the delimiter.
+                let end = if pos >= 2 && body[pos - 2] == b'\r' {
+                    pos - 2
+                } else {
+                    pos.saturating_sub(1)
+                };
+                parts.push(&body[start..end.max(start)]);
+            }
+            if line[delimiter.len()..].starts_with(b"--") {
+                return parts;
+            }
+            part_start = Some((line_end + 1).min(body.len()));
+        }
+
+        pos = line_end + 1;
+    }
+
+    // A missing closing delimiter, e.g. in a truncated mail.
+    if let Some(start) = part_start {
+        parts.push(&body[start..]);
+    }
+
+    return parts;
+}
+
+fn split_mbox(buf: &[u8]) -> Vec<Vec<u8>> {
+    let mut messages = Vec::new();
+    let mut current: Option<Vec<u8>> = None;
+    let mut previous_line_was_empty = true;
+
+    for line in buf.split_inclusive(|&b| b == b'\n') {
+        if previous_line_was_empty && line.starts_with(b"From ") {
+            if let Some(message) = current.take() {
+                messages.push(message);
+            }
+            current = Some(Vec::new());
+            previous_line_was_empty = false;
+            continue;
+        }
+
+        previous_line_was_empty = line == b"\n" || line == b"\r\n";
+
+        if let Some(message) = current.as_mut() {
+            // Lines starting with `From ` were escaped with `>` when they were written to the mbox.
+            let unquoted = line.iter().position(|&b| b != b'>').unwrap_or(0);
+            if unquoted > 0 && line[unquoted..].starts_with(b"From ") {
+                message.extend_from_slice(&line[1..]);
+            } else {
+                message.extend_from_slice(line);
+            }
+        }
+    }
+
+    if let Some(message) = current {
+        messages.push(message);
+    }
+
+    return messages;
+}
+
+fn decode_transfer_encoding(body: &[u8], transfer_encoding: &str) -> Vec<u8> {
+    return match transfer_encoding {
+        "base64" => decode_base64(body),
+        "quoted-printable" => decode_quoted_printable(body),
+        _ => body.to_vec(),
+    };
+}
+
+fn decode_base64(buf: &[u8]) -> Vec<u8> {
+    let cleaned = buf
+        .iter()
+        .copied()
+        .filter(|b| b.is_ascii_alphanumeric() || *b == b'+' || *b == b'/')
+        .collect::<Vec<_>>();
+
+    // A truncated last quantum is dropped instead of failing the whole part.
+    let complete_length = cleaned.len() - cleaned.len() % 4;
+    return match BASE64.decode(&cleaned) {
+        Ok(v) => v,
+        Err(_) => BASE64
+            .decode(&cleaned[..complete_length])
+            .unwrap_or_default(),
+    };
+}
+
+fn decode_quoted_printable(buf: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(buf.len());
+    let mut i = 0;
+
+    while i < buf.len() {
+        if buf[i] != b'=' {
+            out.push(buf[i]);
+            i += 1;
+            continue;
+        }
+
+        match (buf.get(i + 1), buf.get(i + 2)) {
+            // Soft line breaks join lines that were wrapped for transport.
+            (Some(b'\r'), Some(b'\n')) => i += 3,
+            (Some(b'\n'), _) => i += 2,
+            (Some(&high), Some(&low)) if high.is_ascii_hexdigit() && low.is_ascii_hexdigit() => {
+                let hex = [high, low];
+                let hex = std::str::from_utf8(&hex).unwrap_or("3F");
+                out.push(u8::from_str_radix(hex, 16).unwrap_or(b'?'));
+                i += 3;
+            }
+            _ => {
+                out.push(b'=');
+                i += 1;
+            }
+        }
+    }
+
+    return out;
+}
+
+fn decode_percent_encoding(value: &str) -> Vec<u8> {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let decoded = bytes
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|v| std::str::from_utf8(v).ok())
+            .and_then(|v| u8::from_str_radix(v, 16).ok());
+
+        match decoded {
+            Some(v) => {
+                out.push(v);
+                i += 3;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    return out;
+}
+
+/// Decodes text of the given charset, falling back to UTF-8 or Windows-1252 for unknown or missing charsets.
+fn decode_charset(buf: &[u8], charset: Option<&str>) -> String {
+    let encoding = charset
+        .map(|v| v.split('*').next().unwrap_or(v).trim())
+        .and_then(|v| Encoding::for_label(v.as_bytes()))
+        .unwrap_or(if std::str::from_utf8(buf).is_ok() {
+            UTF_8
+        } else {
+            WINDOWS_1252
+        });
+
+    return encoding.decode_without_bom_handling(buf).0.into_owned();
+}
+
+/// Decodes the encoded words of RFC 2047 in a header, e.g. `=?UTF-8?Q?Gr=C3=BC=C3=9Fe?=`.
+fn decode_header_value(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    let mut previous_was_encoded_word = false;
+
+    while let Some(start) = rest.find("=?") {
+        let before = &rest[..start];
+        let encoded_word = &rest[start + 2..];
+
+        match parse_encoded_word(encoded_word) {
+            Some((decoded, length)) => {
+                // Whitespace between two encoded words isn't part of the text.
+                if !(previous_was_encoded_word && before.trim().is_empty()) {
+                    out.push_str(before);
+                }
+                out.push_str(&decoded);
+                rest = &encoded_word[length..];
+                previous_was_encoded_word = true;
+            }
+            None => {
+                out.push_str(&rest[..start + 2]);
+                rest = encoded_word;
+                previous_was_encoded_word = false;
+            }
+        }
+    }
+    out.push_str(rest);
+
+    return out.trim().to_string();
+}
+
+/// Returns the decoded text and the length of the encoded word after its leading `=?`.
+fn parse_encoded_word(encoded_word: &str) -> Option<(String, usize)> {
+    let mut sections = encoded_word.splitn(3, '?');
+    let charset = sections.next()?;
+    let encoding = sections.next()?;
+    let remainder = sections.next()?;
+    let text_end = remainder.find("?=")?;
+    let text = &remainder[..text_end];
+
+    let decoded = match encoding {
+        "B" | "b" => decode_base64(text.as_bytes()),
+        "Q" | "q" => decode_quoted_printable(text.replace('_', " ").as_bytes()),
+        _ => return None,
+    };
+
+    let length = charset.len() + encoding.len() + 2 + text_end + 2;
+
+    return Some((decode_charset(&decoded, Some(charset)), length));
+}
+
+/// Converts a date like `Mon, 15 Apr 2024 09:30:00 +0200 (CEST)` to RFC 3339.
+fn parse_mail_date(value: &str) -> Option<String> {
+    let without_comment = match value.find('(') {
+        Some(v) => &value[..v],
+        None => value,
+    };
+
+    return chrono::DateTime::parse_from_rfc2822(without_comment.trim())
+        .ok()
+        .map(|v| v.to_rfc3339());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_if_mail_with_attachment_is_converted() {
+        let mail = b"From: =?UTF-8?Q?J=C3=BCrgen?= <juergen@example.com>\r
+To: team@example.com\r
+Subject: =?UTF-8?B?UXVhcnRhbHNiZXJpY2h0?=\r
+ Q1\r
+Date: Mon, 15 Apr 2024 09:30:00 +0200 (CEST)\r
+MIME-Version: 1.0\r
+Content-Type: multipart/mixed; boundary=\"outer\"\r
+\r
+--outer\r
+Content-Type: multipart/alternative; boundary=\"inner\"\r
+\r
+--inner\r
+Content-Type: text/plain; charset=utf-8\r
+Content-Transfer-Encoding: quoted-printable\r
+\r
+Anbei der Bericht f=C3=BCr das erste =\r
+Quartal.\r
+--inner\r
+Content-Type: text/html; charset=utf-8\r
+\r
+<p>Anbei der Bericht</p>\r
+--inner--\r
+--outer\r
+Content-Type: application/pdf; name=\"bericht.pdf\"\r
+Content-Disposition: attachment; filename*=UTF-8''Bericht%20Q1.pdf\r
+Content-Transfer-Encoding: base64\r
+\r
+JVBERi0xLjcK\r
+--outer--\r
+";
+
+        let result = MailConversion::new().convert(mail.to_vec()).unwrap();
+
+        assert_eq!(
+            result.contents,
+            "Quartalsbericht Q1\n\nAnbei der Bericht für das erste Quartal."
+        );
+        assert_eq!(
+            result.metadata,
+            vec![
+                (
+                    "from".to_string(),
+                    "Jürgen <juergen@example.com>".to_string()
+                ),
+                ("to".to_string(), "team@example.com".to_string()),
+                ("subject".to_string(), "Quartalsbericht Q1".to_string()),
+                (
+                    "sent_at".to_string(),
+                    "2024-04-15T09:30:00+02:00".to_string()
+                ),
+                ("attachments".to_string(), "Bericht Q1.pdf".to_string()),
+            ]
+        );
+        assert_eq!(result.embedded_files.len(), 1);
+        assert_eq!(result.embedded_files[0].contents, b"%PDF-1.7\n");
+    }
+
+    #[test]
+    fn test_if_deeply_nested_parts_are_skipped() {
+        let mut mail = "Subject: Nested\r\nContent-Type: text/plain\r\n\r\nToo deep".to_string();
+        for i in 0..=MAX_PART_DEPTH {
+            mail = format!(
+                "Content-Type: multipart/mixed; boundary=\"b{0}\"\r\n\r\n--b{0}\r\n{1}\r\n--b{0}--\r\n",
+                i, mail
+            );
+        }
+        mail = format!("Subject: Nested\r\n{}", mail);
+
+        let result = MailConversion::new().convert(mail.into_bytes()).unwrap();
+
+        assert_eq!(result.contents, "Nested\n\n");
+    }
+
+    #[test]
+    fn test_if_mbox_is_split_into_messages() {
+        let mbox = b"From alice@example.com Mon Apr 15 09:30:00 2024
+Subject: First
+
+>From the start
+line
+
+From bob@example.com Mon Apr 15 10:30:00 2024
+Subject: Second
+
+Hello
+";
+
+        let result = MboxConversion::new().convert(mbox.to_vec()).unwrap();
+
+        assert_eq!(result.embedded_files.len(), 2);
+        assert_eq!(result.embedded_files[0].name, "1.eml");
+        assert_eq!(
+            result.embedded_files[0].contents,
+            b"Subject: First\n\nFrom the start\nline\n\n"
+        );
+        assert_eq!(
+            result.embedded_files[1].contents,
+            b"Subject: Second\n\nHello\n"
+        );
+    }
+}
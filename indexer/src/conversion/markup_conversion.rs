@@ -0,0 +1,512 @@
+use crate::conversion::clear_text_conversion::decode_text;
+use crate::conversion::{Conversion, ConvertedDocument};
+use anyhow::Error;
+
+/// Elements whose contents are never shown as text.
+const SKIPPED_ELEMENTS: &[&str] = &["script", "style", "noscript", "template", "svg"];
+
+/// Elements that start a new line of text, everything else is rendered inline.
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "br",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "td",
+    "th",
+    "title",
+    "tr",
+    "ul",
+];
+
+/// Extracts the text of HTML and XML documents without their markup.
+pub(crate) struct MarkupConversion {
+    is_html: bool,
+}
+
+impl MarkupConversion {
+    pub(crate) fn html() -> Self {
+        return Self { is_html: true };
+    }
+
+    pub(crate) fn xml() -> Self {
+        return Self { is_html: false };
+    }
+}
+
+impl Conversion for MarkupConversion {
+    fn convert(&self, buf: Vec<u8>) -> Result<ConvertedDocument, Error> {
+        let (text, encoding) = decode_text(&buf);
+        let parsed = MarkupParser::new(&text, self.is_html).parse();
+
+        let mut converted_document = ConvertedDocument::new(parsed.contents)
+            .with_metadata("encoding", encoding.name().to_string());
+        if let Some(title) = parsed.title {
+            converted_document = converted_document.with_metadata("title", title);
+        }
+        if let Some(description) = parsed.description {
+            converted_document = converted_document.with_metadata("description", description);
+        }
+
+        return Ok(converted_document);
+    }
+}
+
+/// Used by other conversions for documents that embed HTML, e.g. mails.
+pub(crate) fn convert_html_to_text(text: &str) -> String {
+    return MarkupParser::new(text, true).parse().contents;
+}
+
+struct ParsedMarkup {
+    contents: String,
+    title: Option<String>,
+    description: Option<String>,
+}
+
+struct MarkupParser<'a> {
+    text: &'a str,
+    pos: usize,
+    is_html: bool,
+    out: String,
+    title: Option<String>,
+    description: Option<String>,
+    /// The start of the title in `out`, while the title element is open.
+    title_start: Option<usize>,
+    preformatted_depth: usize,
+}
+
+impl<'a> MarkupParser<'a> {
+    fn new(text: &'a str, is_html: bool) -> Self {
+        return Self {
+            text,
+            pos: 0,
+            is_html,
+            out: String::with_capacity(text.len() / 2),
+            title: None,
+            description: None,
+            title_start: None,
+            preformatted_depth: 0,
+        };
+    }
+
+    fn parse(mut self) -> ParsedMarkup {
+        while self.pos < self.text.len() {
+            let rest = &self.text[self.pos..];
+            let next_tag = rest.find('<').unwrap_or(rest.len());
+
+            self.push_text(&rest[..next_tag]);
+            self.pos += next_tag;
+
+            if self.pos < self.text.len() {
+                self.parse_markup();
+            }
+        }
+
+        return ParsedMarkup {
+            contents: self.out.trim().to_string(),
+            title: self.title,
+            description: self.description,
+        };
+    }
+
+    fn parse_markup(&mut self) {
+        let rest = &self.text[self.pos..];
+
+        if rest.starts_with("<!--") {
+            self.skip_past("-->");
+        } else if rest.starts_with("<![CDATA[") {
+            let end = rest.find("]]>").unwrap_or(rest.len());
+            let cdata = &rest["<![CDATA[".len().min(end)..end];
+            self.push_raw_text(cdata);
+            self.pos = (self.pos + end + "]]>".len()).min(self.text.len());
+        } else if rest.starts_with("<!") || rest.starts_with("<?") {
+            self.skip_past(">");
+        } else if rest[1..].starts_with(|c: char| c.is_alphabetic() || c == '/') {
+            self.parse_tag();
+        } else {
+            // A lonely `<` in text, which is common in sloppy HTML.
+            self.push_text("<");
+            self.pos += 1;
+        }
+    }
+
+    fn parse_tag(&mut self) {
+        let tag_end = match find_tag_end(&self.text[self.pos..]) {
+            Some(v) => self.pos + v,
+            None => self.text.len(),
+        };
+        let tag = &self.text[self.pos + 1..tag_end.min(self.text.len())];
+        self.pos = (tag_end + 1).min(self.text.len());
+
+        let is_closing = tag.starts_with('/');
+        let tag = tag.trim_start_matches('/');
+        let name_end = tag
+            .find(|c: char| c.is_whitespace() || c == '/' || c == '>')
+            .unwrap_or(tag.len());
+        let name = tag[..name_end].to_lowercase();
+        // Elements of XML namespaces, e.g. `<dc:title>`, are treated like their local name.
+        let local_name = name.rsplit(':').next().unwrap_or(&name).to_string();
+        let is_self_closing = tag.ends_with('/');
+
+        if !self.is_html {
+            self.push_separator(" ");
+            return;
+        }
+
+        if is_closing {
+            self.close_element(&local_name);
+            return;
+        }
+
+        if BLOCK_ELEMENTS.contains(&local_name.as_str()) {
+            self.push_separator("\n");
+        }
+
+        match local_name.as_str() {
+            _ if SKIPPED_ELEMENTS.contains(&local_name.as_str()) && !is_self_closing => {
+                self.skip_element(&name);
+            }
+            "title" if self.title.is_none() => self.title_start = Some(self.out.len()),
+            "pre" | "textarea" => self.preformatted_depth += 1,
+            "meta" => self.parse_meta(&tag[name_end..]),
+            "img" => {
+                if let Some(alt) = get_attribute(&tag[name_end..], "alt") {
+                    self.push_separator(" ");
+                    self.push_raw_text(&alt);
+                    self.push_separator(" ");
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn close_element(&mut self, name: &str) {
+        match name {
+            "title" => {
+                if let Some(title_start) = self.title_start.take() {
+                    let title = collapse_whitespace(&self.out[title_start..]);
+                    if !title.is_empty() {
+                        self.title = Some(title);
+                    }
+                }
+            }
+            "pre" | "textarea" => {
+                self.preformatted_depth = self.preformatted_depth.saturating_sub(1)
+            }
+            _ => {}
+        }
+
+        if BLOCK_ELEMENTS.contains(&name) {
+            self.push_separator("\n");
+        }
+    }
+
+    fn parse_meta(&mut self, attributes: &str) {
+        let name = get_attribute(attributes, "name")
+            .or_else(|| get_attribute(attributes, "property"))
+            .map(|v| v.to_lowercase());
+
+        if self.description.is_none()
+            && matches!(
+                name.as_deref(),
+                Some("description") | Some("og:description")
+            )
+        {
+            self.description = get_attribute(attributes, "content")
+                .map(|v| collapse_whitespace(&v))
+                .filter(|v| !v.is_empty());
+        }
+    }
+
+    /// Skips everything up to the closing tag, because scripts and styles may contain `<`.
+    fn skip_element(&mut self, name: &str) {
+        while let Some(v) = self.text[self.pos..].find("</") {
+            self.pos += v + 2;
+
+            let is_closing_tag = self
+                .text
+                .as_bytes()
+                .get(self.pos..self.pos + name.len())
+                .is_some_and(|v| v.eq_ignore_ascii_case(name.as_bytes()));
+            if is_closing_tag {
+                self.skip_past(">");
+                return;
+            }
+        }
+
+        self.pos = self.text.len();
+    }
+
+    fn skip_past(&mut self, end: &str) {
+        self.pos = match self.text[self.pos..].find(end) {
+            Some(v) => self.pos + v + end.len(),
+            None => self.text.len(),
+        };
+    }
+
+    fn push_text(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+
+        self.push_raw_text(&decode_entities(text));
+    }
+
+    fn push_raw_text(&mut self, text: &str) {
+        if self.preformatted_depth > 0 {
+            self.out.push_str(text);
+            return;
+        }
+
+        for c in text.chars() {
+            if c.is_whitespace() {
+                if !self.out.ends_with(char::is_whitespace) && !self.out.is_empty() {
+                    self.out.push(' ');
+                }
+            } else {
+                self.out.push(c);
+            }
+        }
+    }
+
+    /// Separates words of adjacent elements without stacking up blank lines.
+    fn push_separator(&mut self, separator: &str) {
+        if self.out.is_empty() || self.out.ends_with('\n') {
+            return;
+        }
+
+        if separator == "\n" && self.out.ends_with(' ') {
+            self.out.pop();
+        } else if self.out.ends_with(' ') {
+            return;
+        }
+
+        self.out.push_str(separator);
+    }
+}
+
+/// Finds the `>` that ends the tag, ignoring any inside of quoted attribute values.
+pub(crate) fn find_tag_end(text: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in text.char_indices().skip(1) {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            (None, '>') => return Some(i),
+            _ => {}
+        }
+    }
+
+    return None;
+}
+
+pub(crate) fn get_attribute(attributes: &str, name: &str) -> Option<String> {
+    let mut rest = attributes;
+    while let Some(position) = rest.find('=') {
+        let key = rest[..position]
+            .trim_end()
+            .rsplit(|c: char| c.is_whitespace() || c == '"' || c == '\'')
+            .next()
+            .unwrap_or_default();
+        let value_part = rest[position + 1..].trim_start();
+
+        let (value, remainder) = match value_part.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let end = value_part[1..]
+                    .find(quote)
+                    .map_or(value_part.len(), |v| v + 1);
+                (
+                    &value_part[1..end],
+                    &value_part[(end + 1).min(value_part.len())..],
+                )
+            }
+            _ => {
+                let end = value_part
+                    .find(|c: char| c.is_whitespace() || c == '/' || c == '>')
+                    .unwrap_or(value_part.len());
+                (&value_part[..end], &value_part[end..])
+            }
+        };
+
+        if key.eq_ignore_ascii_case(name) {
+            return Some(decode_entities(value));
+        }
+
+        rest = remainder;
+    }
+
+    return None;
+}
+
+pub(crate) fn collapse_whitespace(text: &str) -> String {
+    return text.split_whitespace().collect::<Vec<_>>().join(" ");
+}
+
+/// Decodes numeric character references and the named entities that are common in text.
+pub(crate) fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(position) = rest.find('&') {
+        out.push_str(&rest[..position]);
+        rest = &rest[position..];
+
+        let end = rest
+            .char_indices()
+            .skip(1)
+            .take(32)
+            .find(|(_, c)| !(c.is_ascii_alphanumeric() || *c == '#'))
+            .filter(|(_, c)| *c == ';');
+
+        let decoded = end.and_then(|(end, _)| decode_entity(&rest[1..end]).map(|v| (v, end)));
+        match decoded {
+            Some((c, end)) => {
+                out.push_str(&c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+
+    return out;
+}
+
+fn decode_entity(entity: &str) -> Option<String> {
+    if let Some(number) = entity.strip_prefix('#') {
+        let code_point = match number.strip_prefix(['x', 'X']) {
+            Some(v) => u32::from_str_radix(v, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+
+        return Some(char::from_u32(code_point).unwrap_or('\u{FFFD}').to_string());
+    }
+
+    let c = match entity {
+        "amp" => "&",
+        "lt" => "<",
+        "gt" => ">",
+        "quot" => "\"",
+        "apos" => "'",
+        "nbsp" => " ",
+        "shy" => "",
+        "copy" => "©",
+        "reg" => "®",
+        "trade" => "™",
+        "euro" => "€",
+        "pound" => "£",
+        "yen" => "¥",
+        "cent" => "¢",
+        "sect" => "§",
+        "para" => "¶",
+        "deg" => "°",
+        "plusmn" => "±",
+        "times" => "×",
+        "divide" => "÷",
+        "middot" => "·",
+        "bull" => "•",
+        "hellip" => "…",
+        "ndash" => "–",
+        "mdash" => "—",
+        "lsquo" => "‘",
+        "rsquo" => "’",
+        "sbquo" => "‚",
+        "ldquo" => "“",
+        "rdquo" => "”",
+        "bdquo" => "„",
+        "laquo" => "«",
+        "raquo" => "»",
+        "auml" => "ä",
+        "ouml" => "ö",
+        "uuml" => "ü",
+        "Auml" => "Ä",
+        "Ouml" => "Ö",
+        "Uuml" => "Ü",
+        "szlig" => "ß",
+        "aacute" => "á",
+        "agrave" => "à",
+        "acirc" => "â",
+        "eacute" => "é",
+        "egrave" => "è",
+        "ecirc" => "ê",
+        "iacute" => "í",
+        "oacute" => "ó",
+        "uacute" => "ú",
+        "ccedil" => "ç",
+        "ntilde" => "ñ",
+        "Eacute" => "É",
+        _ => return None,
+    };
+
+    return Some(c.to_string());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_if_html_is_converted_without_markup() {
+        let html = br#"<!DOCTYPE html>
+<html><head>
+<title>Caf&eacute; &amp; Bar</title>
+<meta name="description" content="Opening hours &amp; menu">
+<style>body { color: red; }</style>
+<script>if (a < b) { document.write("<p>nope</p>"); }</SCRIPT>
+</head>
+<body><h1>Welcome</h1><p>We serve <b>coffee</b>&nbsp;and&#32;<i>cake</i>.</p><!-- hidden --></body></html>"#;
+
+        let result = MarkupConversion::html().convert(html.to_vec()).unwrap();
+
+        assert_eq!(
+            result.contents,
+            "Café & Bar\nWelcome\nWe serve coffee and cake."
+        );
+        assert!(result
+            .metadata
+            .contains(&("title".to_string(), "Café & Bar".to_string())));
+        assert!(result.metadata.contains(&(
+            "description".to_string(),
+            "Opening hours & menu".to_string()
+        )));
+    }
+
+    #[test]
+    fn test_if_xml_elements_are_separated() {
+        let xml = br#"<?xml version="1.0"?><config><name>indexer</name><port>50051</port><![CDATA[a < b]]></config>"#;
+
+        let result = MarkupConversion::xml().convert(xml.to_vec()).unwrap();
+
+        assert_eq!(result.contents, "indexer 50051 a < b");
+    }
+}
@@ -0,0 +1,108 @@
+use crate::conversion::media::mp3_conversion::get_id3v2_length;
+use crate::conversion::media::{read_vorbis_comments, ByteReader, MediaTags};
+use crate::conversion::{Conversion, ConvertedDocument};
+use anyhow::{anyhow, Error};
+
+const BLOCK_TYPE_STREAMINFO: u8 = 0;
+const BLOCK_TYPE_VORBIS_COMMENT: u8 = 4;
+
+/// Reads the stream info and the Vorbis comments from the metadata blocks of a FLAC file.
+pub(crate) struct FlacConversion;
+
+impl FlacConversion {
+    pub(crate) fn new() -> Self {
+        return Self {};
+    }
+}
+
+impl Conversion for FlacConversion {
+    fn convert(&self, buf: Vec<u8>) -> Result<ConvertedDocument, Error> {
+        // Some taggers put an ID3v2 tag in front of the stream, although the format doesn't allow it.
+        let stream = buf.get(get_id3v2_length(&buf)..).unwrap_or_default();
+        if !stream.starts_with(b"fLaC") {
+            return Err(anyhow!("The file doesn't start with a FLAC stream marker."));
+        }
+
+        let mut tags = MediaTags::default();
+        tags.add_codec("flac");
+        read_metadata_blocks(&stream[4..], &mut tags);
+
+        return Ok(tags.into_converted_document());
+    }
+}
+
+/// Also used for FLAC streams in Ogg containers, whose first packet contains the stream info block.
+pub(crate) fn read_metadata_blocks(data: &[u8], tags: &mut MediaTags) -> Option<()> {
+    let mut reader = ByteReader::new(data);
+
+    loop {
+        let header = reader.read_u8()?;
+        let length_bytes = reader.read_bytes(3)?;
+        let length = (length_bytes[0] as usize) << 16
+            | (length_bytes[1] as usize) << 8
+            | length_bytes[2] as usize;
+        let block = reader.read_bytes(length)?;
+
+        match header & 0x7F {
+            BLOCK_TYPE_STREAMINFO => read_stream_info(block, tags),
+            BLOCK_TYPE_VORBIS_COMMENT => read_vorbis_comments(block, tags),
+            _ => {}
+        }
+
+        if header & 0x80 != 0 {
+            return Some(());
+        }
+    }
+}
+
+/// The sample rate has 20 bits and the total amount of samples 36 bits, starting at the 11th byte.
+fn read_stream_info(block: &[u8], tags: &mut MediaTags) {
+    let bits = match block.get(10..18) {
+        Some(v) => u64::from_be_bytes(v.try_into().unwrap_or_default()),
+        None => return,
+    };
+
+    let sample_rate = bits >> 44;
+    let total_samples = bits & 0xF_FFFF_FFFF;
+    if sample_rate > 0 && total_samples > 0 {
+        tags.duration_in_seconds = Some(total_samples as f64 / sample_rate as f64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_if_stream_info_and_comments_are_read() {
+        let mut stream_info = [0u8; 34];
+        // 44100 Hz and 441000 samples.
+        let bits: u64 = 44100 << 44 | 441000;
+        stream_info[10..18].copy_from_slice(&bits.to_be_bytes());
+
+        let comment = b"ALBUM=Substance";
+        let mut comments = Vec::new();
+        comments.extend_from_slice(&0u32.to_le_bytes());
+        comments.extend_from_slice(&1u32.to_le_bytes());
+        comments.extend_from_slice(&(comment.len() as u32).to_le_bytes());
+        comments.extend_from_slice(comment);
+
+        let mut flac = b"fLaC".to_vec();
+        flac.extend_from_slice(&[0x00, 0, 0, 34]);
+        flac.extend_from_slice(&stream_info);
+        flac.extend_from_slice(&[0x84, 0, 0, comments.len() as u8]);
+        flac.extend_from_slice(&comments);
+
+        let result = FlacConversion::new().convert(flac).unwrap();
+
+        assert_eq!(result.contents, "Substance");
+        assert_eq!(
+            result.metadata,
+            vec![
+                ("album".to_string(), "Substance".to_string()),
+                ("duration".to_string(), "10.000".to_string()),
+                ("codec".to_string(), "flac".to_string()),
+            ]
+        );
+    }
+}
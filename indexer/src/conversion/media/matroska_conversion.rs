@@ -0,0 +1,277 @@
+use crate::conversion::media::{ByteReader, MediaTags, MAX_CONTAINER_DEPTH};
+use crate::conversion::{Conversion, ConvertedDocument};
+use anyhow::{anyhow, Error};
+
+const ID_EBML: u32 = 0x1A45DFA3;
+const ID_SEGMENT: u32 = 0x18538067;
+const ID_INFO: u32 = 0x1549A966;
+const ID_TIMESTAMP_SCALE: u32 = 0x2AD7B1;
+const ID_DURATION: u32 = 0x4489;
+const ID_TITLE: u32 = 0x7BA9;
+const ID_TRACKS: u32 = 0x1654AE6B;
+const ID_TRACK_ENTRY: u32 = 0xAE;
+const ID_CODEC_ID: u32 = 0x86;
+const ID_CLUSTER: u32 = 0x1F43B675;
+const ID_TAGS: u32 = 0x1254C367;
+const ID_TAG: u32 = 0x7373;
+const ID_TARGETS: u32 = 0x63C0;
+const ID_TARGET_TYPE_VALUE: u32 = 0x68CA;
+const ID_SIMPLE_TAG: u32 = 0x67C8;
+const ID_TAG_NAME: u32 = 0x45A3;
+const ID_TAG_STRING: u32 = 0x4487;
+
+/// Tags with this target type or a higher one describe the album instead of the track.
+const TARGET_TYPE_ALBUM: u64 = 50;
+
+/// The default timestamp scale, which makes durations count in milliseconds.
+const DEFAULT_TIMESTAMP_SCALE: u64 = 1_000_000;
+
+/// Reads the segment info, the codecs of the tracks and the tags of Matroska and WebM files.
+pub(crate) struct MatroskaConversion;
+
+impl MatroskaConversion {
+    pub(crate) fn new() -> Self {
+        return Self {};
+    }
+}
+
+impl Conversion for MatroskaConversion {
+    fn convert(&self, buf: Vec<u8>) -> Result<ConvertedDocument, Error> {
+        if !buf.starts_with(&ID_EBML.to_be_bytes()) {
+            return Err(anyhow!("The file doesn't start with an EBML header."));
+        }
+
+        let mut tags = MediaTags::default();
+        let mut segment_info = SegmentInfo::default();
+        read_elements(&buf, &mut tags, &mut segment_info, 0);
+
+        if let Some(duration) = segment_info.duration {
+            let timestamp_scale = segment_info
+                .timestamp_scale
+                .unwrap_or(DEFAULT_TIMESTAMP_SCALE);
+            tags.duration_in_seconds = Some(duration * timestamp_scale as f64 / 1e9);
+        }
+
+        return Ok(tags.into_converted_document());
+    }
+}
+
+/// The duration is measured in units of the timestamp scale, which may appear after it.
+#[derive(Default)]
+struct SegmentInfo {
+    timestamp_scale: Option<u64>,
+    duration: Option<f64>,
+}
+
+fn read_elements(data: &[u8], tags: &mut MediaTags, segment_info: &mut SegmentInfo, depth: u32) {
+    if depth > MAX_CONTAINER_DEPTH {
+        return;
+    }
+
+    let mut reader = ByteReader::new(data);
+
+    while let Some((id, contents, has_unknown_size)) = read_element(&mut reader) {
+        match id {
+            ID_SEGMENT | ID_INFO | ID_TRACKS | ID_TRACK_ENTRY | ID_TAGS => {
+                read_elements(contents, tags, segment_info, depth + 1)
+            }
+            ID_TIMESTAMP_SCALE => segment_info.timestamp_scale = Some(read_unsigned(contents)),
+            ID_DURATION => segment_info.duration = read_float(contents),
+            ID_TITLE => tags.set_text("TITLE", &String::from_utf8_lossy(contents)),
+            ID_CODEC_ID => tags.add_codec(&map_codec_id(&String::from_utf8_lossy(contents))),
+            ID_TAG => read_tag(contents, tags),
+            // Clusters contain the media itself, the rest of a live stream can't be skipped without reading it.
+            ID_CLUSTER if has_unknown_size => return,
+            _ => {}
+        }
+    }
+}
+
+/// Reads the simple tags of a tag, whose meaning depends on the target type of the tag.
+fn read_tag(data: &[u8], tags: &mut MediaTags) {
+    let mut reader = ByteReader::new(data);
+    let mut target_type = 0;
+
+    while let Some((id, contents, _)) = read_element(&mut reader) {
+        match id {
+            ID_TARGETS => {
+                let mut targets_reader = ByteReader::new(contents);
+                while let Some((id, contents, _)) = read_element(&mut targets_reader) {
+                    if id == ID_TARGET_TYPE_VALUE {
+                        target_type = read_unsigned(contents);
+                    }
+                }
+            }
+            ID_SIMPLE_TAG => {
+                let mut simple_tag_reader = ByteReader::new(contents);
+                let mut name = None;
+                let mut value = None;
+                while let Some((id, contents, _)) = read_element(&mut simple_tag_reader) {
+                    match id {
+                        ID_TAG_NAME => name = Some(String::from_utf8_lossy(contents)),
+                        ID_TAG_STRING => value = Some(String::from_utf8_lossy(contents)),
+                        _ => {}
+                    }
+                }
+
+                if let (Some(name), Some(value)) = (name, value) {
+                    let name = match name.to_uppercase().as_str() {
+                        "TITLE" if target_type >= TARGET_TYPE_ALBUM => "ALBUM".to_string(),
+                        _ => name.to_string(),
+                    };
+                    tags.set_text(&name, &value);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Returns the id, the contents and whether the size is unknown, in which case the element extends to the end.
+fn read_element<'a>(reader: &mut ByteReader<'a>) -> Option<(u32, &'a [u8], bool)> {
+    let (id, _) = read_variable_size_integer(reader, true)?;
+    let (size, has_unknown_size) = read_variable_size_integer(reader, false)?;
+
+    let length = match has_unknown_size {
+        true => reader.remaining(),
+        false => usize::try_from(size).ok()?,
+    };
+
+    return Some((
+        u32::try_from(id).ok()?,
+        reader.read_bytes(length)?,
+        has_unknown_size,
+    ));
+}
+
+/// The amount of leading zeros of the first byte determines the length, the following marker bit is only
+/// part of the value for ids. A size with all bits set means that it is unknown.
+fn read_variable_size_integer(reader: &mut ByteReader, keep_marker: bool) -> Option<(u64, bool)> {
+    let first = reader.read_u8()?;
+    let length = first.leading_zeros() as usize + 1;
+    if length > 8 {
+        return None;
+    }
+
+    let mut value = match keep_marker {
+        true => first as u64,
+        false => first as u64 & (0xFF >> length),
+    };
+    for _ in 1..length {
+        value = value << 8 | reader.read_u8()? as u64;
+    }
+
+    let is_unknown = !keep_marker && value == (1 << (7 * length)) - 1;
+    return Some((value, is_unknown));
+}
+
+fn read_unsigned(contents: &[u8]) -> u64 {
+    return contents
+        .iter()
+        .take(8)
+        .fold(0, |value, byte| value << 8 | *byte as u64);
+}
+
+fn read_float(contents: &[u8]) -> Option<f64> {
+    return match contents.len() {
+        4 => Some(f32::from_be_bytes(contents.try_into().ok()?) as f64),
+        8 => Some(f64::from_be_bytes(contents.try_into().ok()?)),
+        _ => None,
+    };
+}
+
+fn map_codec_id(codec_id: &str) -> String {
+    let codec_id = codec_id.trim_matches(char::from(0));
+    let codec = match codec_id {
+        "V_MPEG4/ISO/AVC" => "h264",
+        "V_MPEGH/ISO/HEVC" => "hevc",
+        "V_VP8" => "vp8",
+        "V_VP9" => "vp9",
+        "V_AV1" => "av1",
+        "A_OPUS" => "opus",
+        "A_VORBIS" => "vorbis",
+        "A_FLAC" => "flac",
+        "A_MPEG/L3" => "mp3",
+        "A_AC3" => "ac3",
+        "A_EAC3" => "eac3",
+        "A_DTS" => "dts",
+        _ if codec_id.starts_with("A_AAC") => "aac",
+        _ if codec_id.starts_with("A_PCM") => "pcm",
+        // Subtitles also have tracks, but aren't relevant.
+        _ if codec_id.starts_with("S_") => "",
+        _ => return codec_id.to_lowercase(),
+    };
+
+    return codec.to_string();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_element(id: u32, contents: &[u8]) -> Vec<u8> {
+        let mut result = id
+            .to_be_bytes()
+            .into_iter()
+            .skip_while(|v| *v == 0)
+            .collect::<Vec<_>>();
+        result.push(0x01);
+        result.extend_from_slice(&(contents.len() as u64).to_be_bytes()[1..]);
+        result.extend_from_slice(contents);
+
+        return result;
+    }
+
+    #[test]
+    fn test_if_info_tracks_and_tags_are_read() {
+        let mut info = create_element(ID_TIMESTAMP_SCALE, &[0x0F, 0x42, 0x40]);
+        info.extend(create_element(ID_DURATION, &12345.0f32.to_be_bytes()));
+        info.extend(create_element(ID_TITLE, b"Concert"));
+
+        let mut tracks = create_element(ID_TRACK_ENTRY, &create_element(ID_CODEC_ID, b"V_VP9"));
+        tracks.extend(create_element(
+            ID_TRACK_ENTRY,
+            &create_element(ID_CODEC_ID, b"A_OPUS"),
+        ));
+
+        let mut simple_tag = create_element(ID_TAG_NAME, b"TITLE");
+        simple_tag.extend(create_element(ID_TAG_STRING, b"Live at Wembley"));
+        let mut tag = create_element(ID_TARGETS, &create_element(ID_TARGET_TYPE_VALUE, &[50]));
+        tag.extend(create_element(ID_SIMPLE_TAG, &simple_tag));
+
+        let mut segment = create_element(ID_INFO, &info);
+        segment.extend(create_element(ID_TRACKS, &tracks));
+        segment.extend(create_element(ID_TAGS, &create_element(ID_TAG, &tag)));
+
+        let mut matroska = create_element(ID_EBML, &create_element(0x4282, b"webm"));
+        matroska.extend(create_element(ID_SEGMENT, &segment));
+
+        let result = MatroskaConversion::new().convert(matroska).unwrap();
+
+        assert_eq!(
+            result.metadata,
+            vec![
+                ("title".to_string(), "Concert".to_string()),
+                ("album".to_string(), "Live at Wembley".to_string()),
+                ("duration".to_string(), "12.345".to_string()),
+                ("codec".to_string(), "vp9".to_string()),
+                ("codec".to_string(), "opus".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_if_deeply_nested_elements_are_skipped() {
+        let mut segment = create_element(ID_INFO, &create_element(ID_TITLE, b"Too Deep"));
+        for _ in 0..MAX_CONTAINER_DEPTH {
+            segment = create_element(ID_SEGMENT, &segment);
+        }
+
+        let mut matroska = create_element(ID_EBML, &create_element(0x4282, b"webm"));
+        matroska.extend(create_element(ID_SEGMENT, &segment));
+
+        let result = MatroskaConversion::new().convert(matroska).unwrap();
+
+        assert!(result.metadata.is_empty());
+    }
+}
@@ -0,0 +1,196 @@
+pub(crate) mod flac_conversion;
+pub(crate) mod matroska_conversion;
+pub(crate) mod mp3_conversion;
+pub(crate) mod mp4_conversion;
+pub(crate) mod ogg_conversion;
+
+use crate::conversion::ConvertedDocument;
+
+/// Containers of MP4 and Matroska files are only nested a few levels, deeper ones are skipped so a crafted file can't
+/// exhaust the stack.
+pub(crate) const MAX_CONTAINER_DEPTH: u32 = 16;
+
+/// The tags of an audio or video file. Only the metadata is parsed, the media itself is never decoded.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct MediaTags {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub year: Option<u32>,
+    pub duration_in_seconds: Option<f64>,
+    /// The codecs of all tracks, e.g. `h264` and `aac` for a common video.
+    pub codecs: Vec<String>,
+}
+
+impl MediaTags {
+    /// Sets the value of a tag that is stored as text, unless the tag was already set.
+    pub(crate) fn set_text(&mut self, name: &str, value: &str) {
+        let value = value.trim_matches(char::from(0)).trim();
+        if value.is_empty() {
+            return;
+        }
+
+        let target = match name.to_uppercase().as_str() {
+            "TITLE" => &mut self.title,
+            "ARTIST" => &mut self.artist,
+            "ALBUM" => &mut self.album,
+            "DATE" | "YEAR" | "DATE_RELEASED" | "DATE_RECORDED" => {
+                if self.year.is_none() {
+                    self.year = parse_year(value);
+                }
+                return;
+            }
+            _ => return,
+        };
+
+        if target.is_none() {
+            *target = Some(value.to_string());
+        }
+    }
+
+    pub(crate) fn add_codec(&mut self, codec: &str) {
+        if !codec.is_empty() && !self.codecs.iter().any(|v| v == codec) {
+            self.codecs.push(codec.to_string());
+        }
+    }
+
+    pub(crate) fn into_converted_document(self) -> ConvertedDocument {
+        let contents = [&self.title, &self.artist, &self.album]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let mut converted_document = ConvertedDocument::new(contents);
+        let metadata = [
+            ("title", self.title),
+            ("artist", self.artist),
+            ("album", self.album),
+            ("year", self.year.map(|v| v.to_string())),
+            (
+                "duration",
+                self.duration_in_seconds
+                    .filter(|v| v.is_finite() && *v >= 0.0)
+                    .map(|v| format!("{:.3}", v)),
+            ),
+        ];
+        for (field_name, value) in metadata {
+            if let Some(value) = value {
+                converted_document = converted_document.with_metadata(field_name, value);
+            }
+        }
+        for codec in self.codecs {
+            converted_document = converted_document.with_metadata("codec", codec);
+        }
+
+        return converted_document;
+    }
+}
+
+/// Dates are usually just a year or start with one, e.g. `2023` or `2023-04-15`.
+fn parse_year(value: &str) -> Option<u32> {
+    let digits = value.trim().get(0..4)?;
+    if !digits.bytes().all(|v| v.is_ascii_digit()) {
+        return None;
+    }
+
+    return digits.parse().ok().filter(|v| *v > 0);
+}
+
+/// Reads the comments of Vorbis, Opus and FLAC files, which are `KEY=value` pairs prefixed by their length.
+pub(crate) fn read_vorbis_comments(data: &[u8], tags: &mut MediaTags) {
+    let mut reader = ByteReader::new(data);
+    let read_comments = |reader: &mut ByteReader, tags: &mut MediaTags| -> Option<()> {
+        let vendor_length = reader.read_u32_le()? as usize;
+        reader.skip(vendor_length)?;
+
+        let count = reader.read_u32_le()?;
+        for _ in 0..count {
+            let length = reader.read_u32_le()? as usize;
+            let comment = String::from_utf8_lossy(reader.read_bytes(length)?);
+            if let Some((name, value)) = comment.split_once('=') {
+                tags.set_text(name, value);
+            }
+        }
+
+        return Some(());
+    };
+
+    let _ = read_comments(&mut reader, tags);
+}
+
+/// Reads numbers and slices from a buffer, returning `None` instead of panicking at its end.
+pub(crate) struct ByteReader<'a> {
+    buf: &'a [u8],
+    pub position: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    pub(crate) fn new(buf: &'a [u8]) -> Self {
+        return Self { buf, position: 0 };
+    }
+
+    pub(crate) fn remaining(&self) -> usize {
+        return self.buf.len().saturating_sub(self.position);
+    }
+
+    pub(crate) fn read_bytes(&mut self, length: usize) -> Option<&'a [u8]> {
+        let bytes = self
+            .buf
+            .get(self.position..self.position.checked_add(length)?)?;
+        self.position += length;
+
+        return Some(bytes);
+    }
+
+    pub(crate) fn skip(&mut self, length: usize) -> Option<()> {
+        self.read_bytes(length)?;
+
+        return Some(());
+    }
+
+    pub(crate) fn read_u8(&mut self) -> Option<u8> {
+        return Some(self.read_bytes(1)?[0]);
+    }
+
+    pub(crate) fn read_u16_be(&mut self) -> Option<u16> {
+        return Some(u16::from_be_bytes(self.read_bytes(2)?.try_into().ok()?));
+    }
+
+    pub(crate) fn read_u32_be(&mut self) -> Option<u32> {
+        return Some(u32::from_be_bytes(self.read_bytes(4)?.try_into().ok()?));
+    }
+
+    pub(crate) fn read_u64_be(&mut self) -> Option<u64> {
+        return Some(u64::from_be_bytes(self.read_bytes(8)?.try_into().ok()?));
+    }
+
+    pub(crate) fn read_u32_le(&mut self) -> Option<u32> {
+        return Some(u32::from_le_bytes(self.read_bytes(4)?.try_into().ok()?));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_if_vorbis_comments_are_read() {
+        let mut data = Vec::new();
+        data.extend_from_slice(&6u32.to_le_bytes());
+        data.extend_from_slice(b"vendor");
+        data.extend_from_slice(&3u32.to_le_bytes());
+        for comment in ["title=Blue Monday", "ARTIST=New Order", "DATE=1983-03-07"] {
+            data.extend_from_slice(&(comment.len() as u32).to_le_bytes());
+            data.extend_from_slice(comment.as_bytes());
+        }
+
+        let mut tags = MediaTags::default();
+        read_vorbis_comments(&data, &mut tags);
+
+        assert_eq!(tags.title, Some("Blue Monday".to_string()));
+        assert_eq!(tags.artist, Some("New Order".to_string()));
+        assert_eq!(tags.year, Some(1983));
+    }
+}
@@ -0,0 +1,384 @@
+use crate::conversion::media::{ByteReader, MediaTags};
+use crate::conversion::{Conversion, ConvertedDocument};
+use anyhow::{anyhow, Error};
+use encoding_rs::{UTF_16BE, UTF_16LE, WINDOWS_1252};
+
+/// Bitrates in kbit/s by bitrate index for MPEG-1 layer 1, 2 and 3, and MPEG-2/2.5 layer 1, and layer 2 and 3.
+const BITRATES: [[u32; 15]; 5] = [
+    [
+        0, 32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448,
+    ],
+    [
+        0, 32, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384,
+    ],
+    [
+        0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320,
+    ],
+    [
+        0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256,
+    ],
+    [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160],
+];
+
+const SAMPLE_RATES: [u32; 3] = [44100, 48000, 32000];
+
+/// Reads the ID3v2 and ID3v1 tags of MPEG audio files and calculates the duration from the first frame.
+pub(crate) struct Mp3Conversion;
+
+impl Mp3Conversion {
+    pub(crate) fn new() -> Self {
+        return Self {};
+    }
+}
+
+impl Conversion for Mp3Conversion {
+    fn convert(&self, buf: Vec<u8>) -> Result<ConvertedDocument, Error> {
+        let mut tags = MediaTags::default();
+
+        let id3v2_length = read_id3v2(&buf, &mut tags).unwrap_or(0);
+        let id3v1_length = read_id3v1(&buf, &mut tags);
+
+        let audio_end = buf.len() - id3v1_length;
+        let audio = &buf[id3v2_length.min(audio_end)..audio_end];
+        match read_first_frame(audio) {
+            Some(frame) => {
+                tags.add_codec(frame.codec);
+                if tags.duration_in_seconds.is_none() {
+                    tags.duration_in_seconds = Some(frame.duration_in_seconds);
+                }
+            }
+            None if tags == MediaTags::default() => {
+                return Err(anyhow!(
+                    "The file contains neither ID3 tags nor MPEG audio frames."
+                ));
+            }
+            None => {}
+        }
+
+        return Ok(tags.into_converted_document());
+    }
+}
+
+/// Returns the length of the tag, including its header.
+fn read_id3v2(buf: &[u8], tags: &mut MediaTags) -> Option<usize> {
+    if !buf.starts_with(b"ID3") {
+        return None;
+    }
+
+    let major_version = *buf.get(3)?;
+    let flags = *buf.get(5)?;
+    let size = read_syncsafe(buf.get(6..10)?);
+    let tag_length = 10 + size;
+
+    let mut data = buf.get(10..tag_length.min(buf.len()))?.to_vec();
+    if major_version < 4 && flags & 0x80 != 0 {
+        data = remove_unsynchronisation(&data);
+    }
+
+    let mut reader = ByteReader::new(&data);
+    if flags & 0x40 != 0 {
+        // The extended header only contains information about the tag itself.
+        let extended_header_size = reader.read_bytes(4)?;
+        let extended_header_size = match major_version {
+            4 => read_syncsafe(extended_header_size).saturating_sub(4),
+            _ => u32::from_be_bytes(extended_header_size.try_into().ok()?) as usize,
+        };
+        reader.skip(extended_header_size)?;
+    }
+
+    let id_length = if major_version == 2 { 3 } else { 4 };
+    while reader.remaining() > id_length * 2 {
+        let id = reader.read_bytes(id_length)?;
+        if id[0] == 0 {
+            // The rest of the tag is padding.
+            break;
+        }
+
+        let (frame_size, frame_flags) = match major_version {
+            2 => {
+                let size = reader.read_bytes(3)?;
+                (
+                    (size[0] as usize) << 16 | (size[1] as usize) << 8 | size[2] as usize,
+                    0,
+                )
+            }
+            3 => (reader.read_u32_be()? as usize, reader.read_u16_be()?),
+            _ => (read_syncsafe(reader.read_bytes(4)?), reader.read_u16_be()?),
+        };
+        let mut frame = reader.read_bytes(frame_size)?.to_vec();
+
+        if major_version == 4 {
+            if frame_flags & 0x0002 != 0 {
+                frame = remove_unsynchronisation(&frame);
+            }
+            if frame_flags & 0x0001 != 0 {
+                frame = frame.get(4..)?.to_vec();
+            }
+        }
+        // Compressed and encrypted frames are skipped.
+        let is_unreadable = match major_version {
+            3 => frame_flags & 0x00C0 != 0,
+            4 => frame_flags & 0x000C != 0,
+            _ => false,
+        };
+        if is_unreadable {
+            continue;
+        }
+
+        let name = match id {
+            b"TIT2" | b"TT2" => "TITLE",
+            b"TPE1" | b"TP1" => "ARTIST",
+            b"TALB" | b"TAL" => "ALBUM",
+            b"TYER" | b"TYE" | b"TDRC" | b"TDOR" => "YEAR",
+            b"TLEN" | b"TLE" => {
+                tags.duration_in_seconds = decode_text_frame(&frame)
+                    .and_then(|v| v.trim().parse::<f64>().ok())
+                    .map(|v| v / 1000.0);
+                continue;
+            }
+            _ => continue,
+        };
+
+        if let Some(value) = decode_text_frame(&frame) {
+            tags.set_text(name, &value);
+        }
+    }
+
+    return Some(tag_length);
+}
+
+/// Returns the length of an ID3v2 tag at the start of the buffer, which is 0 if there is none.
+pub(crate) fn get_id3v2_length(buf: &[u8]) -> usize {
+    if !buf.starts_with(b"ID3") {
+        return 0;
+    }
+
+    return match buf.get(6..10) {
+        Some(v) => 10 + read_syncsafe(v),
+        None => 0,
+    };
+}
+
+/// Returns the length of the tag, which is 0 if there is none.
+fn read_id3v1(buf: &[u8], tags: &mut MediaTags) -> usize {
+    if buf.len() < 128 || &buf[buf.len() - 128..buf.len() - 125] != b"TAG" {
+        return 0;
+    }
+
+    let tag = &buf[buf.len() - 128..];
+    let read_field = |start: usize, end: usize| -> String {
+        let field = &tag[start..end];
+        let field = &field[..field.iter().position(|v| *v == 0).unwrap_or(field.len())];
+        return WINDOWS_1252
+            .decode_without_bom_handling(field)
+            .0
+            .into_owned();
+    };
+
+    tags.set_text("TITLE", &read_field(3, 33));
+    tags.set_text("ARTIST", &read_field(33, 63));
+    tags.set_text("ALBUM", &read_field(63, 93));
+    tags.set_text("YEAR", &read_field(93, 97));
+
+    return 128;
+}
+
+/// Text frames start with their encoding and may contain several values separated by null characters, of which
+/// only the first one is used.
+fn decode_text_frame(frame: &[u8]) -> Option<String> {
+    let (encoding, text) = frame.split_first()?;
+
+    let value = match encoding {
+        1 => match text {
+            [0xFF, 0xFE, rest @ ..] => UTF_16LE.decode_without_bom_handling(rest).0,
+            [0xFE, 0xFF, rest @ ..] => UTF_16BE.decode_without_bom_handling(rest).0,
+            _ => UTF_16LE.decode_without_bom_handling(text).0,
+        },
+        2 => UTF_16BE.decode_without_bom_handling(text).0,
+        3 => String::from_utf8_lossy(text),
+        _ => WINDOWS_1252.decode_without_bom_handling(text).0,
+    };
+
+    return value
+        .split('\0')
+        .map(|v| v.trim())
+        .find(|v| !v.is_empty())
+        .map(|v| v.to_string());
+}
+
+fn read_syncsafe(bytes: &[u8]) -> usize {
+    return bytes
+        .iter()
+        .fold(0, |size, byte| size << 7 | (*byte & 0x7F) as usize);
+}
+
+/// Unsynchronisation inserts a null byte after every 0xFF, so the tag can't be mistaken for an audio frame.
+fn remove_unsynchronisation(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    for (i, byte) in data.iter().enumerate() {
+        if *byte == 0 && i > 0 && data[i - 1] == 0xFF {
+            continue;
+        }
+        out.push(*byte);
+    }
+
+    return out;
+}
+
+struct Frame {
+    codec: &'static str,
+    duration_in_seconds: f64,
+}
+
+/// Variable bitrate files announce their frame count in a Xing or VBRI header inside the first frame, otherwise the
+/// duration is calculated from the bitrate.
+fn read_first_frame(audio: &[u8]) -> Option<Frame> {
+    let start = (0..audio.len().saturating_sub(4))
+        .take(64 * 1024)
+        .find(|i| parse_frame_header(&audio[*i..]).is_some())?;
+    let frame = &audio[start..];
+    let header = parse_frame_header(frame)?;
+
+    let xing_offset = 4 + header.side_info_length;
+    let frame_count = if matches!(
+        frame.get(xing_offset..xing_offset + 4),
+        Some(b"Xing" | b"Info")
+    ) {
+        let mut reader = ByteReader::new(&frame[xing_offset + 4..]);
+        let flags = reader.read_u32_be()?;
+        if flags & 0x01 != 0 {
+            reader.read_u32_be()
+        } else {
+            None
+        }
+    } else if frame.get(36..40) == Some(b"VBRI") {
+        ByteReader::new(frame.get(50..54)?).read_u32_be()
+    } else {
+        None
+    };
+
+    let duration_in_seconds = match frame_count {
+        Some(v) => v as f64 * header.samples_per_frame as f64 / header.sample_rate as f64,
+        None => (audio.len() - start) as f64 * 8.0 / (header.bitrate as f64 * 1000.0),
+    };
+
+    return Some(Frame {
+        codec: header.codec,
+        duration_in_seconds,
+    });
+}
+
+/// Files without an ID3 tag are recognized by the header of their first frame.
+pub(crate) fn is_mpeg_audio_frame(bytes: &[u8]) -> bool {
+    return parse_frame_header(bytes).is_some();
+}
+
+struct FrameHeader {
+    codec: &'static str,
+    bitrate: u32,
+    sample_rate: u32,
+    samples_per_frame: u32,
+    side_info_length: usize,
+}
+
+fn parse_frame_header(bytes: &[u8]) -> Option<FrameHeader> {
+    let header = u32::from_be_bytes(bytes.get(0..4)?.try_into().ok()?);
+    if header & 0xFFE0_0000 != 0xFFE0_0000 {
+        return None;
+    }
+
+    // 3 is MPEG-1, 2 is MPEG-2 and 0 is MPEG-2.5.
+    let version = (header >> 19) & 0x3;
+    let layer = match (header >> 17) & 0x3 {
+        3 => 1,
+        2 => 2,
+        1 => 3,
+        _ => return None,
+    };
+    let bitrate_index = ((header >> 12) & 0xF) as usize;
+    let sample_rate_index = ((header >> 10) & 0x3) as usize;
+    if version == 1 || bitrate_index == 0 || bitrate_index == 15 || sample_rate_index == 3 {
+        return None;
+    }
+
+    let is_mpeg1 = version == 3;
+    let bitrates = match (is_mpeg1, layer) {
+        (true, _) => &BITRATES[layer - 1],
+        (false, 1) => &BITRATES[3],
+        (false, _) => &BITRATES[4],
+    };
+    let sample_rate = SAMPLE_RATES[sample_rate_index]
+        >> match version {
+            3 => 0,
+            2 => 1,
+            _ => 2,
+        };
+    let samples_per_frame = match (layer, is_mpeg1) {
+        (1, _) => 384,
+        (3, false) => 576,
+        _ => 1152,
+    };
+    let is_mono = (header >> 6) & 0x3 == 3;
+    let side_info_length = match (is_mpeg1, is_mono) {
+        (true, false) => 32,
+        (true, true) | (false, false) => 17,
+        (false, true) => 9,
+    };
+
+    return Some(FrameHeader {
+        codec: match layer {
+            1 => "mp1",
+            2 => "mp2",
+            _ => "mp3",
+        },
+        bitrate: bitrates[bitrate_index],
+        sample_rate,
+        samples_per_frame,
+        side_info_length,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_if_id3_tags_and_duration_are_read() {
+        let mut frames = Vec::new();
+        for (id, text) in [(b"TIT2", "Blue Monday"), (b"TPE1", "New Order")] {
+            frames.extend_from_slice(id);
+            frames.extend_from_slice(&(text.len() as u32 + 1).to_be_bytes());
+            frames.extend_from_slice(&[0, 0, 3]);
+            frames.extend_from_slice(text.as_bytes());
+        }
+
+        let mut mp3 = b"ID3\x03\x00\x00".to_vec();
+        mp3.extend_from_slice(&[0, 0, 0, frames.len() as u8]);
+        mp3.extend_from_slice(&frames);
+        // Two seconds of MPEG-1 layer 3 at 128 kbit/s.
+        for _ in 0..32 {
+            let mut frame = vec![0; 1000];
+            frame[0..4].copy_from_slice(&[0xFF, 0xFB, 0x90, 0x00]);
+            mp3.extend_from_slice(&frame);
+        }
+        let mut id3v1 = [0u8; 128];
+        id3v1[0..3].copy_from_slice(b"TAG");
+        id3v1[63..70].copy_from_slice(b"Power, ");
+        id3v1[93..97].copy_from_slice(b"1983");
+        mp3.extend_from_slice(&id3v1);
+
+        let result = Mp3Conversion::new().convert(mp3).unwrap();
+
+        assert_eq!(
+            result.metadata,
+            vec![
+                ("title".to_string(), "Blue Monday".to_string()),
+                ("artist".to_string(), "New Order".to_string()),
+                ("album".to_string(), "Power,".to_string()),
+                ("year".to_string(), "1983".to_string()),
+                ("duration".to_string(), "2.000".to_string()),
+                ("codec".to_string(), "mp3".to_string()),
+            ]
+        );
+    }
+}
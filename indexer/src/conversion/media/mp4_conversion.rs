@@ -0,0 +1,220 @@
+use crate::conversion::media::{ByteReader, MediaTags, MAX_CONTAINER_DEPTH};
+use crate::conversion::{Conversion, ConvertedDocument};
+use anyhow::{anyhow, Error};
+
+/// Boxes that only contain other boxes and lead to the movie header, the tracks or the iTunes tags.
+const CONTAINER_BOXES: [&[u8; 4]; 7] = [
+    b"moov", b"trak", b"mdia", b"minf", b"stbl", b"udta", b"ilst",
+];
+
+/// Reads the movie header, the sample descriptions of the tracks and the iTunes tags of MP4 and M4A files.
+pub(crate) struct Mp4Conversion;
+
+impl Mp4Conversion {
+    pub(crate) fn new() -> Self {
+        return Self {};
+    }
+}
+
+impl Conversion for Mp4Conversion {
+    fn convert(&self, buf: Vec<u8>) -> Result<ConvertedDocument, Error> {
+        if buf.get(4..8) != Some(&b"ftyp"[..]) {
+            return Err(anyhow!("The file doesn't start with a file type box."));
+        }
+
+        let mut tags = MediaTags::default();
+        read_boxes(&buf, &mut tags, 0);
+
+        return Ok(tags.into_converted_document());
+    }
+}
+
+fn read_boxes(data: &[u8], tags: &mut MediaTags, depth: u32) {
+    if depth > MAX_CONTAINER_DEPTH {
+        return;
+    }
+
+    let mut reader = ByteReader::new(data);
+
+    while let Some((box_type, contents)) = read_box(&mut reader) {
+        match box_type {
+            // The meta box is a full box, which has a version and flags before its children.
+            b"meta" => read_boxes(contents.get(4..).unwrap_or_default(), tags, depth + 1),
+            _ if CONTAINER_BOXES.contains(&box_type) => read_boxes(contents, tags, depth + 1),
+            b"mvhd" => read_movie_header(contents, tags),
+            b"stsd" => read_sample_description(contents, tags),
+            b"\xA9nam" => set_item(contents, "TITLE", tags),
+            b"\xA9ART" | b"aART" => set_item(contents, "ARTIST", tags),
+            b"\xA9alb" => set_item(contents, "ALBUM", tags),
+            b"\xA9day" => set_item(contents, "DATE", tags),
+            _ => {}
+        }
+    }
+}
+
+fn read_box<'a>(reader: &mut ByteReader<'a>) -> Option<(&'a [u8; 4], &'a [u8])> {
+    let size = reader.read_u32_be()? as usize;
+    let box_type = reader.read_bytes(4)?.try_into().ok()?;
+
+    let contents = match size {
+        // The box extends to the end of the file.
+        0 => reader.read_bytes(reader.remaining())?,
+        1 => {
+            let size = usize::try_from(reader.read_u64_be()?).ok()?;
+            reader.read_bytes(size.checked_sub(16)?)?
+        }
+        _ => reader.read_bytes(size.checked_sub(8)?)?,
+    };
+
+    return Some((box_type, contents));
+}
+
+/// The timescale is the amount of time units per second, in which the duration is measured.
+fn read_movie_header(contents: &[u8], tags: &mut MediaTags) {
+    let mut reader = ByteReader::new(contents);
+    let read_duration = |reader: &mut ByteReader| -> Option<(u32, u64)> {
+        let version = reader.read_u8()?;
+        reader.skip(3)?;
+
+        if version == 1 {
+            reader.skip(16)?;
+            let timescale = reader.read_u32_be()?;
+            return Some((timescale, reader.read_u64_be()?));
+        }
+
+        reader.skip(8)?;
+        let timescale = reader.read_u32_be()?;
+        return Some((timescale, reader.read_u32_be()? as u64));
+    };
+
+    if let Some((timescale, duration)) = read_duration(&mut reader) {
+        // A duration with all bits set means that it is unknown.
+        if timescale > 0 && duration != u32::MAX as u64 && duration != u64::MAX {
+            tags.duration_in_seconds = Some(duration as f64 / timescale as f64);
+        }
+    }
+}
+
+/// The format of the first sample entry identifies the codec of the track.
+fn read_sample_description(contents: &[u8], tags: &mut MediaTags) {
+    let format = match contents.get(12..16) {
+        Some(v) => v,
+        None => return,
+    };
+
+    let codec = match format {
+        b"mp4a" => "aac".to_string(),
+        b"avc1" | b"avc3" => "h264".to_string(),
+        b"hvc1" | b"hev1" => "hevc".to_string(),
+        b"av01" => "av1".to_string(),
+        b"vp08" => "vp8".to_string(),
+        b"vp09" => "vp9".to_string(),
+        b"mp4v" => "mpeg4".to_string(),
+        b"Opus" => "opus".to_string(),
+        b"fLaC" => "flac".to_string(),
+        b"ac-3" => "ac3".to_string(),
+        b"ec-3" => "eac3".to_string(),
+        b".mp3" => "mp3".to_string(),
+        // Subtitles and chapters also have tracks, but aren't relevant.
+        b"text" | b"tx3g" | b"wvtt" | b"c608" => return,
+        _ => String::from_utf8_lossy(format).trim().to_lowercase(),
+    };
+
+    tags.add_codec(&codec);
+}
+
+/// The value of an iTunes item is stored in a data box, after its type indicator and locale.
+fn set_item(contents: &[u8], name: &str, tags: &mut MediaTags) {
+    let mut reader = ByteReader::new(contents);
+    while let Some((box_type, data)) = read_box(&mut reader) {
+        if box_type == b"data" {
+            if let Some(value) = data.get(8..) {
+                tags.set_text(name, &String::from_utf8_lossy(value));
+            }
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_box(box_type: &[u8; 4], contents: &[u8]) -> Vec<u8> {
+        let mut result = ((contents.len() + 8) as u32).to_be_bytes().to_vec();
+        result.extend_from_slice(box_type);
+        result.extend_from_slice(contents);
+
+        return result;
+    }
+
+    #[test]
+    fn test_if_movie_header_tracks_and_items_are_read() {
+        let mut movie_header = vec![0; 12];
+        movie_header.extend_from_slice(&1000u32.to_be_bytes());
+        movie_header.extend_from_slice(&90500u32.to_be_bytes());
+        movie_header.extend_from_slice(&[0; 80]);
+
+        let mut sample_description = vec![0, 0, 0, 0, 0, 0, 0, 1];
+        sample_description.extend(create_box(b"avc1", &[0; 8]));
+        let track = create_box(
+            b"trak",
+            &create_box(
+                b"mdia",
+                &create_box(
+                    b"minf",
+                    &create_box(b"stbl", &create_box(b"stsd", &sample_description)),
+                ),
+            ),
+        );
+
+        let mut data = vec![0, 0, 0, 1, 0, 0, 0, 0];
+        data.extend_from_slice(b"Holiday Video");
+        let mut meta = vec![0; 4];
+        meta.extend(create_box(
+            b"ilst",
+            &create_box(b"\xA9nam", &create_box(b"data", &data)),
+        ));
+
+        let mut moov = create_box(b"mvhd", &movie_header);
+        moov.extend(track);
+        moov.extend(create_box(b"udta", &create_box(b"meta", &meta)));
+
+        let mut mp4 = create_box(b"ftyp", b"isom\0\0\0\0");
+        mp4.extend(create_box(b"moov", &moov));
+
+        let result = Mp4Conversion::new().convert(mp4).unwrap();
+
+        assert_eq!(
+            result.metadata,
+            vec![
+                ("title".to_string(), "Holiday Video".to_string()),
+                ("duration".to_string(), "90.500".to_string()),
+                ("codec".to_string(), "h264".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_if_deeply_nested_boxes_are_skipped() {
+        let mut data = vec![0, 0, 0, 1, 0, 0, 0, 0];
+        data.extend_from_slice(b"Too Deep");
+        let mut udta = create_box(
+            b"udta",
+            &create_box(
+                b"ilst",
+                &create_box(b"\xA9nam", &create_box(b"data", &data)),
+            ),
+        );
+        for _ in 0..MAX_CONTAINER_DEPTH {
+            udta = create_box(b"udta", &udta);
+        }
+
+        let mut mp4 = create_box(b"ftyp", b"isom\0\0\0\0");
+        mp4.extend(create_box(b"moov", &udta));
+
+        let result = Mp4Conversion::new().convert(mp4).unwrap();
+
+        assert!(result.metadata.is_empty());
+    }
+}
@@ -0,0 +1,221 @@
+use crate::conversion::media::flac_conversion::read_metadata_blocks;
+use crate::conversion::media::{read_vorbis_comments, ByteReader, MediaTags};
+use crate::conversion::{Conversion, ConvertedDocument};
+use anyhow::{anyhow, Error};
+
+/// The identification and comment headers are the first two packets of every stream.
+const HEADER_PACKET_COUNT: usize = 2;
+
+/// Opus always counts its granule positions in samples at 48 kHz.
+const OPUS_SAMPLE_RATE: u32 = 48000;
+
+/// Reads the header packets of the Vorbis, Opus, FLAC and Speex streams in an Ogg file.
+pub(crate) struct OggConversion;
+
+impl OggConversion {
+    pub(crate) fn new() -> Self {
+        return Self {};
+    }
+}
+
+impl Conversion for OggConversion {
+    fn convert(&self, buf: Vec<u8>) -> Result<ConvertedDocument, Error> {
+        if !buf.starts_with(b"OggS") {
+            return Err(anyhow!("The file doesn't start with an Ogg page."));
+        }
+
+        let mut tags = MediaTags::default();
+        for stream in read_streams(&buf) {
+            read_stream(&stream, &mut tags);
+        }
+
+        return Ok(tags.into_converted_document());
+    }
+}
+
+struct Stream {
+    serial: u32,
+    packets: Vec<Vec<u8>>,
+    unfinished_packet: Vec<u8>,
+    /// The granule position of the last page, which is the amount of samples for audio streams.
+    last_granule_position: Option<u64>,
+}
+
+/// Splits the pages of all logical streams into packets, of which only the header packets are kept.
+fn read_streams(buf: &[u8]) -> Vec<Stream> {
+    let mut streams: Vec<Stream> = Vec::new();
+    let mut reader = ByteReader::new(buf);
+
+    while let Some(capture_pattern) = reader.read_bytes(4) {
+        if capture_pattern != b"OggS" {
+            // Skips damaged data until the next page.
+            match find(&buf[reader.position - 3..], b"OggS") {
+                Some(v) => reader.position = reader.position - 3 + v,
+                None => break,
+            }
+            continue;
+        }
+
+        let page = (|| {
+            reader.skip(2)?;
+            let granule_position = reader.read_bytes(8)?;
+            let granule_position = i64::from_le_bytes(granule_position.try_into().ok()?);
+            let serial = reader.read_u32_le()?;
+            reader.skip(8)?;
+            let segment_count = reader.read_u8()? as usize;
+            let segment_table = reader.read_bytes(segment_count)?;
+            let segments = segment_table
+                .iter()
+                .map(|length| reader.read_bytes(*length as usize))
+                .collect::<Option<Vec<_>>>()?;
+            return Some((granule_position, serial, segment_table, segments));
+        })();
+        let (granule_position, serial, segment_table, segments) = match page {
+            Some(v) => v,
+            None => break,
+        };
+
+        let stream = match streams.iter().position(|v| v.serial == serial) {
+            Some(v) => &mut streams[v],
+            None => {
+                streams.push(Stream {
+                    serial,
+                    packets: Vec::new(),
+                    unfinished_packet: Vec::new(),
+                    last_granule_position: None,
+                });
+                streams.last_mut().unwrap()
+            }
+        };
+
+        for (length, segment) in segment_table.iter().zip(segments) {
+            if stream.packets.len() >= HEADER_PACKET_COUNT {
+                break;
+            }
+            stream.unfinished_packet.extend_from_slice(segment);
+            // Packets continue in the next segment as long as the segment has the maximum length.
+            if *length < 255 {
+                stream
+                    .packets
+                    .push(std::mem::take(&mut stream.unfinished_packet));
+            }
+        }
+
+        // Pages on which no packet ends have a granule position of -1.
+        if granule_position >= 0 {
+            stream.last_granule_position = Some(granule_position as u64);
+        }
+    }
+
+    return streams;
+}
+
+fn read_stream(stream: &Stream, tags: &mut MediaTags) -> Option<()> {
+    let identification = stream.packets.first()?;
+    let comments = stream.packets.get(1);
+    let samples = stream.last_granule_position;
+
+    let set_duration = |tags: &mut MediaTags, samples: Option<u64>, sample_rate: u32| {
+        if let Some(samples) = samples.filter(|_| sample_rate > 0) {
+            tags.duration_in_seconds = Some(samples as f64 / sample_rate as f64);
+        }
+    };
+
+    if identification.starts_with(b"\x01vorbis") {
+        tags.add_codec("vorbis");
+        let sample_rate = ByteReader::new(identification.get(12..16)?).read_u32_le()?;
+        set_duration(tags, samples, sample_rate);
+        if let Some(comments) = comments.and_then(|v| v.strip_prefix(b"\x03vorbis")) {
+            read_vorbis_comments(comments, tags);
+        }
+    } else if identification.starts_with(b"OpusHead") {
+        tags.add_codec("opus");
+        let pre_skip = u16::from_le_bytes(identification.get(10..12)?.try_into().ok()?);
+        set_duration(
+            tags,
+            samples.map(|v| v.saturating_sub(pre_skip as u64)),
+            OPUS_SAMPLE_RATE,
+        );
+        if let Some(comments) = comments.and_then(|v| v.strip_prefix(b"OpusTags")) {
+            read_vorbis_comments(comments, tags);
+        }
+    } else if identification.starts_with(b"\x7FFLAC") {
+        tags.add_codec("flac");
+        // The packet contains the stream marker and the stream info block, the next one the comments block.
+        read_metadata_blocks(identification.get(13..)?, tags);
+        if let Some(comments) = comments {
+            read_metadata_blocks(comments, tags);
+        }
+    } else if identification.starts_with(b"Speex   ") {
+        tags.add_codec("speex");
+        let sample_rate = ByteReader::new(identification.get(36..40)?).read_u32_le()?;
+        set_duration(tags, samples, sample_rate);
+        if let Some(comments) = comments {
+            read_vorbis_comments(comments, tags);
+        }
+    } else if identification.starts_with(b"\x80theora") {
+        tags.add_codec("theora");
+    }
+
+    return Some(());
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    return haystack
+        .windows(needle.len())
+        .position(|window| window == needle);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_page(serial: u32, granule_position: i64, packets: &[&[u8]]) -> Vec<u8> {
+        let mut segment_table = Vec::new();
+        let mut data = Vec::new();
+        for packet in packets {
+            segment_table.extend(std::iter::repeat(255).take(packet.len() / 255));
+            segment_table.push((packet.len() % 255) as u8);
+            data.extend_from_slice(packet);
+        }
+
+        let mut page = b"OggS\x00\x00".to_vec();
+        page.extend_from_slice(&granule_position.to_le_bytes());
+        page.extend_from_slice(&serial.to_le_bytes());
+        page.extend_from_slice(&[0; 8]);
+        page.push(segment_table.len() as u8);
+        page.extend_from_slice(&segment_table);
+        page.extend_from_slice(&data);
+
+        return page;
+    }
+
+    #[test]
+    fn test_if_opus_stream_is_read() {
+        let mut identification = b"OpusHead\x01\x02".to_vec();
+        identification.extend_from_slice(&312u16.to_le_bytes());
+        identification.extend_from_slice(&[0; 7]);
+
+        let title = "TITLE=".to_string() + &"a".repeat(300);
+        let mut comments = b"OpusTags".to_vec();
+        comments.extend_from_slice(&0u32.to_le_bytes());
+        comments.extend_from_slice(&1u32.to_le_bytes());
+        comments.extend_from_slice(&(title.len() as u32).to_le_bytes());
+        comments.extend_from_slice(title.as_bytes());
+
+        let mut ogg = create_page(7, 0, &[&identification]);
+        ogg.extend(create_page(7, -1, &[&comments]));
+        ogg.extend(create_page(7, 48000 * 3 + 312, &[b"audio"]));
+
+        let result = OggConversion::new().convert(ogg).unwrap();
+
+        assert_eq!(
+            result.metadata,
+            vec![
+                ("title".to_string(), "a".repeat(300)),
+                ("duration".to_string(), "3.000".to_string()),
+                ("codec".to_string(), "opus".to_string()),
+            ]
+        );
+    }
+}
@@ -1,10 +1,67 @@
+pub(crate) mod archive_conversion;
 pub(crate) mod clear_text_conversion;
+pub(crate) mod conversion_registry;
 pub(crate) mod convert_to_clear_text_strategy;
 pub(crate) mod determine_file_type;
+pub(crate) mod epub_conversion;
+pub(crate) mod image_conversion;
+pub(crate) mod legacy_office;
+pub(crate) mod mail_conversion;
+pub(crate) mod markup_conversion;
+pub(crate) mod media;
+pub(crate) mod no_op_conversion;
+pub(crate) mod notebook_conversion;
 pub(crate) mod pdf_conversion;
+pub(crate) mod rtf_conversion;
+pub(crate) mod sandbox;
+pub(crate) mod source_code_conversion;
+pub(crate) mod structured_data_conversion;
 
 use anyhow::Error;
 
 pub(crate) trait Conversion: Send + Sync {
-    fn convert(&self, buf: Vec<u8>) -> Result<String, Error>;
+    fn convert(&self, buf: Vec<u8>) -> Result<ConvertedDocument, Error>;
+
+    /// Has to be increased whenever the output of the conversion changes, so cached results are converted again.
+    fn get_version(&self) -> u32 {
+        return 1;
+    }
+}
+
+/// The clear text of a file together with metadata, which is indexed into the fields of the same name.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct ConvertedDocument {
+    pub contents: String,
+    pub metadata: Vec<(String, String)>,
+    /// Files inside of this one, e.g. attachments of a mail, which are indexed as files of their own.
+    pub embedded_files: Vec<EmbeddedFile>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct EmbeddedFile {
+    /// The path of the file relative to the file it is embedded in.
+    pub name: String,
+    pub contents: Vec<u8>,
+}
+
+impl ConvertedDocument {
+    pub(crate) fn new(contents: String) -> Self {
+        return Self {
+            contents,
+            metadata: Vec::new(),
+            embedded_files: Vec::new(),
+        };
+    }
+
+    pub(crate) fn with_metadata(mut self, field_name: &str, value: String) -> Self {
+        self.metadata.push((field_name.to_string(), value));
+
+        return self;
+    }
+
+    pub(crate) fn with_embedded_file(mut self, name: String, contents: Vec<u8>) -> Self {
+        self.embedded_files.push(EmbeddedFile { name, contents });
+
+        return self;
+    }
 }
@@ -0,0 +1,11 @@
+use crate::conversion::{Conversion, ConvertedDocument};
+use anyhow::Error;
+
+/// Indexes files only by their path, e.g. images or types without a conversion yet.
+pub(crate) struct NoOpConversion;
+
+impl Conversion for NoOpConversion {
+    fn convert(&self, _: Vec<u8>) -> Result<ConvertedDocument, Error> {
+        return Ok(ConvertedDocument::default());
+    }
+}
@@ -0,0 +1,171 @@
+use crate::conversion::markup_conversion::convert_html_to_text;
+use crate::conversion::{Conversion, ConvertedDocument};
+use anyhow::{anyhow, Error};
+use serde_json::Value;
+
+/// The representations of an output that are indexed, in the order of preference. Images and other binary data are
+/// never indexed.
+const TEXT_OUTPUT_MIME_TYPES: &[&str] = &["text/plain", "text/markdown", "text/html"];
+
+/// Extracts the sources of the markdown and code cells of Jupyter notebooks and the text of their outputs, instead of
+/// indexing the JSON with its escaped sources and base64 encoded images.
+pub(crate) struct NotebookConversion;
+
+impl NotebookConversion {
+    pub(crate) fn new() -> Self {
+        return Self {};
+    }
+}
+
+impl Conversion for NotebookConversion {
+    fn convert(&self, buf: Vec<u8>) -> Result<ConvertedDocument, Error> {
+        let notebook: Value = serde_json::from_slice(&buf)?;
+
+        // Notebooks before version 4 keep their cells in worksheets.
+        let cells = match notebook.get("cells") {
+            Some(v) => v.as_array().into_iter().flatten().collect::<Vec<_>>(),
+            None => notebook
+                .get("worksheets")
+                .and_then(Value::as_array)
+                .ok_or_else(|| anyhow!("The notebook doesn't contain any cells."))?
+                .iter()
+                .filter_map(|v| v.get("cells").and_then(Value::as_array))
+                .flatten()
+                .collect(),
+        };
+
+        let mut parts = Vec::new();
+        for cell in cells {
+            let cell_type = cell.get("cell_type").and_then(Value::as_str);
+            if !matches!(cell_type, Some("markdown") | Some("code") | Some("raw")) {
+                continue;
+            }
+
+            let source = cell.get("source").or_else(|| cell.get("input"));
+            parts.push(join_text(source));
+
+            for output in cell
+                .get("outputs")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+            {
+                parts.push(get_output_text(output));
+            }
+        }
+
+        let contents = parts
+            .into_iter()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        let mut converted_document = ConvertedDocument::new(contents);
+        if let Some(kernel_language) = get_kernel_language(&notebook) {
+            converted_document =
+                converted_document.with_metadata("kernel_language", kernel_language);
+        }
+
+        return Ok(converted_document);
+    }
+}
+
+/// Multiline strings are stored either as a single string or as a list of lines.
+fn join_text(value: Option<&Value>) -> String {
+    return match value {
+        Some(Value::String(v)) => v.clone(),
+        Some(Value::Array(v)) => v.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    };
+}
+
+fn get_output_text(output: &Value) -> String {
+    match output.get("output_type").and_then(Value::as_str) {
+        Some("stream") => return join_text(output.get("text")),
+        Some("error") | Some("pyerr") => {
+            // The traceback is full of terminal escape sequences, the name and the message are enough.
+            let name = output.get("ename").and_then(Value::as_str).unwrap_or("");
+            let value = output.get("evalue").and_then(Value::as_str).unwrap_or("");
+            return format!("{}: {}", name, value);
+        }
+        _ => {}
+    }
+
+    // Version 4 keeps the representations in `data`, older versions directly in the output.
+    let data = output.get("data").unwrap_or(output);
+    for mime_type in TEXT_OUTPUT_MIME_TYPES {
+        let text = join_text(data.get(*mime_type));
+        if text.is_empty() {
+            continue;
+        }
+
+        return match *mime_type {
+            "text/html" => convert_html_to_text(&text),
+            _ => text,
+        };
+    }
+
+    return join_text(data.get("text"));
+}
+
+fn get_kernel_language(notebook: &Value) -> Option<String> {
+    let metadata = notebook.get("metadata")?;
+    let language = metadata
+        .pointer("/kernelspec/language")
+        .or_else(|| metadata.pointer("/language_info/name"))
+        .and_then(Value::as_str)?
+        .trim()
+        .to_lowercase();
+
+    if language.is_empty() {
+        return None;
+    }
+
+    return Some(language);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_if_sources_and_text_outputs_are_extracted() {
+        let notebook = r##"{
+            "nbformat": 4,
+            "metadata": {
+                "kernelspec": {"name": "python3", "display_name": "Python 3", "language": "python"}
+            },
+            "cells": [
+                {"cell_type": "markdown", "metadata": {}, "source": ["# Sales\n", "Revenue by quarter"]},
+                {
+                    "cell_type": "code",
+                    "metadata": {},
+                    "source": "df.plot()\nprint(total)",
+                    "outputs": [
+                        {"output_type": "stream", "name": "stdout", "text": ["1200\n"]},
+                        {
+                            "output_type": "display_data",
+                            "data": {"image/png": "iVBORw0KGgoAAAANSUhEUgAAAAEAAAAB", "text/plain": ["<Figure>"]}
+                        },
+                        {"output_type": "display_data", "data": {"image/png": "iVBORw0KGgoAAAANSUhEUgAAAAEAAAAB"}},
+                        {"output_type": "error", "ename": "KeyError", "evalue": "'region'", "traceback": ["\u001b[0;31m"]}
+                    ]
+                }
+            ]
+        }"##;
+
+        let result = NotebookConversion::new()
+            .convert(notebook.as_bytes().to_vec())
+            .unwrap();
+
+        assert_eq!(
+            result.contents,
+            "# Sales\nRevenue by quarter\n\ndf.plot()\nprint(total)\n\n1200\n\n<Figure>\n\nKeyError: 'region'"
+        );
+        assert_eq!(
+            result.metadata,
+            vec![("kernel_language".to_string(), "python".to_string())]
+        );
+    }
+}
@@ -1,27 +1,206 @@
-use crate::conversion::Conversion;
+use crate::conversion::{Conversion, ConvertedDocument};
 use anyhow::{anyhow, Error};
+use chrono::{FixedOffset, NaiveDate, TimeZone};
+use encoding_rs::{UTF_16BE, WINDOWS_1252};
+use pdf_extract::{output_doc_page, Document, Object, PlainTextOutput};
 use std::panic;
 
+/// The entries of the document information dictionary and the index fields they are stored in.
+const INFO_FIELDS: &[(&[u8], &str)] = &[
+    (b"Title", "title"),
+    (b"Author", "author"),
+    (b"Subject", "subject"),
+    (b"Keywords", "keywords"),
+];
+
 pub(crate) struct PdfConversion;
 
 impl PdfConversion {
     pub(crate) fn new() -> Self {
         return Self {};
     }
+
+    fn extract(buf: &[u8]) -> Result<ConvertedDocument, Error> {
+        let mut doc = Document::load_mem(buf)?;
+        if doc.is_encrypted() {
+            // Most encrypted PDFs only restrict permissions and can be opened with an empty password.
+            doc.decrypt("")?;
+        }
+
+        let page_numbers = doc.get_pages().into_keys().collect::<Vec<_>>();
+
+        let mut contents = String::new();
+        let mut page_offsets = Vec::with_capacity(page_numbers.len());
+        for page_number in &page_numbers {
+            page_offsets.push(contents.len());
+            let mut output = PlainTextOutput::new(&mut contents);
+            output_doc_page(&doc, &mut output, *page_number)?;
+        }
+
+        let mut converted_document = ConvertedDocument::new(contents)
+            .with_metadata("page_count", page_numbers.len().to_string());
+
+        for page_offset in page_offsets {
+            converted_document =
+                converted_document.with_metadata("page_offsets", page_offset.to_string());
+        }
+
+        let info = match doc
+            .trailer
+            .get(b"Info")
+            .and_then(|v| doc.dereference(v))
+            .and_then(|(_, v)| v.as_dict())
+        {
+            Ok(v) => v,
+            Err(_) => return Ok(converted_document),
+        };
+
+        for (key, field_name) in INFO_FIELDS {
+            if let Some(value) = get_info_string(&doc, info, key) {
+                converted_document = converted_document.with_metadata(field_name, value);
+            }
+        }
+
+        if let Some(created_at) =
+            get_info_string(&doc, info, b"CreationDate").and_then(|v| parse_pdf_date(&v))
+        {
+            converted_document = converted_document.with_metadata("created_at", created_at);
+        }
+
+        return Ok(converted_document);
+    }
 }
 
 impl Conversion for PdfConversion {
-    fn convert(&self, buf: Vec<u8>) -> Result<String, Error> {
-        let out = match panic::catch_unwind(|| pdf_extract::extract_text_from_mem(&buf)) {
-            Ok(v) => v?,
-            Err(e) => {
-                return Err(anyhow!(
-                    "There was an unknown error while trying to extract text from a pdf. {:?}",
-                    e
-                ))
-            }
+    fn convert(&self, buf: Vec<u8>) -> Result<ConvertedDocument, Error> {
+        return match panic::catch_unwind(|| Self::extract(&buf)) {
+            Ok(v) => v,
+            Err(e) => Err(anyhow!(
+                "There was an unknown error while trying to extract text from a pdf. {:?}",
+                e
+            )),
         };
+    }
+
+    fn get_version(&self) -> u32 {
+        return 2;
+    }
+}
+
+fn get_info_string(doc: &Document, info: &pdf_extract::Dictionary, key: &[u8]) -> Option<String> {
+    let value = match info.get(key).and_then(|v| doc.dereference(v)) {
+        Ok((_, Object::String(v, _))) => decode_pdf_string(v),
+        _ => return None,
+    };
+
+    let value = value.trim_matches(char::from(0)).trim();
+    if value.is_empty() {
+        return None;
+    }
+
+    return Some(value.to_string());
+}
+
+/// Text strings are either UTF-16BE with a byte order mark, UTF-8 with a byte order mark since PDF 2.0,
+/// or PDFDocEncoding, which only differs from Windows-1252 in rarely used characters.
+fn decode_pdf_string(buf: &[u8]) -> String {
+    if let Some(v) = buf.strip_prefix(b"\xFE\xFF") {
+        return UTF_16BE.decode_without_bom_handling(v).0.into_owned();
+    }
+    if let Some(v) = buf.strip_prefix(b"\xEF\xBB\xBF") {
+        return String::from_utf8_lossy(v).into_owned();
+    }
+
+    return WINDOWS_1252.decode_without_bom_handling(buf).0.into_owned();
+}
+
+/// Converts a date like `D:20230415093000+02'00'` to RFC 3339. Everything after the year is optional.
+fn parse_pdf_date(value: &str) -> Option<String> {
+    let value = value.strip_prefix("D:").unwrap_or(value).trim();
+    let digits = value
+        .bytes()
+        .take_while(|v| v.is_ascii_digit())
+        .count()
+        .min(14);
+
+    let component = |start: usize, len: usize, default: u32| -> Option<u32> {
+        if start + len > digits {
+            return Some(default);
+        }
+        return value[start..start + len].parse().ok();
+    };
+
+    if digits < 4 {
+        return None;
+    }
+
+    let date_time = NaiveDate::from_ymd_opt(
+        component(0, 4, 0)? as i32,
+        component(4, 2, 1)?,
+        component(6, 2, 1)?,
+    )?
+    .and_hms_opt(
+        component(8, 2, 0)?,
+        component(10, 2, 0)?,
+        component(12, 2, 0)?,
+    )?;
+
+    let rest = &value[digits..];
+    let offset_seconds = match rest.chars().next() {
+        Some(sign @ ('+' | '-')) => {
+            let offset = rest[1..].replace('\'', "");
+            let hours = offset
+                .get(0..2)
+                .and_then(|v| v.parse::<i32>().ok())
+                .unwrap_or(0);
+            let minutes = offset
+                .get(2..4)
+                .and_then(|v| v.parse::<i32>().ok())
+                .unwrap_or(0);
+            let seconds = hours * 3600 + minutes * 60;
+            if sign == '-' {
+                -seconds
+            } else {
+                seconds
+            }
+        }
+        _ => 0,
+    };
+
+    let offset = FixedOffset::east_opt(offset_seconds)?;
+
+    return Some(
+        offset
+            .from_local_datetime(&date_time)
+            .single()?
+            .to_rfc3339(),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_if_pdf_dates_are_converted() {
+        assert_eq!(
+            parse_pdf_date("D:20230415093000+02'00'"),
+            Some("2023-04-15T09:30:00+02:00".to_string())
+        );
+        assert_eq!(
+            parse_pdf_date("D:20230415093000Z"),
+            Some("2023-04-15T09:30:00+00:00".to_string())
+        );
+        assert_eq!(
+            parse_pdf_date("D:2023"),
+            Some("2023-01-01T00:00:00+00:00".to_string())
+        );
+        assert_eq!(parse_pdf_date("yesterday"), None);
+    }
 
-        return Ok(out);
+    #[test]
+    fn test_if_pdf_strings_are_decoded() {
+        assert_eq!(decode_pdf_string(b"\xFE\xFF\x00H\x00i"), "Hi");
+        assert_eq!(decode_pdf_string(b"Gr\xFC\xDFe"), "Grüße");
     }
 }
@@ -0,0 +1,427 @@
+use crate::conversion::{Conversion, ConvertedDocument};
+use anyhow::{anyhow, Error};
+use encoding_rs::{Encoding, WINDOWS_1252};
+use std::collections::HashMap;
+
+/// Destinations whose contents aren't part of the visible text of the document.
+const IGNORED_DESTINATIONS: &[&str] = &[
+    "author",
+    "buptim",
+    "colortbl",
+    "comment",
+    "creatim",
+    "datastore",
+    "doccomm",
+    "filetbl",
+    "fldinst",
+    "generator",
+    "info",
+    "keywords",
+    "latentstyles",
+    "listoverridetable",
+    "listtable",
+    "nonshppict",
+    "object",
+    "objdata",
+    "operator",
+    "pict",
+    "pntext",
+    "pntxta",
+    "pntxtb",
+    "printim",
+    "private",
+    "revtbl",
+    "revtim",
+    "rsidtbl",
+    "stylesheet",
+    "subject",
+    "themedata",
+    "title",
+    "xmlnstbl",
+];
+
+pub(crate) struct RtfConversion;
+
+impl RtfConversion {
+    pub(crate) fn new() -> Self {
+        return Self {};
+    }
+}
+
+impl Conversion for RtfConversion {
+    fn convert(&self, buf: Vec<u8>) -> Result<ConvertedDocument, Error> {
+        if !buf.starts_with(b"{\\rtf") {
+            return Err(anyhow!("The file doesn't start with an RTF header."));
+        }
+
+        return Ok(ConvertedDocument::new(RtfParser::new(&buf).parse()));
+    }
+}
+
+#[derive(Clone)]
+struct GroupState {
+    is_ignored: bool,
+    is_font_table: bool,
+    /// The amount of fallback characters following a `\uN` escape.
+    unicode_skip_count: usize,
+    encoding: &'static Encoding,
+}
+
+struct RtfParser<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    out: String,
+    state: GroupState,
+    stack: Vec<GroupState>,
+    default_encoding: &'static Encoding,
+    font_encodings: HashMap<i32, &'static Encoding>,
+    current_font: Option<i32>,
+    pending_bytes: Vec<u8>,
+    pending_utf16: Vec<u16>,
+    chars_to_skip: usize,
+}
+
+impl<'a> RtfParser<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        return Self {
+            buf,
+            pos: 0,
+            out: String::with_capacity(buf.len() / 2),
+            state: GroupState {
+                is_ignored: false,
+                is_font_table: false,
+                unicode_skip_count: 1,
+                encoding: WINDOWS_1252,
+            },
+            stack: Vec::new(),
+            default_encoding: WINDOWS_1252,
+            font_encodings: HashMap::new(),
+            current_font: None,
+            pending_bytes: Vec::new(),
+            pending_utf16: Vec::new(),
+            chars_to_skip: 0,
+        };
+    }
+
+    fn parse(mut self) -> String {
+        while self.pos < self.buf.len() {
+            let c = self.buf[self.pos];
+            self.pos += 1;
+
+            match c {
+                b'{' => {
+                    self.flush();
+                    self.stack.push(self.state.clone());
+                    self.chars_to_skip = 0;
+                }
+                b'}' => {
+                    self.flush();
+                    if self.state.is_font_table {
+                        self.current_font = None;
+                    }
+                    match self.stack.pop() {
+                        Some(v) => self.state = v,
+                        None => break,
+                    }
+                    self.chars_to_skip = 0;
+                }
+                b'\\' => self.parse_control(),
+                b'\r' | b'\n' => {}
+                _ => {
+                    if self.skip_fallback_char() {
+                        continue;
+                    }
+                    if self.state.is_font_table && c == b';' {
+                        self.current_font = None;
+                    }
+                    self.push_byte(c);
+                }
+            }
+        }
+
+        self.flush();
+
+        return self.out;
+    }
+
+    fn parse_control(&mut self) {
+        let c = match self.buf.get(self.pos) {
+            Some(v) => *v,
+            None => return,
+        };
+        self.pos += 1;
+
+        if !c.is_ascii_alphabetic() {
+            self.parse_control_symbol(c);
+            return;
+        }
+
+        let word_start = self.pos - 1;
+        while self.pos < self.buf.len() && self.buf[self.pos].is_ascii_alphabetic() {
+            self.pos += 1;
+        }
+        let word = String::from_utf8_lossy(&self.buf[word_start..self.pos]).into_owned();
+
+        let param_start = self.pos;
+        if self.buf.get(self.pos) == Some(&b'-') {
+            self.pos += 1;
+        }
+        while self.pos < self.buf.len() && self.buf[self.pos].is_ascii_digit() {
+            self.pos += 1;
+        }
+        let param = std::str::from_utf8(&self.buf[param_start..self.pos])
+            .ok()
+            .and_then(|v| v.parse::<i32>().ok());
+
+        // A single space delimits the control word and is not part of the text.
+        if self.buf.get(self.pos) == Some(&b' ') {
+            self.pos += 1;
+        }
+
+        self.handle_control_word(&word, param);
+    }
+
+    fn parse_control_symbol(&mut self, c: u8) {
+        match c {
+            b'\'' => {
+                let hex = self.buf.get(self.pos..self.pos + 2).unwrap_or_default();
+                self.pos += hex.len();
+                if self.skip_fallback_char() {
+                    return;
+                }
+                if let Some(byte) = std::str::from_utf8(hex)
+                    .ok()
+                    .and_then(|v| u8::from_str_radix(v, 16).ok())
+                {
+                    self.push_byte(byte);
+                }
+            }
+            // Optional destinations marked with `\*` are ignored as a whole.
+            b'*' => self.state.is_ignored = true,
+            b'~' => self.push_char(' '),
+            b'_' => self.push_char('-'),
+            b'-' => {}
+            b'\r' | b'\n' => self.push_char('\n'),
+            b'\\' | b'{' | b'}' => {
+                if !self.skip_fallback_char() {
+                    self.push_byte(c);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_control_word(&mut self, word: &str, param: Option<i32>) {
+        if IGNORED_DESTINATIONS.contains(&word) {
+            self.state.is_ignored = true;
+            return;
+        }
+
+        match word {
+            "ansicpg" => {
+                if let Some(encoding) = param.and_then(encoding_for_code_page) {
+                    self.default_encoding = encoding;
+                    self.state.encoding = encoding;
+                }
+            }
+            "mac" => {
+                self.default_encoding = encoding_for_code_page(10000).unwrap_or(WINDOWS_1252);
+                self.state.encoding = self.default_encoding;
+            }
+            "fonttbl" => {
+                // The font table isn't shown but its charsets define the code page of every font.
+                self.state.is_font_table = true;
+                self.state.is_ignored = true;
+            }
+            "f" => {
+                if self.state.is_font_table {
+                    self.current_font = param;
+                } else if let Some(font) = param {
+                    self.flush();
+                    self.state.encoding = *self
+                        .font_encodings
+                        .get(&font)
+                        .unwrap_or(&self.default_encoding);
+                }
+            }
+            "fcharset" => {
+                if let (Some(font), Some(encoding)) = (
+                    self.current_font,
+                    param
+                        .and_then(code_page_for_charset)
+                        .and_then(encoding_for_code_page),
+                ) {
+                    self.font_encodings.insert(font, encoding);
+                }
+            }
+            "cpg" => {
+                if let (Some(font), Some(encoding)) =
+                    (self.current_font, param.and_then(encoding_for_code_page))
+                {
+                    self.font_encodings.insert(font, encoding);
+                }
+            }
+            "plain" => {
+                self.flush();
+                self.state.encoding = self.default_encoding;
+            }
+            "uc" => self.state.unicode_skip_count = param.unwrap_or(1).max(0) as usize,
+            "u" => {
+                if let Some(v) = param {
+                    self.push_utf16((if v < 0 { v + 65536 } else { v }) as u16);
+                    self.chars_to_skip = self.state.unicode_skip_count;
+                }
+            }
+            "bin" => {
+                self.pos = self
+                    .pos
+                    .saturating_add(param.unwrap_or(0).max(0) as usize)
+                    .min(self.buf.len());
+            }
+            "par" | "line" | "sect" | "page" | "row" => self.push_char('\n'),
+            "tab" | "cell" => self.push_char('\t'),
+            "emdash" => self.push_char('\u{2014}'),
+            "endash" => self.push_char('\u{2013}'),
+            "bullet" => self.push_char('\u{2022}'),
+            "lquote" => self.push_char('\u{2018}'),
+            "rquote" => self.push_char('\u{2019}'),
+            "ldblquote" => self.push_char('\u{201C}'),
+            "rdblquote" => self.push_char('\u{201D}'),
+            _ => {}
+        }
+    }
+
+    /// Skips a character that only exists as fallback for readers that don't understand `\uN`.
+    fn skip_fallback_char(&mut self) -> bool {
+        if self.chars_to_skip > 0 {
+            self.chars_to_skip -= 1;
+            return true;
+        }
+
+        return false;
+    }
+
+    fn push_byte(&mut self, byte: u8) {
+        if self.state.is_ignored {
+            return;
+        }
+        if !self.pending_utf16.is_empty() {
+            self.flush();
+        }
+
+        self.pending_bytes.push(byte);
+    }
+
+    fn push_utf16(&mut self, code_unit: u16) {
+        if self.state.is_ignored {
+            return;
+        }
+        if !self.pending_bytes.is_empty() {
+            self.flush();
+        }
+
+        self.pending_utf16.push(code_unit);
+    }
+
+    fn push_char(&mut self, c: char) {
+        if self.state.is_ignored {
+            return;
+        }
+
+        self.flush();
+        self.out.push(c);
+    }
+
+    /// Decodes the collected bytes together, so multi-byte code pages are decoded correctly.
+    fn flush(&mut self) {
+        if !self.pending_bytes.is_empty() {
+            let (decoded, _, _) = self.state.encoding.decode(&self.pending_bytes);
+            self.out.push_str(&decoded);
+            self.pending_bytes.clear();
+        }
+
+        if !self.pending_utf16.is_empty() {
+            self.out
+                .push_str(&String::from_utf16_lossy(&self.pending_utf16));
+            self.pending_utf16.clear();
+        }
+    }
+}
+
+fn code_page_for_charset(charset: i32) -> Option<i32> {
+    let code_page = match charset {
+        0 => 1252,
+        77 => 10000,
+        128 => 932,
+        129 => 949,
+        134 => 936,
+        136 => 950,
+        161 => 1253,
+        162 => 1254,
+        163 => 1258,
+        177 => 1255,
+        178 => 1256,
+        186 => 1257,
+        204 => 1251,
+        222 => 874,
+        238 => 1250,
+        _ => return None,
+    };
+
+    return Some(code_page);
+}
+
+fn encoding_for_code_page(code_page: i32) -> Option<&'static Encoding> {
+    let label = match code_page {
+        437 | 850 | 1252 => "windows-1252",
+        866 => "ibm866",
+        874 => "windows-874",
+        932 => "shift_jis",
+        936 => "gbk",
+        949 => "euc-kr",
+        950 => "big5",
+        1250..=1258 => return Encoding::for_label(format!("windows-{}", code_page).as_bytes()),
+        10000 => "macintosh",
+        20866 => "koi8-r",
+        28591..=28599 => {
+            return Encoding::for_label(format!("iso-8859-{}", code_page - 28590).as_bytes())
+        }
+        65001 => "utf-8",
+        _ => return None,
+    };
+
+    return Encoding::for_label(label.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_if_text_is_extracted_without_font_table_and_pictures() {
+        let rtf = br"{\rtf1\ansi\ansicpg1252\deff0{\fonttbl{\f0\fswiss Arial;}}{\colortbl;\red0\green0\blue0;}
+\pard\f0\fs20 Sehr geehrte Damen und Herren,\par
+{\pict\pngblip 89504e470d0a}Gr\'fc\'dfe\tab \u8364?100\par
+{\*\generator Some Writer;}}";
+
+        let result = RtfConversion::new().convert(rtf.to_vec()).unwrap().contents;
+
+        assert_eq!(result, "Sehr geehrte Damen und Herren,\nGrüße\t€100\n");
+    }
+
+    #[test]
+    fn test_if_hex_escapes_use_the_code_page_of_the_font() {
+        let rtf = br"{\rtf1\ansi\ansicpg1252{\fonttbl{\f0 Arial;}{\f1\fcharset204 Arial Cyr;}}\f1\'cf\'f0\'e8\'e2\'e5\'f2\f0  \'e9}";
+
+        let result = RtfConversion::new().convert(rtf.to_vec()).unwrap().contents;
+
+        assert_eq!(result, "Привет é");
+    }
+
+    #[test]
+    fn test_if_non_rtf_returns_error() {
+        let result = RtfConversion::new().convert(b"just text".to_vec());
+
+        assert!(result.is_err());
+    }
+}
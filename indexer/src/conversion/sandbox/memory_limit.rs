@@ -0,0 +1,107 @@
+use std::io;
+use std::process::{Child, Command};
+
+/// Limits the address space of the worker before it is started. The limit is enforced by the operating system, so it
+/// also covers memory that libraries allocate outside of Rust and memory mapped files.
+#[cfg(unix)]
+pub(crate) fn limit_memory_of_command(command: &mut Command, memory_limit: u64) {
+    use std::os::unix::process::CommandExt;
+
+    if memory_limit == 0 {
+        return;
+    }
+
+    // The hard limit can only be lowered, so the limit is read before forking.
+    let mut limit = libc::rlimit {
+        rlim_cur: libc::RLIM_INFINITY,
+        rlim_max: libc::RLIM_INFINITY,
+    };
+    if unsafe { libc::getrlimit(libc::RLIMIT_AS, &mut limit) } == 0 {
+        limit.rlim_cur = limit.rlim_max.min(memory_limit as libc::rlim_t);
+    } else {
+        limit.rlim_cur = memory_limit as libc::rlim_t;
+    }
+
+    // Only `setrlimit` runs between forking and executing the worker, which is safe to call there.
+    unsafe {
+        command.pre_exec(move || {
+            if libc::setrlimit(libc::RLIMIT_AS, &limit) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            return Ok(());
+        });
+    }
+}
+
+#[cfg(not(unix))]
+pub(crate) fn limit_memory_of_command(_command: &mut Command, _memory_limit: u64) {}
+
+/// Limits the memory of the running worker through a job object, because Windows can't limit a process before it is
+/// started. The worker doesn't convert anything before it received the first file, so it is limited in time.
+#[cfg(windows)]
+pub(crate) fn limit_memory_of_child(child: &Child, memory_limit: u64) -> Result<(), io::Error> {
+    use std::ffi::c_void;
+    use std::mem::{size_of, zeroed};
+    use std::os::windows::io::AsRawHandle;
+    use std::ptr::null;
+    use windows_sys::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows_sys::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation,
+        SetInformationJobObject, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+        JOB_OBJECT_LIMIT_PROCESS_MEMORY,
+    };
+
+    if memory_limit == 0 {
+        return Ok(());
+    }
+
+    unsafe {
+        let job = CreateJobObjectW(null(), null());
+        if job == 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut limit_information: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = zeroed();
+        limit_information.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_PROCESS_MEMORY;
+        limit_information.ProcessMemoryLimit = usize::try_from(memory_limit).unwrap_or(usize::MAX);
+
+        let result = if SetInformationJobObject(
+            job,
+            JobObjectExtendedLimitInformation,
+            &limit_information as *const JOBOBJECT_EXTENDED_LIMIT_INFORMATION as *const c_void,
+            size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+        ) == 0
+            || AssignProcessToJobObject(job, child.as_raw_handle() as HANDLE) == 0
+        {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        };
+
+        // The job is kept alive by the worker that is assigned to it.
+        CloseHandle(job);
+
+        return result;
+    }
+}
+
+#[cfg(not(windows))]
+pub(crate) fn limit_memory_of_child(_child: &Child, _memory_limit: u64) -> Result<(), io::Error> {
+    return Ok(());
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_if_address_space_of_command_is_limited() {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg("ulimit -v");
+        limit_memory_of_command(&mut command, 1024 * 1024 * 1024);
+
+        let output = command.output().unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "1048576");
+    }
+}
@@ -0,0 +1,140 @@
+pub(crate) mod memory_limit;
+pub(crate) mod sandboxed_conversion;
+pub(crate) mod worker;
+
+use crate::conversion::{ConvertedDocument, EmbeddedFile};
+use prost::Message;
+use std::io::{self, Read, Write};
+use std::time::Duration;
+use thiserror::Error;
+
+/// Marks the start of every message, so output that a library prints to stdout can be skipped.
+const MESSAGE_MAGIC: &[u8] = b"DSCVR\x00";
+
+/// A message larger than this is treated as a corrupted stream.
+const MAX_MESSAGE_SIZE: usize = 1024 * 1024 * 1024;
+
+#[derive(Debug, Error)]
+pub(crate) enum SandboxError {
+    #[error("The conversion took longer than {0:?} and was aborted.")]
+    Timeout(Duration),
+    #[error("The conversion worker exited unexpectedly ({0}), e.g. because it exceeded the memory limit of {1} bytes.")]
+    WorkerExited(String, u64),
+    #[error("There was an error while trying to start the conversion worker: {0}")]
+    Spawn(io::Error),
+    #[error("{0}")]
+    Conversion(String),
+}
+
+pub(crate) fn write_message(writer: &mut impl Write, message: &impl Message) -> io::Result<()> {
+    let buf = message.encode_to_vec();
+
+    writer.write_all(MESSAGE_MAGIC)?;
+    writer.write_all(&(buf.len() as u32).to_le_bytes())?;
+    writer.write_all(&buf)?;
+    writer.flush()?;
+
+    return Ok(());
+}
+
+/// Returns `None` when the stream ended before the start of the next message.
+pub(crate) fn read_message<M: Message + Default>(reader: &mut impl Read) -> io::Result<Option<M>> {
+    let mut matched = 0;
+    let mut byte = [0u8; 1];
+    while matched < MESSAGE_MAGIC.len() {
+        if reader.read(&mut byte)? == 0 {
+            return Ok(None);
+        }
+
+        if byte[0] == MESSAGE_MAGIC[matched] {
+            matched += 1;
+        } else {
+            matched = if byte[0] == MESSAGE_MAGIC[0] { 1 } else { 0 };
+        }
+    }
+
+    let mut length = [0u8; 4];
+    reader.read_exact(&mut length)?;
+    let length = u32::from_le_bytes(length) as usize;
+    if length > MAX_MESSAGE_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("The message of {} bytes exceeds the maximum size.", length),
+        ));
+    }
+
+    let mut buf = vec![0u8; length];
+    reader.read_exact(&mut buf)?;
+
+    return match M::decode(buf.as_slice()) {
+        Ok(v) => Ok(Some(v)),
+        Err(e) => Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+    };
+}
+
+impl From<crate::conversion_worker::ConvertedDocument> for ConvertedDocument {
+    fn from(value: crate::conversion_worker::ConvertedDocument) -> Self {
+        return Self {
+            contents: value.contents,
+            metadata: value
+                .metadata
+                .into_iter()
+                .map(|v| (v.field_name, v.value))
+                .collect(),
+            embedded_files: value
+                .embedded_files
+                .into_iter()
+                .map(|v| EmbeddedFile {
+                    name: v.name,
+                    contents: v.contents,
+                })
+                .collect(),
+        };
+    }
+}
+
+impl From<ConvertedDocument> for crate::conversion_worker::ConvertedDocument {
+    fn from(value: ConvertedDocument) -> Self {
+        return Self {
+            contents: value.contents,
+            metadata: value
+                .metadata
+                .into_iter()
+                .map(|(field_name, value)| crate::conversion_worker::Metadata { field_name, value })
+                .collect(),
+            embedded_files: value
+                .embedded_files
+                .into_iter()
+                .map(|v| crate::conversion_worker::EmbeddedFile {
+                    name: v.name,
+                    contents: v.contents,
+                })
+                .collect(),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conversion_worker::ConversionRequest;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_if_messages_are_read_after_unrelated_output() {
+        let request = ConversionRequest {
+            conversion: "pdf".to_string(),
+            contents: b"%PDF-1.7".to_vec(),
+        };
+        let mut buf = b"DSCVR some debug output of a library\n".to_vec();
+        write_message(&mut buf, &request).unwrap();
+
+        let mut reader = Cursor::new(buf);
+
+        let result = read_message::<ConversionRequest>(&mut reader).unwrap();
+        assert_eq!(result, Some(request));
+
+        let result = read_message::<ConversionRequest>(&mut reader).unwrap();
+        assert_eq!(result, None);
+    }
+}
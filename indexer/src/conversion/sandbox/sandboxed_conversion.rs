@@ -0,0 +1,309 @@
+use crate::conversion::sandbox::memory_limit::{limit_memory_of_child, limit_memory_of_command};
+use crate::conversion::sandbox::worker::CONVERSION_WORKER_ARG;
+use crate::conversion::sandbox::{read_message, write_message, SandboxError};
+use crate::conversion::{Conversion, ConvertedDocument};
+use crate::conversion_worker::conversion_response::Result as ConversionResult;
+use crate::conversion_worker::{ConversionRequest, ConversionResponse};
+use anyhow::Error;
+use dscvr_common::config::ConversionWorkerSettings;
+use log::{debug, error, warn};
+use std::env;
+use std::io;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Runs a conversion in the conversion worker, so a file that hangs or exhausts the memory
+/// only takes down the worker instead of the indexer.
+pub(crate) struct SandboxedConversion {
+    conversion_name: String,
+    version: u32,
+    worker: Arc<ConversionWorker>,
+}
+
+impl SandboxedConversion {
+    pub(crate) fn new(conversion_name: &str, version: u32, worker: Arc<ConversionWorker>) -> Self {
+        return Self {
+            conversion_name: conversion_name.to_string(),
+            version,
+            worker,
+        };
+    }
+}
+
+impl Conversion for SandboxedConversion {
+    fn convert(&self, buf: Vec<u8>) -> Result<ConvertedDocument, Error> {
+        return Ok(self.worker.convert(&self.conversion_name, buf)?);
+    }
+
+    fn get_version(&self) -> u32 {
+        return self.version;
+    }
+}
+
+/// Creates the command that starts a worker process.
+type WorkerCommandFactory = Box<dyn Fn() -> io::Result<Command> + Send + Sync>;
+
+/// Owns the worker process, which is started on first use and restarted after it was killed.
+pub(crate) struct ConversionWorker {
+    timeout: Duration,
+    memory_limit: u64,
+    create_command: WorkerCommandFactory,
+    process: Mutex<Option<WorkerProcess>>,
+}
+
+struct WorkerProcess {
+    child: Child,
+    stdin: ChildStdin,
+    responses: Receiver<ConversionResponse>,
+}
+
+impl Drop for WorkerProcess {
+    fn drop(&mut self) {
+        if let Err(e) = self.child.kill() {
+            debug!(
+                "There was an error while trying to kill the conversion worker: {:?}",
+                e
+            );
+        }
+        let _ = self.child.wait();
+    }
+}
+
+impl ConversionWorker {
+    /// The worker is the indexer itself, started with the argument that makes it convert files instead.
+    pub(crate) fn build_with_settings(settings: &ConversionWorkerSettings) -> Self {
+        let memory_limit = settings.memory_limit;
+
+        return Self::new(
+            Duration::from_secs(settings.timeout_in_seconds),
+            memory_limit,
+            Box::new(move || {
+                let mut command = Command::new(env::current_exe()?);
+                command
+                    .arg(CONVERSION_WORKER_ARG)
+                    .arg(memory_limit.to_string());
+                return Ok(command);
+            }),
+        );
+    }
+
+    pub(crate) fn new(
+        timeout: Duration,
+        memory_limit: u64,
+        create_command: WorkerCommandFactory,
+    ) -> Self {
+        return Self {
+            timeout,
+            memory_limit,
+            create_command,
+            process: Mutex::new(None),
+        };
+    }
+
+    fn convert(
+        &self,
+        conversion_name: &str,
+        buf: Vec<u8>,
+    ) -> Result<ConvertedDocument, SandboxError> {
+        let mut guard = match self.process.lock() {
+            Ok(v) => v,
+            Err(e) => e.into_inner(),
+        };
+
+        let process = match guard.as_mut() {
+            Some(v) => v,
+            None => guard.insert(self.spawn().map_err(SandboxError::Spawn)?),
+        };
+
+        let request = ConversionRequest {
+            conversion: conversion_name.to_string(),
+            contents: buf,
+        };
+
+        if let Err(e) = write_message(&mut process.stdin, &request) {
+            warn!(
+                "There was an error while trying to send a file to the conversion worker: {:?}",
+                e
+            );
+            return Err(self.restart(&mut guard));
+        }
+
+        return match process.responses.recv_timeout(self.timeout) {
+            Ok(v) => match v.result {
+                Some(ConversionResult::Document(v)) => Ok(v.into()),
+                Some(ConversionResult::Error(e)) => Err(SandboxError::Conversion(e)),
+                None => Err(SandboxError::Conversion(
+                    "The conversion worker returned an empty response.".to_string(),
+                )),
+            },
+            Err(RecvTimeoutError::Timeout) => {
+                warn!(
+                    "The conversion worker didn't respond within {:?}, it will be restarted.",
+                    self.timeout
+                );
+                *guard = None;
+                Err(SandboxError::Timeout(self.timeout))
+            }
+            Err(RecvTimeoutError::Disconnected) => Err(self.restart(&mut guard)),
+        };
+    }
+
+    /// Kills the worker, so the next conversion starts a fresh one, and describes why it was lost.
+    fn restart(&self, process: &mut Option<WorkerProcess>) -> SandboxError {
+        let status = match process.as_mut() {
+            Some(v) => Self::wait_for_exit(&mut v.child),
+            None => "not running".to_string(),
+        };
+        *process = None;
+
+        error!(
+            "The conversion worker exited unexpectedly ({}), it will be restarted.",
+            status
+        );
+
+        return SandboxError::WorkerExited(status, self.memory_limit);
+    }
+
+    /// The worker closes its stdout while exiting, so its status is usually available right away.
+    fn wait_for_exit(child: &mut Child) -> String {
+        for _ in 0..10 {
+            match child.try_wait() {
+                Ok(Some(v)) => return v.to_string(),
+                Ok(None) => thread::sleep(Duration::from_millis(50)),
+                Err(e) => return format!("unknown status: {}", e),
+            }
+        }
+
+        return "still running".to_string();
+    }
+
+    fn spawn(&self) -> Result<WorkerProcess, io::Error> {
+        let mut command = (self.create_command)()?;
+        command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit());
+        limit_memory_of_command(&mut command, self.memory_limit);
+
+        let mut child = command.spawn()?;
+        // A worker without the limit must not run.
+        if let Err(e) = limit_memory_of_child(&child, self.memory_limit) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(e);
+        }
+
+        let stdin = child.stdin.take().ok_or(io::Error::new(
+            io::ErrorKind::BrokenPipe,
+            "The stdin of the conversion worker isn't available.",
+        ))?;
+        let mut stdout = child.stdout.take().ok_or(io::Error::new(
+            io::ErrorKind::BrokenPipe,
+            "The stdout of the conversion worker isn't available.",
+        ))?;
+
+        // Reading happens on a separate thread, so waiting for a response can time out.
+        let (sender, responses) = channel();
+        thread::spawn(move || loop {
+            match read_message::<ConversionResponse>(&mut stdout) {
+                Ok(Some(v)) => {
+                    if sender.send(v).is_err() {
+                        return;
+                    }
+                }
+                Ok(None) => return,
+                Err(e) => {
+                    warn!(
+                        "There was an error while trying to read a response of the conversion worker: {:?}",
+                        e
+                    );
+                    return;
+                }
+            }
+        });
+
+        debug!("Started a new conversion worker with pid {}.", child.id());
+
+        return Ok(WorkerProcess {
+            child,
+            stdin,
+            responses,
+        });
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Instant;
+
+    #[test]
+    fn test_if_hanging_worker_is_killed_and_restarted() {
+        let (worker, spawn_count, _response_dir) = create_worker("sleep 5");
+
+        let started_at = Instant::now();
+        let result = worker.convert("text", b"hello".to_vec());
+
+        assert!(matches!(result, Err(SandboxError::Timeout(_))));
+        assert!(started_at.elapsed() < Duration::from_secs(5));
+
+        let result = worker.convert("text", b"hello".to_vec()).unwrap();
+
+        assert_eq!(result.contents, "converted");
+        assert_eq!(spawn_count.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_if_exited_worker_is_restarted() {
+        let (worker, spawn_count, _response_dir) = create_worker("exit 1");
+
+        let result = worker.convert("text", b"hello".to_vec());
+
+        assert!(matches!(result, Err(SandboxError::WorkerExited(_, _))));
+
+        let result = worker.convert("text", b"hello".to_vec()).unwrap();
+
+        assert_eq!(result.contents, "converted");
+        assert_eq!(spawn_count.load(Ordering::SeqCst), 2);
+    }
+
+    /// The first worker runs the given shell script, the ones after it answer every request with a document.
+    fn create_worker(
+        first_script: &str,
+    ) -> (ConversionWorker, Arc<AtomicUsize>, tempfile::TempDir) {
+        let response_dir = tempfile::tempdir().unwrap();
+        let response_path = response_dir.path().join("response");
+        let response = ConversionResponse {
+            result: Some(ConversionResult::Document(
+                ConvertedDocument::new("converted".to_string()).into(),
+            )),
+        };
+        let mut buf = Vec::new();
+        write_message(&mut buf, &response).unwrap();
+        fs::write(&response_path, buf).unwrap();
+
+        let first_script = first_script.to_string();
+        let spawn_count = Arc::new(AtomicUsize::new(0));
+        let worker_spawn_count = spawn_count.clone();
+        let worker = ConversionWorker::new(
+            Duration::from_millis(500),
+            0,
+            Box::new(move || {
+                let script = match worker_spawn_count.fetch_add(1, Ordering::SeqCst) {
+                    0 => first_script.clone(),
+                    _ => format!("cat '{}'; cat > /dev/null", response_path.display()),
+                };
+                let mut command = Command::new("sh");
+                command.arg("-c").arg(script);
+                return Ok(command);
+            }),
+        );
+
+        return (worker, spawn_count, response_dir);
+    }
+}
@@ -0,0 +1,64 @@
+use crate::conversion::conversion_registry::ConversionRegistry;
+use crate::conversion::sandbox::{read_message, write_message};
+use crate::conversion_worker::conversion_response::Result as ConversionResult;
+use crate::conversion_worker::{ConversionRequest, ConversionResponse};
+use anyhow::{anyhow, Error};
+use dscvr_common::config::IndexerSettings;
+use log::{debug, info};
+use std::env;
+use std::io;
+
+/// The indexer starts a copy of itself with this argument to run the conversions.
+pub(crate) const CONVERSION_WORKER_ARG: &str = "--conversion-worker";
+
+/// Returns the memory limit of the worker, if the process was started as conversion worker.
+pub(crate) fn get_conversion_worker_memory_limit() -> Option<u64> {
+    let mut args = env::args().skip(1);
+    if args.next().as_deref() != Some(CONVERSION_WORKER_ARG) {
+        return None;
+    }
+
+    return Some(args.next().and_then(|v| v.parse().ok()).unwrap_or(0));
+}
+
+/// Converts the files sent over stdin until stdin is closed by the indexer.
+pub(crate) fn run_conversion_worker(
+    memory_limit: u64,
+    settings: &IndexerSettings,
+) -> Result<(), Error> {
+    info!(
+        "Started the conversion worker with a memory limit of {} bytes.",
+        memory_limit
+    );
+
+    let conversions = ConversionRegistry::get_available_conversions(settings);
+    let mut stdin = io::stdin().lock();
+    let mut stdout = io::stdout().lock();
+
+    loop {
+        let request = match read_message::<ConversionRequest>(&mut stdin)? {
+            Some(v) => v,
+            None => {
+                debug!("The indexer closed the connection to the conversion worker.");
+                return Ok(());
+            }
+        };
+
+        let result = match conversions.get(request.conversion.as_str()) {
+            Some(v) => v.convert(request.contents),
+            None => Err(anyhow!(
+                "The conversion {:?} doesn't exist.",
+                request.conversion
+            )),
+        };
+
+        let response = ConversionResponse {
+            result: Some(match result {
+                Ok(v) => ConversionResult::Document(v.into()),
+                Err(e) => ConversionResult::Error(format!("{:?}", e)),
+            }),
+        };
+
+        write_message(&mut stdout, &response)?;
+    }
+}
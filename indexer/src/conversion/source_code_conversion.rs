@@ -0,0 +1,205 @@
+use crate::conversion::clear_text_conversion::decode_text;
+use crate::conversion::{Conversion, ConvertedDocument};
+use anyhow::Error;
+use regex::Regex;
+use std::collections::HashSet;
+
+/// Generated files can define a huge amount of symbols, the rest is still found through the contents.
+const MAX_SYMBOLS: usize = 10000;
+
+/// Words that the patterns for methods would otherwise take for a name, e.g. in `} else if (x) {`.
+const KEYWORDS: &[&str] = &[
+    "catch",
+    "do",
+    "else",
+    "for",
+    "foreach",
+    "function",
+    "if",
+    "lock",
+    "new",
+    "return",
+    "sizeof",
+    "switch",
+    "synchronized",
+    "typeof",
+    "using",
+    "when",
+    "while",
+];
+
+const RUST_PATTERNS: &[&str] = &[
+    r#"(?m)^\s*(?:pub(?:\s*\([^)]*\))?\s+)?(?:(?:const|async|unsafe|extern\s+"[^"]*")\s+)*fn\s+(?P<name>[A-Za-z_]\w*)"#,
+    r"(?m)^\s*(?:pub(?:\s*\([^)]*\))?\s+)?(?:(?:unsafe|auto)\s+)?(?:struct|enum|trait|type|union|mod)\s+(?P<name>[A-Za-z_]\w*)",
+    r"(?m)^\s*macro_rules!\s*(?P<name>[A-Za-z_]\w*)",
+];
+
+const PYTHON_PATTERNS: &[&str] = &[
+    r"(?m)^\s*(?:async\s+)?def\s+(?P<name>\w+)",
+    r"(?m)^\s*class\s+(?P<name>\w+)",
+];
+
+/// Shared by JavaScript and TypeScript, whose additional keywords never occur in JavaScript.
+const ECMASCRIPT_PATTERNS: &[&str] = &[
+    r"(?m)^\s*(?:export\s+)?(?:default\s+)?(?:declare\s+)?(?:async\s+)?function\s*\*?\s*(?P<name>[A-Za-z_$][\w$]*)",
+    r"(?m)^\s*(?:export\s+)?(?:default\s+)?(?:declare\s+)?(?:abstract\s+)?(?:const\s+)?(?:class|interface|type|enum|namespace|module)\s+(?P<name>[A-Za-z_$][\w$]*)",
+    r"(?m)^\s*(?:export\s+)?(?:const|let|var)\s+(?P<name>[A-Za-z_$][\w$]*)\s*(?::[^=]+)?=\s*(?:async\s+)?(?:function\b|\([^)]*\)\s*(?::[^=]+)?=>|[A-Za-z_$][\w$]*\s*=>)",
+    r"(?m)^\s+(?:(?:public|private|protected|static|async|readonly|abstract|override|get|set)\s+)*(?P<name>[A-Za-z_$][\w$]*)\s*(?:<[^>]*>)?\s*\([^)]*\)\s*(?::\s*[^{;]+)?\{",
+];
+
+/// Shared by Java, C# and Groovy.
+const CLASS_BASED_PATTERNS: &[&str] = &[
+    r"(?m)^\s*(?:(?:public|private|protected|internal|static|final|abstract|sealed|non-sealed|partial|readonly|unsafe|new|strictfp)\s+)*(?:class|interface|enum|record|struct|trait|@interface)\s+(?P<name>\w+)",
+    r"(?m)^\s*(?:(?:public|private|protected|internal|static|final|abstract|synchronized|native|virtual|override|async|sealed|extern|unsafe|default|def|new|partial)\s+)*(?:<[^>]+>\s+)?[\w<>\[\]?,.\s]*?\b(?P<name>\w+)\s*\([^;{}]*\)\s*(?:throws\s+[\w.,\s]+)?\{",
+    r"(?m)^\s*(?:package|namespace)\s+(?P<name>[\w.]+)",
+];
+
+/// Shared by C and C++, functions are only recognized as definitions if they start at the beginning of a line.
+const C_FAMILY_PATTERNS: &[&str] = &[
+    r"(?m)^(?:[\w:*&<>,~]+[ \t*&]+)+(?:\w+::)*~?(?P<name>[A-Za-z_]\w*)\s*\([^;{}()]*(?:\([^()]*\)[^;{}()]*)*\)\s*(?:const\s*)?(?:noexcept\s*)?(?:override\s*)?\{",
+    r"(?m)^\s*(?:typedef\s+)?(?:struct|class|enum(?:\s+class)?|union|namespace)\s+(?P<name>[A-Za-z_]\w*)\s*(?::[^;{]*)?\{",
+    r"(?m)^\s*#\s*define\s+(?P<name>[A-Za-z_]\w*)",
+];
+
+const PHP_PATTERNS: &[&str] = &[
+    r"(?m)^\s*(?:(?:public|private|protected|static|abstract|final)\s+)*function\s+&?\s*(?P<name>\w+)",
+    r"(?m)^\s*(?:(?:abstract|final|readonly)\s+)*(?:class|interface|trait|enum)\s+(?P<name>\w+)",
+    r"(?m)^\s*namespace\s+(?P<name>[\w\\]+)",
+];
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum ProgrammingLanguage {
+    Rust,
+    TypeScript,
+    JavaScript,
+    Python,
+    Java,
+    CSharp,
+    C,
+    Cpp,
+    Php,
+    Groovy,
+}
+
+impl ProgrammingLanguage {
+    fn get_definition_patterns(&self) -> &'static [&'static str] {
+        return match self {
+            ProgrammingLanguage::Rust => RUST_PATTERNS,
+            ProgrammingLanguage::TypeScript | ProgrammingLanguage::JavaScript => {
+                ECMASCRIPT_PATTERNS
+            }
+            ProgrammingLanguage::Python => PYTHON_PATTERNS,
+            ProgrammingLanguage::Java
+            | ProgrammingLanguage::CSharp
+            | ProgrammingLanguage::Groovy => CLASS_BASED_PATTERNS,
+            ProgrammingLanguage::C | ProgrammingLanguage::Cpp => C_FAMILY_PATTERNS,
+            ProgrammingLanguage::Php => PHP_PATTERNS,
+        };
+    }
+}
+
+/// Indexes source code as text and extracts the names of its function, type and module definitions as `symbols`.
+/// The definitions are found by patterns instead of a parser, which is good enough to jump to them.
+pub(crate) struct SourceCodeConversion {
+    definition_patterns: Vec<Regex>,
+}
+
+impl SourceCodeConversion {
+    pub(crate) fn new(language: ProgrammingLanguage) -> Self {
+        let definition_patterns = language
+            .get_definition_patterns()
+            .iter()
+            .map(|v| Regex::new(v).expect("The definition patterns have to be valid."))
+            .collect();
+
+        return Self {
+            definition_patterns,
+        };
+    }
+
+    fn find_symbols(&self, text: &str) -> Vec<String> {
+        let mut definitions = self
+            .definition_patterns
+            .iter()
+            .flat_map(|pattern| pattern.captures_iter(text))
+            .filter_map(|captures| captures.name("name"))
+            .filter(|v| !KEYWORDS.contains(&v.as_str()))
+            .map(|v| (v.start(), v.as_str()))
+            .collect::<Vec<_>>();
+        // The patterns are applied one after another, sorting restores the order of the definitions in the file.
+        definitions.sort_by_key(|(start, _)| *start);
+
+        let mut seen = HashSet::new();
+        return definitions
+            .into_iter()
+            .map(|(_, name)| name.to_string())
+            .filter(|name| seen.insert(name.clone()))
+            .take(MAX_SYMBOLS)
+            .collect();
+    }
+}
+
+impl Conversion for SourceCodeConversion {
+    fn convert(&self, buf: Vec<u8>) -> Result<ConvertedDocument, Error> {
+        let (contents, encoding) = decode_text(&buf);
+        let symbols = self.find_symbols(&contents);
+
+        let mut converted_document =
+            ConvertedDocument::new(contents).with_metadata("encoding", encoding.name().to_string());
+        for symbol in symbols {
+            converted_document = converted_document.with_metadata("symbols", symbol);
+        }
+
+        return Ok(converted_document);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_if_definitions_are_extracted() {
+        let cases: &[(ProgrammingLanguage, &str, &[&str])] = &[
+            (
+                ProgrammingLanguage::Rust,
+                "pub(crate) mod search;\n\npub struct Query;\n\nimpl Query {\n    pub async fn parse_query(&self) {\n        parse(1);\n    }\n}\n",
+                &["search", "Query", "parse_query"],
+            ),
+            (
+                ProgrammingLanguage::Python,
+                "class Parser:\n    async def parse_query(self):\n        if x:\n            pass\n",
+                &["Parser", "parse_query"],
+            ),
+            (
+                ProgrammingLanguage::TypeScript,
+                "export interface Hit {}\nexport const search = async (term: string) => {};\nclass Client {\n  private async fetchHits(term: string): Promise<Hit[]> {\n    if (term) {\n    }\n  }\n}\n",
+                &["Hit", "search", "Client", "fetchHits"],
+            ),
+            (
+                ProgrammingLanguage::Java,
+                "package dev.dscvr;\n\npublic class Indexer {\n    public List<Hit> search(String term) throws IOException {\n        if (term == null) {\n        }\n    }\n}\n",
+                &["dev.dscvr", "Indexer", "search"],
+            ),
+            (
+                ProgrammingLanguage::Cpp,
+                "#define MAX_HITS 10\nnamespace dscvr {\nstatic int Index::count_hits(const char *term)\n{\n    return 0;\n}\n}\n",
+                &["MAX_HITS", "dscvr", "count_hits"],
+            ),
+        ];
+
+        for (language, source, expected) in cases {
+            let result = SourceCodeConversion::new(*language)
+                .convert(source.as_bytes().to_vec())
+                .unwrap();
+
+            let symbols = result
+                .metadata
+                .iter()
+                .filter(|(name, _)| name == "symbols")
+                .map(|(_, value)| value.as_str())
+                .collect::<Vec<_>>();
+            assert_eq!(&symbols, expected, "{:?}", language);
+        }
+    }
+}
@@ -0,0 +1,312 @@
+use crate::conversion::clear_text_conversion::decode_text;
+use crate::conversion::{Conversion, ConvertedDocument};
+use crate::tokenizer::key_value_tokenizer::KEY_VALUE_SEPARATOR;
+use anyhow::Error;
+use log::debug;
+use serde::Deserialize;
+
+/// Large data files are still indexed completely as contents, but only this many pairs can be searched as `key:value`.
+const MAX_KEY_VALUES: usize = 10000;
+
+/// The delimiters that are recognized in the header of CSV files, in the order of preference.
+const CSV_DELIMITERS: [u8; 4] = [b',', b';', b'\t', b'|'];
+
+enum StructuredDataFormat {
+    Csv,
+    Json,
+    Yaml,
+    Toml,
+}
+
+/// Indexes CSV files as headers paired with cell values and JSON, YAML and TOML files as key paths paired with
+/// values, e.g. `dependencies.serde.version: 1.0`. Files that can't be parsed are indexed as clear text.
+pub(crate) struct StructuredDataConversion {
+    format: StructuredDataFormat,
+}
+
+impl StructuredDataConversion {
+    pub(crate) fn csv() -> Self {
+        return Self {
+            format: StructuredDataFormat::Csv,
+        };
+    }
+
+    pub(crate) fn json() -> Self {
+        return Self {
+            format: StructuredDataFormat::Json,
+        };
+    }
+
+    pub(crate) fn yaml() -> Self {
+        return Self {
+            format: StructuredDataFormat::Yaml,
+        };
+    }
+
+    pub(crate) fn toml() -> Self {
+        return Self {
+            format: StructuredDataFormat::Toml,
+        };
+    }
+}
+
+impl Conversion for StructuredDataConversion {
+    fn convert(&self, buf: Vec<u8>) -> Result<ConvertedDocument, Error> {
+        let (text, encoding) = decode_text(&buf);
+
+        let groups = match self.format {
+            StructuredDataFormat::Csv => read_csv(&text),
+            StructuredDataFormat::Json => serde_json::from_str(&text)
+                .map(|v| vec![flatten(Node::from_json(v))])
+                .map_err(Error::from),
+            StructuredDataFormat::Yaml => read_yaml(&text),
+            StructuredDataFormat::Toml => text
+                .parse::<toml::Table>()
+                .map(|v| vec![flatten(Node::from_toml(toml::Value::Table(v)))])
+                .map_err(Error::from),
+        };
+
+        let groups = match groups {
+            Ok(v) => v,
+            Err(e) => {
+                // E.g. JSON with comments, which is common for configuration files.
+                debug!(
+                    "The structured data couldn't be parsed, it is indexed as clear text: {:?}",
+                    e
+                );
+                return Ok(ConvertedDocument::new(text)
+                    .with_metadata("encoding", encoding.name().to_string()));
+            }
+        };
+
+        let contents = groups
+            .iter()
+            .map(|pairs| {
+                return pairs
+                    .iter()
+                    .map(|(key, value)| match key.is_empty() {
+                        true => value.clone(),
+                        false => format!("{}{}{}", key, KEY_VALUE_SEPARATOR, value),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        let mut converted_document =
+            ConvertedDocument::new(contents).with_metadata("encoding", encoding.name().to_string());
+        for (key, value) in groups
+            .into_iter()
+            .flatten()
+            .filter(|(key, value)| !key.is_empty() && !value.trim().is_empty())
+            .take(MAX_KEY_VALUES)
+        {
+            converted_document = converted_document.with_metadata(
+                "key_values",
+                format!("{}{}{}", key, KEY_VALUE_SEPARATOR, value),
+            );
+        }
+
+        return Ok(converted_document);
+    }
+}
+
+/// Every row is a group of pairs of its headers and cells, empty cells are left out.
+fn read_csv(text: &str) -> Result<Vec<Vec<(String, String)>>, Error> {
+    // The delimiter that occurs most often in the header wins, earlier ones are preferred on ties.
+    let first_line = text.lines().next().unwrap_or_default();
+    let (delimiter, _) = CSV_DELIMITERS.iter().fold((b',', 0), |best, delimiter| {
+        let count = first_line.bytes().filter(|v| v == delimiter).count();
+        return match count > best.1 {
+            true => (*delimiter, count),
+            false => best,
+        };
+    });
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+    let headers = reader.headers()?.clone();
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let row = record
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| !cell.trim().is_empty())
+            .map(|(i, cell)| {
+                let header = match headers.get(i).map(str::trim).filter(|v| !v.is_empty()) {
+                    Some(v) => v.to_string(),
+                    None => format!("column{}", i + 1),
+                };
+                return (header, cell.trim().to_string());
+            })
+            .collect::<Vec<_>>();
+
+        if !row.is_empty() {
+            rows.push(row);
+        }
+    }
+
+    return Ok(rows);
+}
+
+/// Files with multiple documents, separated by `---`, have a group of pairs for every document.
+fn read_yaml(text: &str) -> Result<Vec<Vec<(String, String)>>, Error> {
+    let mut documents = Vec::new();
+    for document in serde_yaml::Deserializer::from_str(text) {
+        let value = serde_yaml::Value::deserialize(document)?;
+        documents.push(flatten(Node::from_yaml(value)));
+    }
+
+    return Ok(documents);
+}
+
+/// The common structure of JSON, YAML and TOML documents.
+enum Node {
+    Empty,
+    Scalar(String),
+    List(Vec<Node>),
+    Map(Vec<(String, Node)>),
+}
+
+impl Node {
+    fn from_json(value: serde_json::Value) -> Self {
+        return match value {
+            serde_json::Value::Null => Node::Empty,
+            serde_json::Value::String(v) => Node::Scalar(v),
+            serde_json::Value::Array(v) => Node::List(v.into_iter().map(Self::from_json).collect()),
+            serde_json::Value::Object(v) => Node::Map(
+                v.into_iter()
+                    .map(|(key, value)| (key, Self::from_json(value)))
+                    .collect(),
+            ),
+            v => Node::Scalar(v.to_string()),
+        };
+    }
+
+    fn from_yaml(value: serde_yaml::Value) -> Self {
+        return match value {
+            serde_yaml::Value::Null => Node::Empty,
+            serde_yaml::Value::Bool(v) => Node::Scalar(v.to_string()),
+            serde_yaml::Value::Number(v) => Node::Scalar(v.to_string()),
+            serde_yaml::Value::String(v) => Node::Scalar(v),
+            serde_yaml::Value::Sequence(v) => {
+                Node::List(v.into_iter().map(Self::from_yaml).collect())
+            }
+            serde_yaml::Value::Mapping(v) => Node::Map(
+                v.into_iter()
+                    .filter_map(|(key, value)| match Self::from_yaml(key) {
+                        Node::Scalar(key) => Some((key, Self::from_yaml(value))),
+                        // Complex keys are allowed, but have no sensible path.
+                        _ => None,
+                    })
+                    .collect(),
+            ),
+            serde_yaml::Value::Tagged(v) => Self::from_yaml(v.value),
+        };
+    }
+
+    fn from_toml(value: toml::Value) -> Self {
+        return match value {
+            toml::Value::String(v) => Node::Scalar(v),
+            toml::Value::Array(v) => Node::List(v.into_iter().map(Self::from_toml).collect()),
+            toml::Value::Table(v) => Node::Map(
+                v.into_iter()
+                    .map(|(key, value)| (key, Self::from_toml(value)))
+                    .collect(),
+            ),
+            v => Node::Scalar(v.to_string()),
+        };
+    }
+}
+
+/// Pairs every scalar with the path of keys that leads to it. The items of lists share the path of the list, so
+/// `authors: [a, b]` becomes `authors: a` and `authors: b`.
+fn flatten(node: Node) -> Vec<(String, String)> {
+    let mut pairs = Vec::new();
+    let mut stack = vec![(String::new(), node)];
+
+    while let Some((path, node)) = stack.pop() {
+        match node {
+            Node::Empty => {}
+            Node::Scalar(v) => pairs.push((path, v)),
+            Node::List(v) => stack.extend(v.into_iter().rev().map(|v| (path.clone(), v))),
+            Node::Map(v) => stack.extend(v.into_iter().rev().map(|(key, value)| {
+                return match path.is_empty() {
+                    true => (key, value),
+                    false => (format!("{}.{}", path, key), value),
+                };
+            })),
+        }
+    }
+
+    return pairs;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_key_values(converted_document: &ConvertedDocument) -> Vec<&str> {
+        return converted_document
+            .metadata
+            .iter()
+            .filter(|(name, _)| name == "key_values")
+            .map(|(_, value)| value.as_str())
+            .collect();
+    }
+
+    #[test]
+    fn test_if_key_paths_are_paired_with_values() {
+        let toml = r#"
+            [package]
+            name = "dscvr"
+
+            [dependencies]
+            serde = { version = "1.0", features = ["derive"] }
+        "#;
+
+        let result = StructuredDataConversion::toml()
+            .convert(toml.as_bytes().to_vec())
+            .unwrap();
+
+        assert_eq!(
+            get_key_values(&result),
+            vec![
+                "dependencies.serde.features: derive",
+                "dependencies.serde.version: 1.0",
+                "package.name: dscvr",
+            ]
+        );
+
+        let yaml =
+            "services:\n  web:\n    image: nginx\n    ports:\n      - 80\n---\nkind: Service\n";
+        let result = StructuredDataConversion::yaml()
+            .convert(yaml.as_bytes().to_vec())
+            .unwrap();
+
+        assert_eq!(
+            result.contents,
+            "services.web.image: nginx\nservices.web.ports: 80\n\nkind: Service"
+        );
+    }
+
+    #[test]
+    fn test_if_csv_headers_are_paired_with_cells() {
+        let csv = "name;city;zip\nJane Doe;Berlin;\nJohn Doe;\"Munich; Bavaria\";80331\n";
+
+        let result = StructuredDataConversion::csv()
+            .convert(csv.as_bytes().to_vec())
+            .unwrap();
+
+        assert_eq!(
+            result.contents,
+            "name: Jane Doe\ncity: Berlin\n\nname: John Doe\ncity: Munich; Bavaria\nzip: 80331"
+        );
+        assert_eq!(get_key_values(&result).len(), 5);
+    }
+}
@@ -0,0 +1,284 @@
+use crate::file_index::query_history_strategy::QueryHistoryStrategy;
+use crate::file_indexer::{Suggestion, SuggestionSource};
+use log::error;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+use std::sync::Arc;
+use tantivy::schema::Schema;
+use tantivy::{IndexReader, Searcher, Term};
+
+pub(crate) const DEFAULT_COMPLETION_LIMIT: usize = 10;
+const MAX_COMPLETION_LIMIT: usize = 100;
+
+/// Short prefixes match a large part of the term dictionary, so only this many terms are scanned in every segment.
+/// This keeps completions fast on large indices, the most frequent ones of the scanned terms are still found regardless
+/// of where they are in the alphabet.
+const MAX_SCANNED_TERMS_PER_SEGMENT: usize = 100_000;
+
+/// Completes what the user typed so far, for a launcher-style search as you type.
+pub(crate) trait AutocompleteStrategy: Send + Sync {
+    /// Returns the completions of past queries, file names and words of the contents, in this order.
+    fn complete(self: &Self, prefix: &str, limit: usize) -> Vec<Suggestion>;
+}
+
+pub(crate) struct TantivyAutocompleteStrategy {
+    index_reader: IndexReader,
+    schema: Schema,
+    query_history_strategy: Arc<dyn QueryHistoryStrategy>,
+}
+
+impl TantivyAutocompleteStrategy {
+    pub(crate) fn new(
+        index_reader: IndexReader,
+        schema: Schema,
+        query_history_strategy: Arc<impl QueryHistoryStrategy + 'static>,
+    ) -> Self {
+        return Self {
+            index_reader,
+            schema,
+            query_history_strategy,
+        };
+    }
+
+    fn complete_terms(
+        self: &Self,
+        searcher: &Searcher,
+        field_name: &str,
+        prefix: &str,
+        limit: usize,
+    ) -> Vec<(String, u64)> {
+        if prefix.is_empty() {
+            return Vec::new();
+        }
+
+        let field = match self.schema.get_field(field_name) {
+            Ok(v) => v,
+            Err(e) => {
+                error!(
+                    "There was an error while trying to get field {:?} from index: {:?}",
+                    field_name, e
+                );
+                return Vec::new();
+            }
+        };
+
+        // The most frequent terms of every segment are the candidates, their frequency in the whole index is looked up
+        // afterwards, because a term can be frequent in one segment and not be a candidate in another.
+        let mut candidates = HashSet::new();
+        for segment_reader in searcher.segment_readers() {
+            let inverted_index = match segment_reader.inverted_index(field) {
+                Ok(v) => v,
+                Err(e) => {
+                    error!(
+                        "There was an error while trying to read the terms of a segment: {:?}",
+                        e
+                    );
+                    continue;
+                }
+            };
+
+            let mut term_stream = match inverted_index
+                .terms()
+                .range()
+                .ge(prefix.as_bytes())
+                .into_stream()
+            {
+                Ok(v) => v,
+                Err(e) => {
+                    error!(
+                        "There was an error while trying to search the terms of a segment: {:?}",
+                        e
+                    );
+                    continue;
+                }
+            };
+
+            let mut most_frequent_terms = BinaryHeap::<Reverse<(u32, Vec<u8>)>>::new();
+            let mut scanned_terms = 0;
+            while scanned_terms < MAX_SCANNED_TERMS_PER_SEGMENT && term_stream.advance() {
+                if !term_stream.key().starts_with(prefix.as_bytes()) {
+                    break;
+                }
+                scanned_terms += 1;
+
+                let doc_freq = term_stream.value().doc_freq;
+                let is_more_frequent = match most_frequent_terms.peek() {
+                    Some(Reverse((least_doc_freq, _))) => doc_freq > *least_doc_freq,
+                    None => true,
+                };
+                if most_frequent_terms.len() < limit || is_more_frequent {
+                    most_frequent_terms.push(Reverse((doc_freq, term_stream.key().to_vec())));
+                    if most_frequent_terms.len() > limit {
+                        most_frequent_terms.pop();
+                    }
+                }
+            }
+
+            candidates.extend(
+                most_frequent_terms
+                    .into_iter()
+                    .filter_map(|Reverse((_, term))| String::from_utf8(term).ok()),
+            );
+        }
+
+        let mut terms = candidates
+            .into_iter()
+            .filter_map(|v| {
+                return match searcher.doc_freq(&Term::from_field_text(field, &v)) {
+                    Ok(doc_freq) => Some((v, doc_freq)),
+                    Err(e) => {
+                        error!(
+                            "There was an error while trying to count the files containing the term {:?}: {:?}",
+                            v, e
+                        );
+                        None
+                    }
+                };
+            })
+            .collect::<Vec<_>>();
+        terms.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        terms.truncate(limit);
+
+        return terms;
+    }
+}
+
+impl AutocompleteStrategy for TantivyAutocompleteStrategy {
+    fn complete(self: &Self, prefix: &str, limit: usize) -> Vec<Suggestion> {
+        let limit = limit.min(MAX_COMPLETION_LIMIT);
+        let prefix = prefix.trim_start().to_lowercase();
+        let searcher = self.index_reader.searcher();
+
+        let past_queries = match self
+            .query_history_strategy
+            .find_queries_by_prefix(&prefix, limit)
+        {
+            Ok(v) => v,
+            Err(e) => {
+                error!(
+                    "There was an error while trying to find past queries for autocompletion: {:?}",
+                    e
+                );
+                Vec::new()
+            }
+        };
+
+        let file_names = self.complete_terms(&searcher, "file_name", &prefix, limit);
+
+        // Only the last word is completed, the words before it are kept as they are.
+        let (head, last_word) = match prefix.rfind(char::is_whitespace) {
+            Some(i) => prefix.split_at(i + 1),
+            None => ("", prefix.as_str()),
+        };
+        let contents = self
+            .complete_terms(&searcher, "contents", last_word, limit)
+            .into_iter()
+            .map(|(term, doc_freq)| (format!("{}{}", head, term), doc_freq));
+
+        let mut seen = HashSet::new();
+        return past_queries
+            .into_iter()
+            .map(|v| (v, SuggestionSource::PastQuery))
+            .chain(
+                file_names
+                    .into_iter()
+                    .map(|v| (v, SuggestionSource::FileName)),
+            )
+            .chain(contents.map(|v| (v, SuggestionSource::Content)))
+            .filter(|((text, _), _)| seen.insert(text.clone()))
+            .take(limit)
+            .map(|((text, frequency), source)| Suggestion {
+                text,
+                source: source as i32,
+                frequency,
+            })
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_index::query_history_strategy::SqliteQueryHistory;
+    use crate::tokenizer::{register_tokenizers, FILE_NAME_TOKENIZER};
+    use rusqlite::Connection;
+    use tantivy::schema::{IndexRecordOption, TextFieldIndexing, TextOptions, TEXT};
+    use tantivy::{doc, Index};
+
+    #[test]
+    fn test_if_prefix_is_completed_from_all_sources() {
+        let mut schema_builder = Schema::builder();
+        let contents_field = schema_builder.add_text_field("contents", TEXT);
+        let file_name_field = schema_builder.add_text_field(
+            "file_name",
+            TextOptions::default().set_indexing_options(
+                TextFieldIndexing::default()
+                    .set_tokenizer(FILE_NAME_TOKENIZER)
+                    .set_index_option(IndexRecordOption::Basic),
+            ),
+        );
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema.clone());
+        register_tokenizers(&index);
+
+        let mut index_writer = index.writer(15_000_000).unwrap();
+        index_writer
+            .add_document(doc!(contents_field => "the quarterly report", file_name_field => "Quarterly Numbers.xlsx"))
+            .unwrap();
+        index_writer
+            .add_document(doc!(contents_field => "quarterly reports and a quartet", file_name_field => "notes.txt"))
+            .unwrap();
+        index_writer.commit().unwrap();
+
+        let query_history =
+            Arc::new(SqliteQueryHistory::new(Connection::open_in_memory().unwrap()).unwrap());
+        query_history.record_query("quarterly report").unwrap();
+
+        let autocomplete_strategy =
+            TantivyAutocompleteStrategy::new(index.reader().unwrap(), schema, query_history);
+
+        let completions = autocomplete_strategy
+            .complete("Quart", 10)
+            .into_iter()
+            .map(|v| (v.source(), v.text, v.frequency))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            completions,
+            vec![
+                (
+                    SuggestionSource::PastQuery,
+                    "quarterly report".to_string(),
+                    1
+                ),
+                (
+                    SuggestionSource::FileName,
+                    "quarterly numbers.xlsx".to_string(),
+                    1
+                ),
+                (SuggestionSource::Content, "quarterly".to_string(), 2),
+                (SuggestionSource::Content, "quartet".to_string(), 1),
+            ]
+        );
+
+        let completions = autocomplete_strategy.complete("quarterly rep", 10);
+        assert_eq!(completions[0].text, "quarterly report");
+        assert_eq!(completions[1].text, "quarterly reports");
+        assert_eq!(completions.len(), 2);
+
+        // Every commit creates a segment, the frequencies are counted in all of them.
+        for _ in 0..2 {
+            index_writer
+                .add_document(doc!(contents_field => "a quartet", file_name_field => "quartet.txt"))
+                .unwrap();
+            index_writer.commit().unwrap();
+        }
+        autocomplete_strategy.index_reader.reload().unwrap();
+
+        let searcher = autocomplete_strategy.index_reader.searcher();
+        assert_eq!(searcher.segment_readers().len(), 3);
+        assert_eq!(
+            autocomplete_strategy.complete_terms(&searcher, "contents", "quart", 1),
+            vec![("quartet".to_string(), 3)]
+        );
+    }
+}
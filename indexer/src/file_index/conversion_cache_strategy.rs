@@ -0,0 +1,271 @@
+use crate::conversion::ConvertedDocument;
+use anyhow::anyhow;
+use dscvr_common::config::AppSettings;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use log::{debug, error};
+use prost::Message;
+use rusqlite::{Connection, OptionalExtension};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Eviction frees a bit more than necessary, so it doesn't run again on every insert.
+const EVICTION_TARGET_RATIO: f64 = 0.9;
+
+pub(crate) trait ConversionCacheStrategy: Send + Sync {
+    fn get(&self, hash: &str, cache_key: &str) -> Result<Option<ConvertedDocument>, anyhow::Error>;
+    fn put(
+        &self,
+        hash: &str,
+        cache_key: &str,
+        document: &ConvertedDocument,
+    ) -> Result<(), anyhow::Error>;
+}
+
+/// Used when the conversion cache is disabled in the settings.
+pub(crate) struct NoConversionCache;
+
+impl ConversionCacheStrategy for NoConversionCache {
+    fn get(&self, _: &str, _: &str) -> Result<Option<ConvertedDocument>, anyhow::Error> {
+        return Ok(None);
+    }
+
+    fn put(&self, _: &str, _: &str, _: &ConvertedDocument) -> Result<(), anyhow::Error> {
+        return Ok(());
+    }
+}
+
+/// Stores the compressed conversions in their own database, so the cache can simply be deleted.
+pub(crate) struct SqliteConversionCache {
+    conn: Arc<Mutex<Connection>>,
+    max_size: u64,
+    /// The size of all cached conversions, which is only summed up once, so writing doesn't scan the whole cache.
+    total_size: AtomicU64,
+}
+
+impl SqliteConversionCache {
+    pub(crate) fn build_with_settings(settings: &AppSettings) -> Result<Self, anyhow::Error> {
+        let path_to_db = Path::new(&settings.common.base_dir)
+            .join(&settings.indexer.conversion_cache.db_file_name);
+
+        let connection = Connection::open(path_to_db)?;
+
+        return Self::new(connection, settings.indexer.conversion_cache.max_size);
+    }
+
+    fn new(connection: Connection, max_size: u64) -> Result<Self, anyhow::Error> {
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS conversion_cache (hash VARCHAR(64) NOT NULL, cache_key VARCHAR(64) NOT NULL, document BLOB NOT NULL, size INT(64) NOT NULL, last_used_at INT(64) NOT NULL, PRIMARY KEY (hash, cache_key))",
+            (),
+        )?;
+        connection.execute(
+            "CREATE INDEX IF NOT EXISTS conversion_cache_last_used_at ON conversion_cache (last_used_at)",
+            (),
+        )?;
+
+        let total_size: u64 = connection.query_row(
+            "SELECT COALESCE(SUM(size), 0) FROM conversion_cache",
+            (),
+            |row| row.get(0),
+        )?;
+
+        return Ok(SqliteConversionCache {
+            conn: Arc::new(Mutex::new(connection)),
+            max_size,
+            total_size: AtomicU64::new(total_size),
+        });
+    }
+
+    /// Removes the least recently used conversions until the cache is below its maximum size again and returns its
+    /// new size.
+    fn evict(&self, conn: &Connection, total_size: u64) -> Result<u64, anyhow::Error> {
+        if total_size <= self.max_size {
+            return Ok(total_size);
+        }
+
+        let target_size = (self.max_size as f64 * EVICTION_TARGET_RATIO) as u64;
+        let mut statement = conn.prepare(
+            "SELECT hash, cache_key, size FROM conversion_cache ORDER BY last_used_at ASC, rowid ASC",
+        )?;
+        let mut rows = statement.query(())?;
+
+        let mut size = total_size;
+        let mut evicted = Vec::new();
+        while size > target_size {
+            let row = match rows.next()? {
+                Some(v) => v,
+                None => break,
+            };
+
+            size -= row.get::<_, u64>(2)?;
+            evicted.push((row.get::<_, String>(0)?, row.get::<_, String>(1)?));
+        }
+
+        drop(rows);
+        drop(statement);
+
+        for (hash, cache_key) in &evicted {
+            conn.execute(
+                "DELETE FROM conversion_cache WHERE hash = ? AND cache_key = ?",
+                (hash, cache_key),
+            )?;
+        }
+
+        debug!(
+            "Evicted {} conversions from the conversion cache, which now has a size of {} bytes.",
+            evicted.len(),
+            size
+        );
+
+        return Ok(size);
+    }
+}
+
+impl ConversionCacheStrategy for SqliteConversionCache {
+    fn get(&self, hash: &str, cache_key: &str) -> Result<Option<ConvertedDocument>, anyhow::Error> {
+        let guard = match self.conn.lock() {
+            Ok(v) => v,
+            Err(e) => {
+                error!("There was an error while trying to acquire the lock for the connection when trying to read the conversion cache: {:?}", e);
+                return Err(anyhow!("Poison Error"));
+            }
+        };
+
+        let compressed: Option<Vec<u8>> = guard
+            .query_row(
+                "SELECT document FROM conversion_cache WHERE hash = ? AND cache_key = ?",
+                (hash, cache_key),
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        let compressed = match compressed {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+
+        guard.execute(
+            "UPDATE conversion_cache SET last_used_at = ? WHERE hash = ? AND cache_key = ?",
+            (
+                chrono::offset::Local::now().timestamp_micros(),
+                hash,
+                cache_key,
+            ),
+        )?;
+
+        drop(guard);
+
+        let mut buf = Vec::new();
+        ZlibDecoder::new(compressed.as_slice()).read_to_end(&mut buf)?;
+        let document = crate::conversion_worker::ConvertedDocument::decode(buf.as_slice())?;
+
+        return Ok(Some(document.into()));
+    }
+
+    fn put(
+        &self,
+        hash: &str,
+        cache_key: &str,
+        document: &ConvertedDocument,
+    ) -> Result<(), anyhow::Error> {
+        let message = crate::conversion_worker::ConvertedDocument::from(document.clone());
+
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&message.encode_to_vec())?;
+        let compressed = encoder.finish()?;
+
+        let mut guard = match self.conn.lock() {
+            Ok(v) => v,
+            Err(e) => {
+                error!("There was an error while trying to acquire the lock for the connection when trying to write the conversion cache: {:?}", e);
+                return Err(anyhow!("Poison Error"));
+            }
+        };
+
+        let transaction = guard.transaction()?;
+
+        let replaced_size: u64 = transaction
+            .query_row(
+                "SELECT size FROM conversion_cache WHERE hash = ? AND cache_key = ?",
+                (hash, cache_key),
+                |row| row.get(0),
+            )
+            .optional()?
+            .unwrap_or(0);
+
+        transaction.execute(
+            "\
+                INSERT OR REPLACE INTO conversion_cache (hash, cache_key, document, size, last_used_at) VALUES (?, ?, ?, ?, ?);
+            ",
+            (
+                hash,
+                cache_key,
+                &compressed,
+                compressed.len() as u64,
+                chrono::offset::Local::now().timestamp_micros(),
+            ),
+        )?;
+
+        let total_size = (self.total_size.load(Ordering::Relaxed) + compressed.len() as u64)
+            .saturating_sub(replaced_size);
+        let total_size = self.evict(&transaction, total_size)?;
+
+        transaction.commit()?;
+        // The size is only changed while the connection is locked.
+        self.total_size.store(total_size, Ordering::Relaxed);
+
+        drop(guard);
+
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_if_cached_conversion_is_returned() {
+        let cache =
+            SqliteConversionCache::new(Connection::open_in_memory().unwrap(), 1024 * 1024).unwrap();
+        let document = ConvertedDocument::new("Hello World".to_string())
+            .with_metadata("encoding", "UTF-8".to_string());
+
+        cache.put("abc", "clear_text@1", &document).unwrap();
+
+        let result = cache.get("abc", "clear_text@1").unwrap().unwrap();
+        assert_eq!(result.contents, "Hello World");
+        assert_eq!(result.metadata, document.metadata);
+        assert!(cache.get("abc", "clear_text@2").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_if_least_recently_used_conversions_are_evicted() {
+        let cache = SqliteConversionCache::new(Connection::open_in_memory().unwrap(), 200).unwrap();
+        // Hashes don't compress well, so every entry takes up more than half of the cache.
+        let contents = (0..4u8)
+            .map(|v| blake3::hash(&[v]).to_hex().to_string())
+            .collect::<String>();
+        let document = ConvertedDocument::new(contents);
+
+        cache.put("first", "clear_text@1", &document).unwrap();
+        cache.put("second", "clear_text@1", &document).unwrap();
+
+        assert!(cache.get("first", "clear_text@1").unwrap().is_none());
+        assert!(cache.get("second", "clear_text@1").unwrap().is_some());
+
+        cache.put("second", "clear_text@1", &document).unwrap();
+        let size_in_db: u64 = cache
+            .conn
+            .lock()
+            .unwrap()
+            .query_row("SELECT SUM(size) FROM conversion_cache", (), |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(cache.total_size.load(Ordering::Relaxed), size_in_db);
+    }
+}
@@ -1,18 +1,24 @@
 use anyhow::{anyhow, Error};
 use log::{debug, error, trace, warn};
-use std::collections::HashMap;
 use std::fs::File;
 
+use crate::conversion::conversion_registry::{ConversionRegistry, RegisteredConversion};
 use crate::conversion::convert_to_clear_text_strategy::MimeType;
 use crate::conversion::determine_file_type::DetermineFileTypeStrategy;
-use crate::conversion::Conversion;
+use crate::conversion::{ConvertedDocument, EmbeddedFile};
+use crate::file_index::conversion_cache_strategy::ConversionCacheStrategy;
 use crate::file_index::file_hash_strategy::FileHashStrategy;
+use crate::file_index::path_scope::create_path_facet;
+use crate::file_index::ranking::{get_path_depth, is_in_demoted_directory};
+use crate::tokenizer::content_language::{detect_language, get_content_languages};
+use dscvr_common::config::IndexerSettings;
 use memmap2::Mmap;
 use std::os::windows::fs::MetadataExt;
 use std::path::PathBuf;
 use std::sync::Arc;
-use tantivy::schema::Schema;
-use tantivy::{doc, Document, IndexWriter, Term};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tantivy::schema::{Field, FieldType, Schema};
+use tantivy::{doc, DateTime, Document, IndexWriter, Term};
 
 use crate::file_index::persist_metadata_strategy::{FileMetadata, PersistMetadataStrategy};
 use crate::file_indexer::ScannedFile;
@@ -29,94 +35,438 @@ pub(crate) struct TantivyIndexStrategy {
     index_writer: IndexWriter,
     persist_metadata_strategy: Arc<dyn PersistMetadataStrategy>,
     determine_file_type_strategy: Arc<dyn DetermineFileTypeStrategy>,
-    conversion_map: HashMap<MimeType, Arc<dyn Conversion>>,
+    conversion_registry: Arc<ConversionRegistry>,
+    conversion_cache_strategy: Arc<dyn ConversionCacheStrategy>,
     file_hash_strategy: Arc<dyn FileHashStrategy>,
+    max_embedded_file_depth: u32,
+    max_embedded_file_count: u64,
+    max_embedded_file_total_size: u64,
+    index_mail_attachments: bool,
+    demoted_directory_words: Vec<String>,
+}
+
+/// What is left of the limits for the files embedded in a single file, including the ones nested in them.
+struct EmbeddedFileBudget {
+    remaining_count: u64,
+    remaining_size: u64,
 }
 
 const MEMORY_LIMIT: u64 = 1000000000; // TODO: Add more sensible memory limit
 
 impl TantivyIndexStrategy {
+    /// The documents are created with the schema of the index the writer belongs to.
     pub(crate) fn new(
-        schema: Schema,
         index_writer: IndexWriter,
         persist_metadata_strategy: Arc<impl PersistMetadataStrategy + 'static>,
         determine_file_type_strategy: Arc<dyn DetermineFileTypeStrategy>,
-        conversion_map: HashMap<MimeType, Arc<dyn Conversion>>,
+        conversion_registry: Arc<ConversionRegistry>,
+        conversion_cache_strategy: Arc<dyn ConversionCacheStrategy>,
         file_hash_strategy: Arc<dyn FileHashStrategy>,
+        settings: &IndexerSettings,
     ) -> Self {
+        let embedded_file_settings = &settings.embedded_files;
+
         TantivyIndexStrategy {
-            schema,
+            schema: index_writer.index().schema(),
             index_writer,
             persist_metadata_strategy,
             determine_file_type_strategy,
-            conversion_map,
+            conversion_registry,
+            conversion_cache_strategy,
             file_hash_strategy,
+            max_embedded_file_depth: embedded_file_settings.max_depth,
+            max_embedded_file_count: embedded_file_settings.max_count,
+            max_embedded_file_total_size: embedded_file_settings.max_total_size,
+            index_mail_attachments: embedded_file_settings.index_mail_attachments,
+            demoted_directory_words: settings
+                .ranking
+                .demoted_directory_words
+                .iter()
+                .map(|v| v.to_lowercase())
+                .collect(),
         }
     }
 
+    /// Returns the document together with the files embedded in the file, which still have to be indexed.
     fn create_doc(
         &self,
-        scanned_file: &ScannedFile,
+        path: &str,
+        mime_type: &MimeType,
         file_contents: Vec<u8>,
         hash: String,
-    ) -> Result<Document, Error> {
+        modified_at: Option<DateTime>,
+    ) -> Result<(Document, Vec<EmbeddedFile>), Error> {
         let contents_field = self.schema.get_field("contents")?;
         let path_field = self.schema.get_field("path")?;
         let hash_field = self.schema.get_field("hash")?;
 
-        let mime_type = match self.determine_file_type_strategy.determine(&scanned_file) {
-            Some(v) => {
-                trace!(
-                    "The file at path {} was found to be of type {:?}.",
-                    scanned_file.path,
-                    v
-                );
-                v
-            }
+        let conversion = match self.conversion_registry.get(mime_type) {
+            Some(v) => v,
             None => {
                 return Err(anyhow!(
-                    "The type of the file at path {} couldn't be determined.",
-                    scanned_file.path
+                    "There was an error while trying to get a conversion strategy for given file with path {:?}. There is no conversion for the type {:?}.",
+                    path,
+                    mime_type
                 ));
             }
         };
 
-        let conversion_strategy = match self.conversion_map.get(&mime_type)
-            .ok_or(anyhow!("There was an error while trying to get the conversion strategy for the given file type"))
-        {
+        let converted_document = match self.convert_with_cache(conversion, file_contents, &hash) {
             Ok(v) => v,
             Err(e) => {
-                return Err(
-                    anyhow!(
-                        "There was an error while trying to get a conversion strategy for given file with path {:?}. {:?}",
-                        scanned_file.path,
-                        e
-                    )
+                return Err(anyhow!(
+                    "There was an error while trying to convert the file at path {:?}. The file was found to of type {:?}. {:?}",
+                    path,
+                    mime_type,
+                    e
+                ));
+            }
+        };
+
+        let mut doc = doc!(
+            contents_field => converted_document.contents.clone(),
+            path_field => path.to_string(),
+            hash_field => hash
+        );
+        // Narrows down the candidates of substring and regex searches.
+        if let Ok(contents_trigrams_field) = self.schema.get_field("contents_trigrams") {
+            doc.add_text(contents_trigrams_field, &converted_document.contents);
+        }
+        // Lets file names be completed as the user types and searched, for embedded files the name inside of their
+        // container.
+        if let Some(file_name) = path.rsplit(['/', '\\']).find(|v| !v.is_empty()) {
+            for field_name in ["file_name", "file_name_terms"] {
+                if let Ok(field) = self.schema.get_field(field_name) {
+                    doc.add_text(field, file_name);
+                }
+            }
+        }
+        // Restricts searches to directories, see `SearchScope`.
+        if let Ok(path_facet_field) = self.schema.get_field("path_facet") {
+            doc.add_facet(path_facet_field, create_path_facet(path));
+        }
+        self.add_ranking_values(&mut doc, path, modified_at);
+
+        for (field_name, value) in converted_document.metadata {
+            match self.schema.get_field(&field_name) {
+                Ok(field) => {
+                    if let Err(e) = self.add_metadata(&mut doc, field, value) {
+                        warn!(
+                            "The metadata {:?} of the file at path {:?} couldn't be indexed: {}",
+                            field_name, path, e
+                        );
+                    }
+                }
+                Err(_) => {
+                    debug!(
+                        "The index has no field {:?} for the metadata of the file at path {:?}.",
+                        field_name, path
+                    );
+                }
+            }
+        }
+
+        if let Ok(language_field) = self.schema.get_field("language") {
+            self.add_language_contents(&mut doc, language_field, &converted_document.contents);
+        }
+
+        return Ok((doc, converted_document.embedded_files));
+    }
+
+    /// Adds the values the ranking reads from fast fields, see [`Ranking`](crate::file_index::ranking::Ranking).
+    fn add_ranking_values(&self, doc: &mut Document, path: &str, modified_at: Option<DateTime>) {
+        if let (Ok(field), Some(modified_at)) = (self.schema.get_field("modified_at"), modified_at)
+        {
+            doc.add_date(field, modified_at);
+        }
+        if let Ok(field) = self.schema.get_field("path_depth") {
+            doc.add_u64(field, get_path_depth(path));
+        }
+        if let Ok(field) = self.schema.get_field("in_demoted_directory") {
+            doc.add_bool(
+                field,
+                is_in_demoted_directory(path, &self.demoted_directory_words),
+            );
+        }
+    }
+
+    /// Detects the language of the contents, unless the conversion found it in the metadata, and adds the contents to
+    /// the fields of their languages, see [`get_content_languages`].
+    fn add_language_contents(&self, doc: &mut Document, language_field: Field, contents: &str) {
+        let language = match doc.get_first(language_field).and_then(|v| v.as_text()) {
+            Some(v) => Some(v.to_string()),
+            None => detect_language(contents).map(|v| {
+                doc.add_text(language_field, v);
+                return v.to_string();
+            }),
+        };
+
+        for content_language in get_content_languages(language.as_deref(), contents) {
+            if let Ok(field) = self.schema.get_field(content_language.field_name()) {
+                doc.add_text(field, contents);
+            }
+        }
+    }
+
+    /// Metadata is converted to the type of its field, e.g. a page count into a number.
+    fn add_metadata(&self, doc: &mut Document, field: Field, value: String) -> Result<(), Error> {
+        match self.schema.get_field_entry(field).field_type() {
+            FieldType::U64(_) => doc.add_u64(field, value.parse()?),
+            FieldType::I64(_) => doc.add_i64(field, value.parse()?),
+            FieldType::F64(_) => doc.add_f64(field, value.parse()?),
+            FieldType::Date(_) => {
+                let date_time = chrono::DateTime::parse_from_rfc3339(&value)?;
+                doc.add_date(
+                    field,
+                    tantivy::DateTime::from_timestamp_secs(date_time.timestamp()),
                 );
             }
+            _ => doc.add_text(field, value),
+        }
+
+        return Ok(());
+    }
+
+    /// Identical files are only converted once, errors of the cache just cause another conversion.
+    fn convert_with_cache(
+        &self,
+        conversion: &RegisteredConversion,
+        file_contents: Vec<u8>,
+        hash: &str,
+    ) -> Result<ConvertedDocument, Error> {
+        let cache_key = match conversion.get_cache_key() {
+            Some(v) => v,
+            None => return conversion.conversion.convert(file_contents),
         };
 
-        return match conversion_strategy.convert(file_contents) {
+        match self.conversion_cache_strategy.get(hash, &cache_key) {
+            Ok(Some(v)) => {
+                trace!(
+                    "Found the conversion {} of the hash {} in the conversion cache.",
+                    cache_key,
+                    hash
+                );
+                return Ok(v);
+            }
+            Ok(None) => {}
+            Err(e) => {
+                warn!(
+                    "There was an error while trying to read the conversion cache: {:?}",
+                    e
+                );
+            }
+        }
+
+        let converted_document = conversion.conversion.convert(file_contents)?;
+
+        // Embedded files are about as large as the file itself, so caching them wouldn't save much.
+        if !converted_document.embedded_files.is_empty() {
+            return Ok(converted_document);
+        }
+
+        if let Err(e) = self
+            .conversion_cache_strategy
+            .put(hash, &cache_key, &converted_document)
+        {
+            warn!(
+                "There was an error while trying to write the conversion cache: {:?}",
+                e
+            );
+        }
+
+        return Ok(converted_document);
+    }
+
+    fn persist_failed_file(&self, path: &str, reason: &str) {
+        if let Err(e) = self
+            .persist_metadata_strategy
+            .persist_failed_file(path, reason)
+        {
+            error!(
+                "There was an error while trying to persist the reason why the file at path {:?} failed: {:?}",
+                path, e
+            );
+        }
+    }
+
+    fn add_to_index(&mut self, doc: Document, metadata: FileMetadata) -> Result<(), Error> {
+        let path_field = self.schema.get_field("path")?;
+        let path = metadata.path.clone();
+
+        match self.index_writer.add_document(doc) {
+            Err(e) => {
+                return Err(anyhow!(
+                    "There was an error while trying to add a document to the index {}",
+                    e
+                ));
+            }
             Ok(v) => {
-                Ok(doc!(
-                    contents_field => v,
-                    path_field => (&scanned_file).path.clone(),
-                    hash_field => hash
-                ))
-            },
+                debug!("Successfully added the document {}", v);
+            }
+        };
+
+        match self.persist_metadata_strategy.persist_metadata(metadata) {
+            Ok(_) => {
+                debug!("Successfully persisted the metadata");
+                return Ok(());
+            }
             Err(e) => {
-                Err(
-                    anyhow!(
-                        "There was an error while trying to convert the file at path {:?}. The file was found to of type {:?}. {:?}",
-                        scanned_file.path,
-                        mime_type,
-                        e
-                    )
-                )
+                let delete_term = Term::from_field_text(path_field, &path);
+                self.index_writer.delete_term(delete_term);
+                return Err(anyhow!(
+                    "There was an error while trying to persist the metadata: {}",
+                    e
+                ));
             }
         };
     }
 
+    /// Indexes the files embedded in the file at the given path, which get a path like `mail.eml!/report.pdf`.
+    /// Failures are only logged, because the file they are embedded in was indexed successfully. Embedded files take
+    /// the modification time of the file they are embedded in.
+    fn index_embedded_files(
+        &mut self,
+        parent_path: &str,
+        parent_mime_type: &MimeType,
+        parent_modified_at: Option<DateTime>,
+        embedded_files: Vec<EmbeddedFile>,
+        depth: u32,
+        budget: &mut EmbeddedFileBudget,
+    ) {
+        if embedded_files.is_empty() {
+            return;
+        }
+
+        if *parent_mime_type == MimeType::MessageRfc822 && !self.index_mail_attachments {
+            trace!(
+                "Skipping the attachments of the mail at path {:?}, because indexing mail attachments is disabled.",
+                parent_path
+            );
+            return;
+        }
+
+        if depth > self.max_embedded_file_depth {
+            debug!(
+                "Skipping the files embedded in the file at path {:?}, because they exceed the maximum depth of {}.",
+                parent_path, self.max_embedded_file_depth
+            );
+            return;
+        }
+
+        // The messages of an mbox are no larger than the mbox itself, so unlike the entries of an archive they can't
+        // blow up. Only what is embedded in them counts against the limits.
+        let is_limited = *parent_mime_type != MimeType::ApplicationMbox;
+
+        let mut embedded_files = embedded_files.into_iter();
+        while let Some(embedded_file) = embedded_files.next() {
+            let path = format!("{}!/{}", parent_path, embedded_file.name);
+
+            let file_size = embedded_file.contents.len() as u64;
+            if is_limited {
+                if budget.remaining_count == 0 || file_size > budget.remaining_size {
+                    warn!(
+                        "Skipping the file at path {:?} and the remaining files embedded in the same file, because they exceed the limits for embedded files.",
+                        path
+                    );
+                    let reason =
+                        "The file exceeds the limits for the files embedded in a single file.";
+                    self.persist_failed_file(&path, reason);
+                    for skipped_file in embedded_files {
+                        self.persist_failed_file(
+                            &format!("{}!/{}", parent_path, skipped_file.name),
+                            reason,
+                        );
+                    }
+                    return;
+                }
+                budget.remaining_count -= 1;
+                budget.remaining_size -= file_size;
+            }
+
+            let mime_type = match self
+                .determine_file_type_strategy
+                .determine_embedded(&embedded_file.name, &embedded_file.contents)
+            {
+                Some(v) => v,
+                None => {
+                    warn!(
+                        "The type of the file at path {} couldn't be determined.",
+                        path
+                    );
+                    continue;
+                }
+            };
+
+            if !self.conversion_registry.is_enabled(&mime_type) {
+                debug!(
+                    "Skipping the file at path {:?}, because indexing files of type {:?} is disabled.",
+                    path, mime_type
+                );
+                continue;
+            }
+
+            if let Some(max_file_size) = self.conversion_registry.get_max_file_size(&mime_type) {
+                if file_size > max_file_size {
+                    debug!(
+                        "Skipping the file at path {:?}, because its size of {} bytes exceeds the limit of {} bytes for files of type {:?}.",
+                        path, file_size, max_file_size, mime_type
+                    );
+                    continue;
+                }
+            }
+
+            let hash = match self
+                .file_hash_strategy
+                .calculate_hash(&embedded_file.contents)
+            {
+                Ok(v) => v,
+                Err(e) => {
+                    warn!(
+                        "Couldn't calculate hash for file at path {:?}: {:?}",
+                        path, e
+                    );
+                    continue;
+                }
+            };
+
+            let metadata = FileMetadata {
+                path: path.clone(),
+                size: file_size,
+                indexed_at: chrono::offset::Local::now(),
+                hash: hash.clone(),
+            };
+
+            let (doc, nested_embedded_files) = match self.create_doc(
+                &path,
+                &mime_type,
+                embedded_file.contents,
+                hash,
+                parent_modified_at,
+            ) {
+                Ok(v) => v,
+                Err(e) => {
+                    warn!("There was an error while trying to build the document from the embedded file: {:?}", e);
+                    self.persist_failed_file(&path, &format!("{:#}", e));
+                    continue;
+                }
+            };
+
+            if let Err(e) = self.add_to_index(doc, metadata) {
+                error!("{:?}", e);
+                continue;
+            }
+
+            self.index_embedded_files(
+                &path,
+                &mime_type,
+                parent_modified_at,
+                nested_embedded_files,
+                depth + 1,
+                budget,
+            );
+        }
+    }
+
     fn create_metadata_from_scanned_file(
         &self,
         scanned_file: &ScannedFile,
@@ -152,8 +502,6 @@ impl TantivyIndexStrategy {
 
 impl IndexFileStrategy for TantivyIndexStrategy {
     fn index_files(&mut self, scanned_files: Vec<ScannedFile>) -> Result<Vec<ScannedFile>, Error> {
-        let path_field = self.schema.get_field("path")?;
-
         let mut failed_files = Vec::new();
         for scanned_file in scanned_files {
             let file = match File::open(&scanned_file.path) {
@@ -165,8 +513,8 @@ impl IndexFileStrategy for TantivyIndexStrategy {
                 }
             };
 
-            let file_size = match file.metadata() {
-                Ok(v) => v.len(),
+            let (file_size, modified_at) = match file.metadata() {
+                Ok(v) => (v.len(), v.modified().ok().and_then(to_date_time)),
                 Err(e) => {
                     warn!("There was an Error while trying to get the size of the file at path {:?}: {:?}", scanned_file.path, e);
                     failed_files.push(scanned_file);
@@ -174,6 +522,43 @@ impl IndexFileStrategy for TantivyIndexStrategy {
                 }
             };
 
+            let mime_type = match self.determine_file_type_strategy.determine(&scanned_file) {
+                Some(v) => {
+                    trace!(
+                        "The file at path {} was found to be of type {:?}.",
+                        scanned_file.path,
+                        v
+                    );
+                    v
+                }
+                None => {
+                    warn!(
+                        "The type of the file at path {} couldn't be determined.",
+                        scanned_file.path
+                    );
+                    failed_files.push(scanned_file);
+                    continue;
+                }
+            };
+
+            if !self.conversion_registry.is_enabled(&mime_type) {
+                debug!(
+                    "Skipping the file at path {:?}, because indexing files of type {:?} is disabled.",
+                    scanned_file.path, mime_type
+                );
+                continue;
+            }
+
+            if let Some(max_file_size) = self.conversion_registry.get_max_file_size(&mime_type) {
+                if file_size > max_file_size {
+                    debug!(
+                        "Skipping the file at path {:?}, because its size of {} bytes exceeds the limit of {} bytes for files of type {:?}.",
+                        scanned_file.path, file_size, max_file_size, mime_type
+                    );
+                    continue;
+                }
+            }
+
             let file_contents = if file_size > MEMORY_LIMIT {
                 match unsafe { Mmap::map(&file) } {
                     Ok(v) => v.to_vec(),
@@ -216,44 +601,40 @@ impl IndexFileStrategy for TantivyIndexStrategy {
                 }
             };
 
-            let doc = match self.create_doc(&scanned_file, file_contents, hash.clone()) {
+            let (doc, embedded_files) = match self.create_doc(
+                &scanned_file.path,
+                &mime_type,
+                file_contents,
+                hash.clone(),
+                modified_at,
+            ) {
                 Ok(v) => v,
                 Err(e) => {
                     warn!("There was an error while trying to build the document from the scanned file: {:?}", e);
+                    self.persist_failed_file(&scanned_file.path, &format!("{:#}", e));
                     failed_files.push(scanned_file);
                     continue;
                 }
             };
 
-            match self.index_writer.add_document(doc) {
-                Err(e) => {
-                    error!(
-                        "There was an error while trying to add a document to the index {}",
-                        e
-                    );
-                    failed_files.push(scanned_file);
-                    continue;
-                }
-                Ok(v) => {
-                    debug!("Successfully added the document {}", v);
-                    v
-                }
-            };
+            if let Err(e) = self.add_to_index(doc, metadata) {
+                error!("{:?}", e);
+                failed_files.push(scanned_file);
+                continue;
+            }
 
-            match self.persist_metadata_strategy.persist_metadata(metadata) {
-                Ok(_) => {
-                    debug!("Successfully persisted the metadata")
-                }
-                Err(e) => {
-                    error!(
-                        "There was an error while trying to persist the metadata: {}",
-                        e
-                    );
-                    let delete_term = Term::from_field_text(path_field, &scanned_file.path.clone());
-                    self.index_writer.delete_term(delete_term);
-                    failed_files.push(scanned_file);
-                }
+            let mut budget = EmbeddedFileBudget {
+                remaining_count: self.max_embedded_file_count,
+                remaining_size: self.max_embedded_file_total_size,
             };
+            self.index_embedded_files(
+                &scanned_file.path,
+                &mime_type,
+                modified_at,
+                embedded_files,
+                1,
+                &mut budget,
+            );
         }
 
         self.index_writer.commit()?;
@@ -261,3 +642,8 @@ impl IndexFileStrategy for TantivyIndexStrategy {
         return Ok(failed_files); // TODO: Do something with the failed files.
     }
 }
+
+fn to_date_time(system_time: SystemTime) -> Option<DateTime> {
+    let duration = system_time.duration_since(UNIX_EPOCH).ok()?;
+    return Some(DateTime::from_timestamp_secs(duration.as_secs() as i64));
+}
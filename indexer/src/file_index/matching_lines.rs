@@ -0,0 +1,149 @@
+use crate::file_indexer::{ColumnRange, MatchingLine};
+use itertools::Itertools;
+use regex::Regex;
+use std::collections::HashSet;
+use tantivy::tokenizer::TextAnalyzer;
+
+/// Long lines, e.g. of minified files, are cut off after this many characters.
+const MAX_LINE_LENGTH: usize = 500;
+
+/// Finds the lines that contain terms of the query together with the columns of these terms, like grep does. The text
+/// is tokenized with the analyzers of the fields that were searched, each paired with the terms of its field, so the
+/// same tokens match as in the index.
+pub(crate) fn find_matching_lines(
+    text: &str,
+    text_analyzers: &mut [(TextAnalyzer, &HashSet<String>)],
+    max_matching_lines: usize,
+) -> Vec<MatchingLine> {
+    if max_matching_lines == 0 {
+        return Vec::new();
+    }
+
+    let matches = find_token_matches(text, text_analyzers);
+    return collect_matching_lines(text, matches, max_matching_lines);
+}
+
+/// Returns the byte ranges of the tokens that are terms of the query, in order. Tokens of different analyzers can
+/// overlap, e.g. a word and its stem.
+pub(crate) fn find_token_matches<'a>(
+    text: &'a str,
+    text_analyzers: &'a mut [(TextAnalyzer, &HashSet<String>)],
+) -> impl Iterator<Item = (usize, usize)> + 'a {
+    return text_analyzers
+        .iter_mut()
+        .filter(|(_, query_terms)| !query_terms.is_empty())
+        .map(|(text_analyzer, query_terms)| {
+            let query_terms = &**query_terms;
+            let mut token_stream = text_analyzer.token_stream(text);
+            return std::iter::from_fn(move || {
+                while token_stream.advance() {
+                    let token = token_stream.token();
+                    if query_terms.contains(&token.text) {
+                        return Some((token.offset_from, token.offset_to));
+                    }
+                }
+
+                return None;
+            });
+        })
+        .kmerge();
+}
+
+/// Finds the lines that contain matches of the pattern of a substring or regex search.
+pub(crate) fn find_pattern_matching_lines(
+    text: &str,
+    pattern: &Regex,
+    max_matching_lines: usize,
+) -> Vec<MatchingLine> {
+    if max_matching_lines == 0 {
+        return Vec::new();
+    }
+
+    let matches = pattern.find_iter(text).map(|v| (v.start(), v.end()));
+    return collect_matching_lines(text, matches, max_matching_lines);
+}
+
+/// Groups the byte ranges of the matches, which have to be in order, by line. Matches that span multiple lines are
+/// cut off at the end of their first line.
+fn collect_matching_lines(
+    text: &str,
+    matches: impl Iterator<Item = (usize, usize)>,
+    max_matching_lines: usize,
+) -> Vec<MatchingLine> {
+    let mut matching_lines: Vec<MatchingLine> = Vec::new();
+    let line_starts = std::iter::once(0)
+        .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+        .collect::<Vec<_>>();
+
+    for (offset_from, offset_to) in matches {
+        let line_index = line_starts.partition_point(|&v| v <= offset_from) - 1;
+        let line_start = line_starts[line_index];
+        let line_end = line_starts
+            .get(line_index + 1)
+            .map_or(text.len(), |v| v - 1);
+        let line = text[line_start..line_end].trim_end_matches('\r');
+
+        let line_number = line_index as u64 + 1;
+        if matching_lines.last().map(|v| v.line_number) != Some(line_number) {
+            if matching_lines.len() >= max_matching_lines {
+                break;
+            }
+
+            matching_lines.push(MatchingLine {
+                line_number,
+                text: line.chars().take(MAX_LINE_LENGTH).collect(),
+                ranges: Vec::new(),
+            });
+        }
+
+        let offset_to = offset_to.min(line_start + line.len()).max(offset_from);
+        let start = text[line_start..offset_from].chars().count() + 1;
+        let end = start + text[offset_from..offset_to].chars().count();
+        if start > MAX_LINE_LENGTH {
+            continue;
+        }
+
+        let ranges = &mut matching_lines.last_mut().unwrap().ranges;
+        match ranges.last_mut() {
+            // Tokens can overlap, e.g. an identifier and its subwords.
+            Some(previous) if start < previous.end as usize => {
+                previous.end = previous.end.max(end as u32);
+            }
+            _ => ranges.push(ColumnRange {
+                start: start as u32,
+                end: end as u32,
+            }),
+        }
+    }
+
+    return matching_lines;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tantivy::tokenizer::{LowerCaser, SimpleTokenizer};
+
+    #[test]
+    fn test_if_matching_lines_are_found_with_columns() {
+        let text_analyzer = TextAnalyzer::builder(SimpleTokenizer::default())
+            .filter(LowerCaser)
+            .build();
+        let query_terms = HashSet::from(["query".to_string()]);
+        let text = "fn main() {\r\n    let äquery = Query::parse(query);\n}\nquery\n";
+
+        let result = find_matching_lines(text, &mut [(text_analyzer, &query_terms)], 1);
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].line_number, 2);
+        assert_eq!(result[0].text, "    let äquery = Query::parse(query);");
+        assert_eq!(
+            result[0]
+                .ranges
+                .iter()
+                .map(|v| (v.start, v.end))
+                .collect::<Vec<_>>(),
+            vec![(18, 23), (31, 36)]
+        );
+    }
+}
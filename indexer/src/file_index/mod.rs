@@ -1,38 +1,63 @@
+use crate::file_index::autocomplete_strategy::{AutocompleteStrategy, DEFAULT_COMPLETION_LIMIT};
 use crate::file_index::find_duplicated_files_strategy::FindDuplicatedFilesStrategy;
 use crate::file_index::index_file_strategy::IndexFileStrategy;
+use crate::file_index::query_history_strategy::QueryHistoryStrategy;
 use crate::file_index::search_file_strategy::SearchFileStrategy;
+use crate::file_index::suggest_query_strategy::SuggestQueryStrategy;
 use crate::file_indexer::file_indexer_server::FileIndexer;
 use crate::file_indexer::{
     FindDuplicatedFilesQuery, FindDuplicatedFilesResponse, IndexFileQuery,
-    SearchFileByContentsQuery, SearchFileResponse,
+    SearchFileByContentsQuery, SearchFileResponse, SearchMode, SuggestQuery, SuggestResponse,
 };
 use crate::proto_utils::Empty;
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 use std::sync::{Arc, Mutex};
 use tonic::{Request, Response, Status};
 
+pub(crate) mod autocomplete_strategy;
+pub(crate) mod conversion_cache_strategy;
 pub(crate) mod file_hash_strategy;
 pub(crate) mod find_duplicated_files_strategy;
 pub(crate) mod index_file_strategy;
+pub(crate) mod matching_lines;
+pub(crate) mod path_scope;
+pub(crate) mod pattern_search;
 pub(crate) mod persist_metadata_strategy;
+pub(crate) mod query_history_strategy;
+pub(crate) mod ranking;
+pub(crate) mod schema_version;
 pub(crate) mod search_file_strategy;
+pub(crate) mod sorting;
+pub(crate) mod suggest_query_strategy;
+
+/// Searches with fewer hits than this get suggestions for corrected queries.
+const MAX_HITS_FOR_SUGGESTIONS: usize = 3;
 
 pub(crate) struct FileIndexService {
     index_file_strategy: Arc<Mutex<dyn IndexFileStrategy>>,
     search_file_strategy: Arc<dyn SearchFileStrategy>,
+    suggest_query_strategy: Arc<dyn SuggestQueryStrategy>,
     find_duplicated_files_strategy: Arc<dyn FindDuplicatedFilesStrategy>,
+    query_history_strategy: Arc<dyn QueryHistoryStrategy>,
+    autocomplete_strategy: Arc<dyn AutocompleteStrategy>,
 }
 
 impl FileIndexService {
     pub(crate) fn new(
         index_file_strategy: Arc<Mutex<impl IndexFileStrategy + 'static>>,
         search_file_strategy: Arc<impl SearchFileStrategy + 'static>,
+        suggest_query_strategy: Arc<impl SuggestQueryStrategy + 'static>,
         find_duplicated_files_strategy: Arc<impl FindDuplicatedFilesStrategy + 'static>,
+        query_history_strategy: Arc<impl QueryHistoryStrategy + 'static>,
+        autocomplete_strategy: Arc<impl AutocompleteStrategy + 'static>,
     ) -> Self {
         Self {
             index_file_strategy,
             search_file_strategy,
+            suggest_query_strategy,
             find_duplicated_files_strategy,
+            query_history_strategy,
+            autocomplete_strategy,
         }
     }
 }
@@ -93,11 +118,34 @@ impl FileIndexer for FileIndexService {
         info!("starting search for file");
 
         let search_file_by_contents_query = request.into_inner();
-        let string_query = search_file_by_contents_query.query.to_string();
 
-        let result = self.search_file_strategy.search_file(&string_query);
+        let mut response = self
+            .search_file_strategy
+            .search_file(&search_file_by_contents_query);
+
+        // Substrings and patterns contain no words that could be corrected.
+        if search_file_by_contents_query.mode() == SearchMode::Terms
+            && response.hits.len() < MAX_HITS_FOR_SUGGESTIONS
+        {
+            response.suggestions = self
+                .suggest_query_strategy
+                .suggest_queries(&search_file_by_contents_query.query);
+        }
+
+        // Only queries that found something are worth completing later.
+        if !response.hits.is_empty() {
+            if let Err(e) = self
+                .query_history_strategy
+                .record_query(&search_file_by_contents_query.query)
+            {
+                warn!(
+                    "There was an error while trying to record the query for autocompletion: {:?}",
+                    e
+                );
+            }
+        }
 
-        return Ok(Response::new(SearchFileResponse { path: result }));
+        return Ok(Response::new(response));
     }
 
     async fn find_duplicated_files(
@@ -115,4 +163,20 @@ impl FileIndexer for FileIndexService {
             files: duplicated_files,
         }))
     }
+
+    async fn suggest(
+        &self,
+        request: Request<SuggestQuery>,
+    ) -> Result<Response<SuggestResponse>, Status> {
+        let suggest_query = request.into_inner();
+        let limit = suggest_query
+            .limit
+            .map_or(DEFAULT_COMPLETION_LIMIT, |v| v as usize);
+
+        let suggestions = self
+            .autocomplete_strategy
+            .complete(&suggest_query.prefix, limit);
+
+        return Ok(Response::new(SuggestResponse { suggestions }));
+    }
 }
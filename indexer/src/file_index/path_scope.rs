@@ -0,0 +1,122 @@
+use crate::file_indexer::SearchScope;
+use tantivy::query::{BooleanQuery, Occur, Query, TermQuery};
+use tantivy::schema::{Facet, Field, IndexRecordOption};
+use tantivy::Term;
+
+/// Splits the path into its directories and file name. The containers of embedded files count as directories, e.g.
+/// `backup.zip!` of `docs/backup.zip!/report.pdf`.
+pub(crate) fn split_path(path: &str) -> Vec<&str> {
+    return path.split(['/', '\\']).filter(|v| !v.is_empty()).collect();
+}
+
+/// The path as a facet, which tantivy indexes together with every parent, so a single term finds every file below a
+/// directory.
+pub(crate) fn create_path_facet(path: &str) -> Facet {
+    return Facet::from_path(split_path(path));
+}
+
+/// Creates the clauses that restrict a search to the scope, to be added to the clauses of the query. A search with
+/// only excluded directories needs at least one other clause, because excluding alone matches nothing.
+pub(crate) fn create_scope_clauses(
+    path_facet_field: Field,
+    scope: &SearchScope,
+) -> Vec<(Occur, Box<dyn Query>)> {
+    let create_query = |path: &str| -> Box<dyn Query> {
+        return Box::new(TermQuery::new(
+            Term::from_facet(path_facet_field, &create_path_facet(path)),
+            IndexRecordOption::Basic,
+        ));
+    };
+
+    let mut clauses: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+
+    // The root includes every file, so it doesn't restrict anything.
+    let includes_root = scope.include.iter().any(|v| split_path(v).is_empty());
+    if !scope.include.is_empty() && !includes_root {
+        let include_query = BooleanQuery::new(
+            scope
+                .include
+                .iter()
+                .map(|v| (Occur::Should, create_query(v)))
+                .collect(),
+        );
+        clauses.push((Occur::Must, Box::new(include_query)));
+    }
+
+    clauses.extend(
+        scope
+            .exclude
+            .iter()
+            .filter(|v| !split_path(v).is_empty())
+            .map(|v| (Occur::MustNot, create_query(v))),
+    );
+
+    return clauses;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tantivy::collector::DocSetCollector;
+    use tantivy::query::AllQuery;
+    use tantivy::schema::{FacetOptions, Schema, STORED};
+    use tantivy::{doc, Index};
+
+    #[test]
+    fn test_if_search_is_restricted_to_scope() {
+        let mut schema_builder = Schema::builder();
+        let path_field = schema_builder.add_text_field("path", STORED);
+        let path_facet_field =
+            schema_builder.add_facet_field("path_facet", FacetOptions::default());
+        let index = Index::create_in_ram(schema_builder.build());
+
+        let mut index_writer = index.writer(15_000_000).unwrap();
+        for path in [
+            "C:\\Users\\jane\\project\\src\\main.rs",
+            "C:\\Users\\jane\\project\\target\\main.rs",
+            "C:\\Users\\jane\\project-old\\main.rs",
+            "C:\\Users\\jane\\notes.txt",
+        ] {
+            index_writer
+                .add_document(doc!(path_field => path, path_facet_field => create_path_facet(path)))
+                .unwrap();
+        }
+        index_writer.commit().unwrap();
+
+        let searcher = index.reader().unwrap().searcher();
+        let search = |include: Vec<&str>, exclude: Vec<&str>| {
+            let scope = SearchScope {
+                include: include.into_iter().map(|v| v.to_string()).collect(),
+                exclude: exclude.into_iter().map(|v| v.to_string()).collect(),
+            };
+            let mut clauses: Vec<(Occur, Box<dyn Query>)> = vec![(Occur::Must, Box::new(AllQuery))];
+            clauses.extend(create_scope_clauses(path_facet_field, &scope));
+
+            let mut paths = searcher
+                .search(&BooleanQuery::new(clauses), &DocSetCollector)
+                .unwrap()
+                .into_iter()
+                .map(|v| {
+                    let doc = searcher.doc(v).unwrap();
+                    return doc
+                        .get_first(path_field)
+                        .unwrap()
+                        .as_text()
+                        .unwrap()
+                        .to_string();
+                })
+                .collect::<Vec<_>>();
+            paths.sort();
+            return paths;
+        };
+
+        assert_eq!(
+            search(
+                vec!["C:\\Users\\jane\\project\\"],
+                vec!["C:/Users/jane/project/target"]
+            ),
+            vec!["C:\\Users\\jane\\project\\src\\main.rs"]
+        );
+        assert_eq!(search(vec!["/"], vec!["C:\\Users\\jane\\project"]).len(), 2);
+    }
+}
@@ -0,0 +1,110 @@
+use crate::file_indexer::SearchMode;
+use anyhow::Error;
+use regex::{Regex, RegexBuilder};
+use regex_syntax::hir::{Hir, HirKind};
+use std::collections::BTreeSet;
+use tantivy::query::{AllQuery, BooleanQuery, Occur, Query, TermQuery};
+use tantivy::schema::{Field, IndexRecordOption};
+use tantivy::Term;
+
+/// Limits the memory of a compiled regular expression, e.g. for `\w{1000}`.
+const MAX_REGEX_SIZE: usize = 10 * 1024 * 1024;
+
+/// Creates the regular expression that verifies the candidates of a substring or regex search. Substrings ignore
+/// case like the search for terms does.
+pub(crate) fn create_pattern(query: &str, mode: SearchMode) -> Result<Regex, Error> {
+    let pattern = match mode {
+        SearchMode::Substring => format!("(?i){}", regex::escape(query)),
+        _ => query.to_string(),
+    };
+
+    return Ok(RegexBuilder::new(&pattern)
+        .size_limit(MAX_REGEX_SIZE)
+        .build()?);
+}
+
+/// Creates a query for the documents that contain every trigram of the literals that a match requires, e.g. `exc`,
+/// `xce`, ..., `on(` for `Exception\(\w+\)`. Patterns without such literals, e.g. `a.c` or `(?i)foo`, match every
+/// document, which all have to be verified then.
+pub(crate) fn create_trigram_query(field: Field, query: &str, mode: SearchMode) -> Box<dyn Query> {
+    let literals = match mode {
+        SearchMode::Substring => vec![query.to_string()],
+        _ => match regex_syntax::parse(query) {
+            Ok(v) => find_required_literals(&v),
+            Err(_) => Vec::new(),
+        },
+    };
+
+    let trigrams = literals
+        .iter()
+        .flat_map(|v| create_trigrams(v))
+        .collect::<BTreeSet<_>>();
+    if trigrams.is_empty() {
+        return Box::new(AllQuery);
+    }
+
+    return Box::new(BooleanQuery::new(
+        trigrams
+            .into_iter()
+            .map(|v| {
+                let query: Box<dyn Query> = Box::new(TermQuery::new(
+                    Term::from_field_text(field, &v),
+                    IndexRecordOption::Basic,
+                ));
+                return (Occur::Must, query);
+            })
+            .collect(),
+    ));
+}
+
+/// Literals inside of alternations or optional repetitions aren't required, so they are left out.
+fn find_required_literals(hir: &Hir) -> Vec<String> {
+    return match hir.kind() {
+        HirKind::Literal(v) => vec![String::from_utf8_lossy(&v.0).to_string()],
+        HirKind::Capture(v) => find_required_literals(&v.sub),
+        HirKind::Repetition(v) if v.min > 0 => find_required_literals(&v.sub),
+        HirKind::Concat(v) => {
+            let mut literals = Vec::new();
+            let mut literal = String::new();
+            for sub in v {
+                match sub.kind() {
+                    HirKind::Literal(v) => literal.push_str(&String::from_utf8_lossy(&v.0)),
+                    _ => {
+                        literals.push(std::mem::take(&mut literal));
+                        literals.extend(find_required_literals(sub));
+                    }
+                }
+            }
+            literals.push(literal);
+
+            literals.into_iter().filter(|v| !v.is_empty()).collect()
+        }
+        _ => Vec::new(),
+    };
+}
+
+/// Creates the same trigrams as the tokenizer of the field `contents_trigrams`.
+fn create_trigrams(literal: &str) -> Vec<String> {
+    return literal
+        .chars()
+        .collect::<Vec<_>>()
+        .windows(3)
+        .map(|v| v.iter().collect::<String>().to_lowercase())
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_if_required_literals_are_found() {
+        let find = |pattern: &str| find_required_literals(&regex_syntax::parse(pattern).unwrap());
+
+        assert_eq!(find("Exception\\("), vec!["Exception("]);
+        assert_eq!(find("fn (parse|read)_query\\w+"), vec!["fn ", "_query"]);
+        assert_eq!(find("(?:todo)+: .*fixme"), vec!["todo", ": ", "fixme"]);
+        assert_eq!(find("a.c|abc"), Vec::<String>::new());
+        assert_eq!(create_trigrams("ÄBCd"), vec!["äbc", "bcd"]);
+    }
+}
@@ -15,6 +15,12 @@ pub(crate) struct FileMetadata {
 
 pub(crate) trait PersistMetadataStrategy: Send + Sync {
     fn persist_metadata(&self, metadata: FileMetadata) -> Result<(), anyhow::Error>;
+
+    /// Remembers why a file couldn't be indexed, until it is indexed successfully.
+    fn persist_failed_file(&self, path: &str, reason: &str) -> Result<(), anyhow::Error>;
+
+    /// Forgets every indexed and failed file, so all of them are indexed again, e.g. after the index was rebuilt.
+    fn forget_indexed_files(&self) -> Result<(), anyhow::Error>;
 }
 
 pub(crate) struct SqlitePersistenceStrategy {
@@ -52,6 +58,11 @@ impl SqlitePersistenceStrategy {
             ()
         )?;
 
+        guard.execute(
+            "CREATE TABLE IF NOT EXISTS failed_files (path VARCHAR(256) PRIMARY KEY, reason TEXT NOT NULL, failed_at DATETIME NOT NULL)",
+            ()
+        )?;
+
         drop(guard);
 
         return Ok(());
@@ -80,6 +91,51 @@ impl PersistMetadataStrategy for SqlitePersistenceStrategy {
             ),
         )?;
 
+        guard.execute(
+            "DELETE FROM failed_files WHERE path = ?;",
+            (&metadata.path,),
+        )?;
+
+        drop(guard);
+
+        return Ok(());
+    }
+
+    fn persist_failed_file(&self, path: &str, reason: &str) -> Result<(), anyhow::Error> {
+        let guard = match self.conn.lock() {
+            Ok(v) => v,
+            Err(e) => {
+                error!("There was an error while trying to acquire the lock for the connection when trying to insert a failed file: {:?}", e);
+                return Err(anyhow!("Poison Error"));
+            }
+        };
+
+        guard.execute(
+            "\
+                INSERT OR REPLACE INTO failed_files (path, reason, failed_at) VALUES (?, ?, ?);
+            ",
+            (path, reason, &chrono::offset::Local::now()),
+        )?;
+
+        drop(guard);
+
+        return Ok(());
+    }
+
+    fn forget_indexed_files(&self) -> Result<(), anyhow::Error> {
+        let mut guard = match self.conn.lock() {
+            Ok(v) => v,
+            Err(e) => {
+                error!("There was an error while trying to acquire the lock for the connection when trying to forget the indexed files: {:?}", e);
+                return Err(anyhow!("Poison Error"));
+            }
+        };
+
+        let transaction = guard.transaction()?;
+        transaction.execute("DELETE FROM indexed_files;", ())?;
+        transaction.execute("DELETE FROM failed_files;", ())?;
+        transaction.commit()?;
+
         drop(guard);
 
         return Ok(());
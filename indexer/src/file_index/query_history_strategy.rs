@@ -0,0 +1,137 @@
+use anyhow::anyhow;
+use dscvr_common::config::AppSettings;
+use log::error;
+use rusqlite::Connection;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+pub(crate) trait QueryHistoryStrategy: Send + Sync {
+    fn record_query(&self, query: &str) -> Result<(), anyhow::Error>;
+
+    /// Returns the past queries that start with the prefix together with how often they were searched, most
+    /// frequent first.
+    fn find_queries_by_prefix(
+        &self,
+        prefix: &str,
+        limit: usize,
+    ) -> Result<Vec<(String, u64)>, anyhow::Error>;
+}
+
+/// Remembers the queries in the database of the indexed files, so they can be completed later.
+pub(crate) struct SqliteQueryHistory {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteQueryHistory {
+    pub(crate) fn build_with_settings(settings: &AppSettings) -> Result<Self, anyhow::Error> {
+        let path_to_db = Path::new(&settings.common.base_dir).join(&settings.indexer.db_file_name);
+
+        let connection = Connection::open(path_to_db)?;
+
+        return Self::new(connection);
+    }
+
+    pub(crate) fn new(connection: Connection) -> Result<Self, anyhow::Error> {
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS query_history (query TEXT PRIMARY KEY, count INT(64) NOT NULL, last_used_at INT(64) NOT NULL)",
+            (),
+        )?;
+
+        return Ok(SqliteQueryHistory {
+            conn: Arc::new(Mutex::new(connection)),
+        });
+    }
+}
+
+/// Queries that only differ in case or whitespace are the same query.
+fn normalize_query(query: &str) -> String {
+    return query
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+}
+
+impl QueryHistoryStrategy for SqliteQueryHistory {
+    fn record_query(&self, query: &str) -> Result<(), anyhow::Error> {
+        let query = normalize_query(query);
+        if query.is_empty() {
+            return Ok(());
+        }
+
+        let guard = match self.conn.lock() {
+            Ok(v) => v,
+            Err(e) => {
+                error!("There was an error while trying to acquire the lock for the connection when trying to record a query: {:?}", e);
+                return Err(anyhow!("Poison Error"));
+            }
+        };
+
+        guard.execute(
+            "\
+                INSERT INTO query_history (query, count, last_used_at) VALUES (?, 1, ?)
+                ON CONFLICT (query) DO UPDATE SET count = count + 1, last_used_at = excluded.last_used_at;
+            ",
+            (&query, chrono::offset::Local::now().timestamp()),
+        )?;
+
+        drop(guard);
+
+        return Ok(());
+    }
+
+    fn find_queries_by_prefix(
+        &self,
+        prefix: &str,
+        limit: usize,
+    ) -> Result<Vec<(String, u64)>, anyhow::Error> {
+        let prefix = prefix.trim_start().to_lowercase();
+
+        let guard = match self.conn.lock() {
+            Ok(v) => v,
+            Err(e) => {
+                error!("There was an error while trying to acquire the lock for the connection when trying to find past queries: {:?}", e);
+                return Err(anyhow!("Poison Error"));
+            }
+        };
+
+        // A range instead of `LIKE` uses the primary key, the largest character ends the range of the prefix.
+        let mut statement = guard.prepare_cached(
+            "SELECT query, count FROM query_history WHERE query >= ? AND query < ? ORDER BY count DESC, last_used_at DESC LIMIT ?",
+        )?;
+        let queries = statement
+            .query_map(
+                (&prefix, format!("{}{}", prefix, char::MAX), limit as i64),
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        return Ok(queries);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_if_most_frequent_queries_are_found_by_prefix() {
+        let query_history = SqliteQueryHistory::new(Connection::open_in_memory().unwrap()).unwrap();
+        query_history.record_query("Quarterly  Report").unwrap();
+        query_history.record_query("quarterly report").unwrap();
+        query_history.record_query("quarterly numbers").unwrap();
+        query_history.record_query("invoice").unwrap();
+
+        assert_eq!(
+            query_history.find_queries_by_prefix("Quar", 10).unwrap(),
+            vec![
+                ("quarterly report".to_string(), 2),
+                ("quarterly numbers".to_string(), 1),
+            ]
+        );
+        assert_eq!(
+            query_history.find_queries_by_prefix("", 1).unwrap(),
+            vec![("quarterly report".to_string(), 2)]
+        );
+    }
+}
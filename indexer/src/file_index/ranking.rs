@@ -0,0 +1,141 @@
+use crate::file_index::path_scope::split_path;
+use dscvr_common::config::RankingSettings;
+use tantivy::{DateTime, DocId, Score, SegmentReader};
+
+const SECONDS_PER_DAY: f32 = 24.0 * 60.0 * 60.0;
+
+/// Ranks files on top of the relevance of their contents, by how recently they were modified, how deeply they are
+/// nested and whether they are in a directory that looks like an archive or a backup. These are read from fast fields,
+/// so ranking doesn't need the stored documents.
+#[derive(Clone)]
+pub(crate) struct Ranking {
+    recency_weight: f32,
+    recency_half_life_in_days: f32,
+    path_depth_weight: f32,
+    demoted_directory_factor: f32,
+}
+
+impl Ranking {
+    pub(crate) fn new(ranking_settings: &RankingSettings) -> Self {
+        return Self {
+            recency_weight: ranking_settings.recency_weight,
+            recency_half_life_in_days: ranking_settings.recency_half_life_in_days,
+            path_depth_weight: ranking_settings.path_depth_weight,
+            demoted_directory_factor: ranking_settings.demoted_directory_factor,
+        };
+    }
+
+    /// Creates the function that tweaks the scores of the documents of a segment, for `TopDocs::tweak_score`. Files
+    /// without a value, e.g. of an older index, aren't boosted or demoted by it.
+    pub(crate) fn create_segment_tweaker(
+        self: &Self,
+        segment_reader: &SegmentReader,
+        now: DateTime,
+    ) -> impl FnMut(DocId, Score) -> Score {
+        let fast_fields = segment_reader.fast_fields();
+        let modified_at = fast_fields
+            .column_opt::<DateTime>("modified_at")
+            .ok()
+            .flatten();
+        let path_depth = fast_fields.column_opt::<u64>("path_depth").ok().flatten();
+        let in_demoted_directory = fast_fields
+            .column_opt::<bool>("in_demoted_directory")
+            .ok()
+            .flatten();
+        let ranking = self.clone();
+
+        return move |doc, score| {
+            let age_in_days = modified_at.as_ref().and_then(|v| v.first(doc)).map(|v| {
+                return (now.into_timestamp_secs() - v.into_timestamp_secs()).max(0) as f32
+                    / SECONDS_PER_DAY;
+            });
+            let path_depth = path_depth.as_ref().and_then(|v| v.first(doc));
+            let in_demoted_directory = in_demoted_directory
+                .as_ref()
+                .and_then(|v| v.first(doc))
+                .unwrap_or(false);
+
+            return score * ranking.get_factor(age_in_days, path_depth, in_demoted_directory);
+        };
+    }
+
+    fn get_factor(
+        self: &Self,
+        age_in_days: Option<f32>,
+        path_depth: Option<u64>,
+        in_demoted_directory: bool,
+    ) -> f32 {
+        let mut factor = 1.0;
+
+        if let Some(age_in_days) = age_in_days {
+            let half_lives = age_in_days / self.recency_half_life_in_days.max(f32::EPSILON);
+            factor *= 1.0 + self.recency_weight * 0.5_f32.powf(half_lives);
+        }
+        if let Some(path_depth) = path_depth {
+            factor /= 1.0 + self.path_depth_weight * path_depth as f32;
+        }
+        if in_demoted_directory {
+            factor *= self.demoted_directory_factor;
+        }
+
+        return factor;
+    }
+}
+
+/// The amount of directories in the path.
+pub(crate) fn get_path_depth(path: &str) -> u64 {
+    return split_path(path).len().saturating_sub(1) as u64;
+}
+
+/// Whether a directory of the path contains one of the words, e.g. `Old Projects` or `project_backup` for `old` and
+/// `backup`. The words have to be lowercase.
+pub(crate) fn is_in_demoted_directory(path: &str, demoted_directory_words: &[String]) -> bool {
+    let components = split_path(path);
+    let directories = &components[..components.len().saturating_sub(1)];
+
+    return directories.iter().any(|directory| {
+        return directory
+            .split(|c: char| !c.is_alphanumeric())
+            .any(|word| demoted_directory_words.contains(&word.to_lowercase()));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_if_recent_shallow_files_rank_higher() {
+        let ranking = Ranking::new(&RankingSettings::default());
+        let demoted_directory_words = RankingSettings::default().demoted_directory_words;
+
+        assert!(
+            ranking.get_factor(Some(1.0), Some(2), false)
+                > ranking.get_factor(Some(365.0), Some(2), false)
+        );
+        assert!(
+            ranking.get_factor(Some(1.0), Some(2), false)
+                > ranking.get_factor(Some(1.0), Some(8), false)
+        );
+        assert!(
+            ranking.get_factor(Some(1.0), Some(2), false)
+                > ranking.get_factor(Some(1.0), Some(2), true)
+        );
+        assert_eq!(ranking.get_factor(None, None, false), 1.0);
+
+        assert_eq!(get_path_depth("C:\\Users\\jane\\report.pdf"), 3);
+        assert_eq!(get_path_depth("/home/jane/mail.eml!/report.pdf"), 3);
+        assert!(is_in_demoted_directory(
+            "/home/jane/Backup 2019/report.pdf",
+            &demoted_directory_words
+        ));
+        assert!(is_in_demoted_directory(
+            "/home/jane/old_projects/report.pdf",
+            &demoted_directory_words
+        ));
+        assert!(!is_in_demoted_directory(
+            "/home/jane/folder/old.pdf",
+            &demoted_directory_words
+        ));
+    }
+}
@@ -0,0 +1,68 @@
+use std::fs;
+use std::path::Path;
+use tantivy::directory::MmapDirectory;
+use tantivy::Index;
+
+/// The version of the schema of the index. It has to be increased whenever a field is added, removed or its options
+/// change, because tantivy can't open an index with another schema.
+pub(crate) const SCHEMA_VERSION: u64 = 16;
+
+const SCHEMA_VERSION_FILE_NAME: &str = "schema_version";
+
+/// Whether the directory contains an index that was created with another version of the schema, including indices that
+/// were created before the version was stored. An empty directory isn't outdated.
+pub(crate) fn is_index_outdated(index_path: &Path) -> Result<bool, anyhow::Error> {
+    if !Index::exists(&MmapDirectory::open(index_path)?)? {
+        return Ok(false);
+    }
+
+    let schema_version = fs::read_to_string(index_path.join(SCHEMA_VERSION_FILE_NAME))
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok());
+
+    return Ok(schema_version != Some(SCHEMA_VERSION));
+}
+
+/// Removes the index from the directory, so a new one can be created in its place.
+pub(crate) fn remove_index(index_path: &Path) -> Result<(), anyhow::Error> {
+    fs::remove_dir_all(index_path)?;
+    fs::create_dir(index_path)?;
+
+    return Ok(());
+}
+
+/// Stores the version of the schema next to the index.
+pub(crate) fn write_schema_version(index_path: &Path) -> Result<(), anyhow::Error> {
+    fs::write(
+        index_path.join(SCHEMA_VERSION_FILE_NAME),
+        SCHEMA_VERSION.to_string(),
+    )?;
+
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tantivy::schema::{Schema, TEXT};
+
+    #[test]
+    fn test_if_index_of_another_schema_version_is_outdated() {
+        let index_dir = tempfile::tempdir().unwrap();
+        assert!(!is_index_outdated(index_dir.path()).unwrap());
+
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("contents", TEXT);
+        Index::create_in_dir(index_dir.path(), schema_builder.build()).unwrap();
+        assert!(is_index_outdated(index_dir.path()).unwrap());
+
+        write_schema_version(index_dir.path()).unwrap();
+        assert!(!is_index_outdated(index_dir.path()).unwrap());
+
+        fs::write(index_dir.path().join(SCHEMA_VERSION_FILE_NAME), "0").unwrap();
+        assert!(is_index_outdated(index_dir.path()).unwrap());
+
+        remove_index(index_dir.path()).unwrap();
+        assert!(!is_index_outdated(index_dir.path()).unwrap());
+    }
+}
@@ -1,33 +1,482 @@
-use log::error;
+use crate::conversion::clear_text_conversion::decode_text;
+use crate::file_index::matching_lines::{
+    find_matching_lines, find_pattern_matching_lines, find_token_matches,
+};
+use crate::file_index::path_scope::create_scope_clauses;
+use crate::file_index::pattern_search::{create_pattern, create_trigram_query};
+use crate::file_index::ranking::Ranking;
+use crate::file_index::sorting::Sorting;
+use crate::file_index::suggest_query_strategy::find_similar_terms;
+use crate::file_indexer::{
+    MatchingLine, SearchFileByContentsQuery, SearchFileResponse, SearchHit, SearchMode,
+};
+use crate::tokenizer::content_language::{get_content_languages, ContentLanguage};
+use crate::tokenizer::key_value_tokenizer::create_key_value_term;
+use anyhow::{anyhow, Error};
+use dscvr_common::config::{RankingSettings, SearchSettings};
+use log::{debug, error};
+use std::collections::{HashMap, HashSet};
+use std::fs;
 use std::ops::Deref;
 use std::sync::Arc;
-use tantivy::collector::TopDocs;
-use tantivy::query::QueryParser;
-use tantivy::schema::Schema;
-use tantivy::{Index, IndexReader};
+use std::time::{Duration, Instant};
+use tantivy::collector::{DocSetCollector, TopDocs};
+use tantivy::query::{AllQuery, BooleanQuery, Occur, Query, QueryParser, TermQuery};
+use tantivy::schema::{Field, IndexRecordOption, Schema};
+use tantivy::tokenizer::TextAnalyzer;
+use tantivy::{DateTime, DocAddress, Document, Index, IndexReader, Searcher, SegmentReader, Term};
+
+/// Larger distances match too many terms to be useful.
+const MAX_FUZZY_DISTANCE: u32 = 2;
+
+/// Stemmed matches, e.g. "houses" for "house", rank below exact ones.
+const CONTENT_LANGUAGE_FIELD_BOOST: f32 = 0.5;
+
+/// Text files larger than this aren't read again to find the matching lines, the stored contents are used instead.
+const MAX_TEXT_FILE_SIZE: u64 = 64 * 1024 * 1024;
+
+/// The terms of the query for every field of the contents that was searched, e.g. `contents` and `contents_de`.
+type QueryTerms = HashMap<Field, HashSet<String>>;
 
 pub(crate) trait SearchFileStrategy: Send + Sync {
-    fn search_file(self: &Self, search_term: &str) -> Vec<String>;
+    fn search_file(self: &Self, query: &SearchFileByContentsQuery) -> SearchFileResponse;
 }
 
 pub(crate) struct TantivySearchStrategy {
     index: Arc<Index>,
     index_reader: IndexReader,
     schema: Schema,
+    max_hits: usize,
+    time_budget: Duration,
+    ranking: Ranking,
+    file_name_boost: f32,
 }
 
 impl TantivySearchStrategy {
-    pub(crate) fn new(index: Arc<Index>, index_reader: IndexReader, schema: Schema) -> Self {
+    pub(crate) fn new(
+        index: Arc<Index>,
+        index_reader: IndexReader,
+        schema: Schema,
+        search_settings: &SearchSettings,
+        ranking_settings: &RankingSettings,
+    ) -> Self {
         TantivySearchStrategy {
             index,
             index_reader,
             schema,
+            max_hits: search_settings.max_hits as usize,
+            time_budget: Duration::from_millis(search_settings.time_budget_in_milliseconds),
+            ranking: Ranking::new(ranking_settings),
+            file_name_boost: ranking_settings.file_name_boost,
+        }
+    }
+
+    /// Finds the page of the first occurrence of a query term, for documents that stored their page offsets.
+    fn find_page_number(
+        &self,
+        doc: &Document,
+        contents_field: Field,
+        query_terms: &QueryTerms,
+    ) -> Option<u32> {
+        let page_offsets = self.get_page_offsets(doc);
+        if page_offsets.is_empty() {
+            return None;
+        }
+
+        let contents = doc.get_first(contents_field)?.as_text()?;
+        let mut text_analyzers =
+            self.get_text_analyzers(doc, contents, contents_field, query_terms);
+        let (offset_from, _) = find_token_matches(contents, &mut text_analyzers).next()?;
+
+        return Some(get_page_number(&page_offsets, offset_from as u64));
+    }
+
+    /// The fields that terms of the query are searched in: the contents and their stemmed fields of every language.
+    fn get_default_fields(&self, contents_field: Field) -> Vec<Field> {
+        return std::iter::once(contents_field)
+            .chain(
+                ContentLanguage::ALL
+                    .iter()
+                    .filter_map(|v| self.schema.get_field(v.field_name()).ok()),
+            )
+            .collect();
+    }
+
+    /// Returns the analyzers of the fields a document is indexed in, each with the query terms of its field. These are
+    /// the contents and the fields of the languages of the document, see [`get_content_languages`].
+    fn get_text_analyzers<'a>(
+        &self,
+        doc: &Document,
+        text: &str,
+        contents_field: Field,
+        query_terms: &'a QueryTerms,
+    ) -> Vec<(TextAnalyzer, &'a HashSet<String>)> {
+        let language = self
+            .schema
+            .get_field("language")
+            .ok()
+            .and_then(|v| doc.get_first(v))
+            .and_then(|v| v.as_text());
+        let content_language_fields = get_content_languages(language, text)
+            .into_iter()
+            .filter_map(|v| self.schema.get_field(v.field_name()).ok());
+
+        return std::iter::once(contents_field)
+            .chain(content_language_fields)
+            .filter_map(|field| {
+                let query_terms = query_terms.get(&field)?;
+                return match self.index.tokenizer_for_field(field) {
+                    Ok(v) => Some((v, query_terms)),
+                    Err(e) => {
+                        error!(
+                            "There was an error while trying to get the tokenizer of a field of the contents: {:?}",
+                            e
+                        );
+                        None
+                    }
+                };
+            })
+            .collect();
+    }
+
+    /// Fuzzy queries don't tell which terms they match, so these are looked up in the term dictionary, to find the
+    /// pages and lines of the terms.
+    fn find_fuzzy_query_terms(
+        &self,
+        searcher: &Searcher,
+        search_term: &str,
+        contents_field: Field,
+        distance: u8,
+    ) -> HashSet<String> {
+        let query_parser = QueryParser::for_index(self.index.deref(), vec![contents_field]);
+        let query = match query_parser.parse_query(search_term) {
+            Ok(v) => v,
+            Err(_) => return HashSet::new(),
+        };
+
+        let mut literal_terms = HashSet::new();
+        query.query_terms(&mut |term, _| {
+            if term.field() == contents_field {
+                if let Some(v) = term.value().as_str() {
+                    literal_terms.insert(v.to_string());
+                }
+            }
+        });
+
+        return literal_terms
+            .iter()
+            .flat_map(|v| find_similar_terms(searcher, contents_field, v, distance))
+            .map(|v| v.text)
+            .collect();
+    }
+
+    /// Collects up to the given amount of hits, by their relevance tweaked by the ranking or by the sorting if the
+    /// query has one.
+    fn collect_top_docs(
+        &self,
+        searcher: &Searcher,
+        query: &dyn Query,
+        sorting: Option<Sorting>,
+    ) -> tantivy::Result<Vec<DocAddress>> {
+        let top_docs = TopDocs::with_limit(self.max_hits + 1);
+
+        if let Some(sorting) = sorting {
+            let collector = top_docs.custom_score(move |segment_reader: &SegmentReader| {
+                return sorting.create_segment_scorer(segment_reader);
+            });
+            return Ok(searcher
+                .search(query, &collector)?
+                .into_iter()
+                .map(|(_, doc_address)| doc_address)
+                .collect());
+        }
+
+        let now = DateTime::from_timestamp_secs(chrono::offset::Utc::now().timestamp());
+        let ranking = self.ranking.clone();
+        let collector = top_docs.tweak_score(move |segment_reader: &SegmentReader| {
+            return ranking.create_segment_tweaker(segment_reader, now);
+        });
+        return Ok(searcher
+            .search(query, &collector)?
+            .into_iter()
+            .map(|(_, doc_address)| doc_address)
+            .collect());
+    }
+
+    /// Restricts the search to the directories of the scope of the query, in the index instead of filtering the hits.
+    fn create_scope_clauses(
+        &self,
+        query: &SearchFileByContentsQuery,
+    ) -> Vec<(Occur, Box<dyn Query>)> {
+        return match (self.schema.get_field("path_facet"), &query.scope) {
+            (Ok(path_facet_field), Some(scope)) => create_scope_clauses(path_facet_field, scope),
+            _ => Vec::new(),
+        };
+    }
+
+    fn get_page_offsets(&self, doc: &Document) -> Vec<u64> {
+        return match self.schema.get_field("page_offsets") {
+            Ok(v) => doc.get_all(v).filter_map(|v| v.as_u64()).collect(),
+            Err(_) => Vec::new(),
+        };
+    }
+
+    /// Returns the text the matching lines are taken from. Text files are read again, because their contents don't
+    /// necessarily consist of the lines of the file, e.g. for structured data, and the lines should match the file in
+    /// an editor.
+    fn get_text_of_lines(
+        &self,
+        doc: &Document,
+        path: &str,
+        contents_field: Field,
+    ) -> Option<String> {
+        let is_text_file = self
+            .schema
+            .get_field("encoding")
+            .ok()
+            .and_then(|v| doc.get_first(v))
+            .is_some();
+        if is_text_file {
+            match read_text_file(path) {
+                Ok(v) => return Some(v),
+                Err(e) => {
+                    debug!(
+                        "The file at path {:?} couldn't be read again, the stored contents are used instead: {:?}",
+                        path, e
+                    );
+                }
+            }
+        }
+
+        return doc
+            .get_first(contents_field)
+            .and_then(|v| v.as_text())
+            .map(|v| v.to_string());
+    }
+
+    /// Finds the lines the query matched on, see [`find_matching_lines`].
+    fn find_matching_lines(
+        &self,
+        doc: &Document,
+        path: &str,
+        contents_field: Field,
+        query_terms: &QueryTerms,
+        max_matching_lines: usize,
+    ) -> Vec<MatchingLine> {
+        if max_matching_lines == 0 || query_terms.values().all(|v| v.is_empty()) {
+            return Vec::new();
+        }
+
+        let text = match self.get_text_of_lines(doc, path, contents_field) {
+            Some(v) => v,
+            None => return Vec::new(),
+        };
+        let mut text_analyzers = self.get_text_analyzers(doc, &text, contents_field, query_terms);
+
+        return find_matching_lines(&text, &mut text_analyzers, max_matching_lines);
+    }
+
+    /// Finds candidates by the trigrams of the query and verifies them against their stored contents, until the
+    /// limit of hits is reached or the time budget is used up.
+    fn search_by_pattern(&self, query: &SearchFileByContentsQuery) -> SearchFileResponse {
+        let pattern = match create_pattern(&query.query, query.mode()) {
+            Ok(v) => v,
+            Err(e) => {
+                error!(
+                    "There was an error while trying to compile the pattern of the query: {:?}",
+                    e
+                );
+                return SearchFileResponse::default();
+            }
+        };
+        let (path_field, contents_field) = match (
+            self.schema.get_field("path"),
+            self.schema.get_field("contents"),
+        ) {
+            (Ok(path_field), Ok(contents_field)) => (path_field, contents_field),
+            (Err(e), _) | (_, Err(e)) => {
+                error!(
+                        "There was an error while trying to get the fields \"path\" and \"contents\" from index: {:?}",
+                        e
+                    );
+                return SearchFileResponse::default();
+            }
+        };
+        let mut candidate_clauses: Vec<(Occur, Box<dyn Query>)> = vec![(
+            Occur::Must,
+            match self.schema.get_field("contents_trigrams") {
+                Ok(v) => create_trigram_query(v, &query.query, query.mode()),
+                Err(_) => Box::new(AllQuery),
+            },
+        )];
+        candidate_clauses.extend(self.create_scope_clauses(query));
+        let candidate_query = BooleanQuery::new(candidate_clauses);
+
+        let deadline = Instant::now() + self.time_budget;
+        let searcher = self.index_reader.searcher();
+        let mut candidates = match searcher.search(&candidate_query, &DocSetCollector) {
+            Ok(v) => v.into_iter().collect::<Vec<_>>(),
+            Err(e) => {
+                error!(
+                    "There was an error while trying to search for the candidates of the pattern: {:?}",
+                    e
+                );
+                return SearchFileResponse::default();
+            }
+        };
+        candidates.sort();
+
+        let max_matching_lines = query.max_matching_lines.unwrap_or_default() as usize;
+        let mut hits = Vec::new();
+        for doc_address in candidates {
+            if hits.len() >= self.max_hits || Instant::now() >= deadline {
+                return create_response(hits, true);
+            }
+
+            let doc = match searcher.doc(doc_address) {
+                Ok(v) => v,
+                Err(e) => {
+                    error!("There was an error while trying to get a document: {:?}", e);
+                    continue;
+                }
+            };
+            let contents = doc
+                .get_first(contents_field)
+                .and_then(|v| v.as_text())
+                .unwrap_or_default();
+            let first_match = match pattern.find(contents) {
+                Some(v) => v,
+                None => continue,
+            };
+
+            let path = doc
+                .get_first(path_field)
+                .and_then(|v| v.as_text())
+                .unwrap_or_default()
+                .to_string();
+            let page_offsets = self.get_page_offsets(&doc);
+            let page = match page_offsets.is_empty() {
+                true => None,
+                false => Some(get_page_number(&page_offsets, first_match.start() as u64)),
+            };
+            let matching_lines = match max_matching_lines {
+                0 => Vec::new(),
+                _ => self
+                    .get_text_of_lines(&doc, &path, contents_field)
+                    .map(|v| find_pattern_matching_lines(&v, &pattern, max_matching_lines))
+                    .unwrap_or_default(),
+            };
+
+            hits.push(SearchHit {
+                path,
+                page,
+                matching_lines,
+            });
         }
+
+        return create_response(hits, false);
     }
 }
 
+fn create_response(hits: Vec<SearchHit>, incomplete: bool) -> SearchFileResponse {
+    return SearchFileResponse {
+        path: hits.iter().map(|v| v.path.clone()).collect(),
+        hits,
+        incomplete,
+        // Added by the service, which knows whether the search found enough.
+        suggestions: Vec::new(),
+    };
+}
+
+fn read_text_file(path: &str) -> Result<String, Error> {
+    let size = fs::metadata(path)?.len();
+    if size > MAX_TEXT_FILE_SIZE {
+        return Err(anyhow!("The file is too large with {} bytes.", size));
+    }
+
+    let (text, _) = decode_text(&fs::read(path)?);
+    return Ok(text);
+}
+
+fn get_page_number(page_offsets: &[u64], offset: u64) -> u32 {
+    return page_offsets.partition_point(|&v| v <= offset).max(1) as u32;
+}
+
+/// The key of terms that search the definitions of source code, e.g. `def:parse_query`.
+const DEFINITION_KEY: &str = "def";
+
+/// Splits terms that search a field with exact terms off the search term, together with the name of that field.
+/// These are `def:name` terms for definitions of source code and `key:value` terms for structured data, e.g.
+/// `serde.version:1.0` or `name:"Jane Doe"`. Terms whose key is a field of the schema, e.g. `title:report`, and
+/// terms for fields that don't exist in the schema are left to the query parser.
+fn extract_field_terms(
+    search_term: &str,
+    schema: &Schema,
+) -> (String, Vec<(&'static str, String)>) {
+    let mut remaining_terms = Vec::new();
+    let mut field_terms = Vec::new();
+
+    for term in split_search_term(search_term) {
+        let field_term = term
+            .split_once(':')
+            .filter(|(key, value)| {
+                return !key.is_empty()
+                    && !value.is_empty()
+                    && !key.starts_with(['-', '+', '"', '('])
+                    && schema.get_field(key).is_err();
+            })
+            .map(|(key, value)| {
+                let value = value.trim_matches('"');
+                return match key.eq_ignore_ascii_case(DEFINITION_KEY) {
+                    true => ("symbols", value.to_lowercase()),
+                    false => ("key_values", create_key_value_term(key, value)),
+                };
+            })
+            .filter(|(field_name, _)| schema.get_field(field_name).is_ok());
+
+        match field_term {
+            Some(v) => field_terms.push(v),
+            None => remaining_terms.push(term),
+        }
+    }
+
+    return (remaining_terms.join(" "), field_terms);
+}
+
+/// Splits at whitespace outside of double quotes, so quoted phrases and values stay together.
+fn split_search_term(search_term: &str) -> Vec<&str> {
+    let mut terms = Vec::new();
+    let mut start = None;
+    let mut is_quoted = false;
+
+    for (i, c) in search_term.char_indices() {
+        if c == '"' {
+            is_quoted = !is_quoted;
+        }
+
+        match (c.is_whitespace() && !is_quoted, start) {
+            (true, Some(v)) => {
+                terms.push(&search_term[v..i]);
+                start = None;
+            }
+            (false, None) => start = Some(i),
+            _ => {}
+        }
+    }
+
+    if let Some(v) = start {
+        terms.push(&search_term[v..]);
+    }
+
+    return terms;
+}
+
 impl SearchFileStrategy for TantivySearchStrategy {
-    fn search_file(self: &Self, search_term: &str) -> Vec<String> {
+    fn search_file(self: &Self, query: &SearchFileByContentsQuery) -> SearchFileResponse {
+        if query.mode() != SearchMode::Terms {
+            return self.search_by_pattern(query);
+        }
+
         let path_field = match self.schema.get_field("path") {
             Ok(v) => v,
             Err(e) => {
@@ -35,7 +484,7 @@ impl SearchFileStrategy for TantivySearchStrategy {
                     "There was an error while trying to get field \"path\" from index: {:?}",
                     e
                 );
-                return Vec::new();
+                return SearchFileResponse::default();
             }
         };
         let contents_field = match self.schema.get_field("contents") {
@@ -45,38 +494,120 @@ impl SearchFileStrategy for TantivySearchStrategy {
                     "There was an error while trying to get field \"contents\" from index: {:?}",
                     e
                 );
-                return Vec::new();
+                return SearchFileResponse::default();
             }
         };
         let searcher = self.index_reader.searcher();
-        let query_parser = QueryParser::for_index(self.index.deref(), vec![contents_field]);
-        let query = match query_parser.parse_query(search_term) {
-            Ok(v) => v,
-            Err(e) => {
-                error!(
-                    "There was an error while trying to parse the query: {:?}",
-                    e
-                );
-                return Vec::new();
+        let sorting = match query
+            .sort_by
+            .as_ref()
+            .map(|v| Sorting::new(&self.schema, v))
+        {
+            Some(Ok(v)) => Some(v),
+            Some(Err(e)) => {
+                error!("There was an error while trying to sort the hits: {:?}", e);
+                return SearchFileResponse::default();
             }
+            None => None,
         };
 
-        let top_docs = match searcher.search(&query, &TopDocs::with_limit(1000)) {
+        let max_matching_lines = query.max_matching_lines.unwrap_or_default() as usize;
+        let fuzzy_distance = query
+            .fuzzy_distance
+            .filter(|v| *v > 0)
+            .map(|v| v.min(MAX_FUZZY_DISTANCE) as u8);
+        let (search_term, field_terms) = extract_field_terms(&query.query, &self.schema);
+        let default_fields = self.get_default_fields(contents_field);
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+        for (field_name, field_term) in &field_terms {
+            if let Ok(field) = self.schema.get_field(field_name) {
+                clauses.push((
+                    Occur::Must,
+                    Box::new(TermQuery::new(
+                        Term::from_field_text(field, field_term),
+                        IndexRecordOption::Basic,
+                    )),
+                ));
+            }
+        }
+
+        if !search_term.trim().is_empty() || clauses.is_empty() {
+            // Files are also found by the words of their name, which count more than the ones of the contents.
+            let file_name_field = self.schema.get_field("file_name_terms").ok();
+            let mut query_parser = QueryParser::for_index(
+                self.index.deref(),
+                default_fields
+                    .iter()
+                    .copied()
+                    .chain(file_name_field)
+                    .collect(),
+            );
+            for field in default_fields.iter().filter(|v| **v != contents_field) {
+                query_parser.set_field_boost(*field, CONTENT_LANGUAGE_FIELD_BOOST);
+            }
+            if let Some(field) = file_name_field {
+                query_parser.set_field_boost(field, self.file_name_boost);
+            }
+            if let Some(distance) = fuzzy_distance {
+                query_parser.set_field_fuzzy(contents_field, false, distance, true);
+            }
+            match query_parser.parse_query(&search_term) {
+                Ok(v) => clauses.push((Occur::Must, v)),
+                Err(e) => {
+                    error!(
+                        "There was an error while trying to parse the query: {:?}",
+                        e
+                    );
+                    return SearchFileResponse::default();
+                }
+            };
+        }
+        clauses.extend(self.create_scope_clauses(query));
+        let query = BooleanQuery::new(clauses);
+
+        let mut query_terms = QueryTerms::new();
+        query.query_terms(&mut |term, _| {
+            if default_fields.contains(&term.field()) {
+                if let Some(v) = term.value().as_str() {
+                    query_terms
+                        .entry(term.field())
+                        .or_default()
+                        .insert(v.to_string());
+                }
+            }
+        });
+        if let Some(distance) = fuzzy_distance {
+            query_terms
+                .entry(contents_field)
+                .or_default()
+                .extend(self.find_fuzzy_query_terms(
+                    &searcher,
+                    &search_term,
+                    contents_field,
+                    distance,
+                ));
+        }
+
+        // One more than the limit tells whether there are more hits.
+        let mut top_docs = match self.collect_top_docs(&searcher, &query, sorting) {
             Ok(v) => v,
             Err(e) => {
                 error!(
                     "There was an error while trying to search with the given query: {:?}",
                     e
                 );
-                return Vec::new();
+                return SearchFileResponse::default();
             }
         };
 
+        let incomplete = top_docs.len() > self.max_hits;
+        top_docs.truncate(self.max_hits);
+
         let _offset = 50;
 
-        return top_docs
+        let hits = top_docs
             .into_iter()
-            .map(|(_, doc_address)| { searcher.doc(doc_address) })
+            .map(|doc_address| { searcher.doc(doc_address) })
             .filter_map(|doc_res| {
                 if doc_res.is_err() {
                     error!("There was an error while trying to get a document");
@@ -112,7 +643,7 @@ impl SearchFileStrategy for TantivySearchStrategy {
                 //     })
                 //     .collect();
 
-                return doc
+                let path: String = doc
                     .get_all(path_field)
                     .filter_map(|field_value| {
                         let as_text = field_value.as_text();
@@ -124,7 +655,62 @@ impl SearchFileStrategy for TantivySearchStrategy {
                         return as_text;
                     })
                     .collect();
+
+                let matching_lines = self.find_matching_lines(
+                    &doc,
+                    &path,
+                    contents_field,
+                    &query_terms,
+                    max_matching_lines,
+                );
+
+                return SearchHit {
+                    path,
+                    page: self.find_page_number(&doc, contents_field, &query_terms),
+                    matching_lines,
+                };
             })
             .collect();
+
+        return create_response(hits, incomplete);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_if_page_number_is_found_by_offset() {
+        let page_offsets = vec![0, 120, 300];
+
+        assert_eq!(get_page_number(&page_offsets, 0), 1);
+        assert_eq!(get_page_number(&page_offsets, 119), 1);
+        assert_eq!(get_page_number(&page_offsets, 120), 2);
+        assert_eq!(get_page_number(&page_offsets, 5000), 3);
+    }
+
+    #[test]
+    fn test_if_field_terms_are_extracted() {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("title", tantivy::schema::TEXT);
+        schema_builder.add_text_field("symbols", tantivy::schema::TEXT);
+        schema_builder.add_text_field("key_values", tantivy::schema::TEXT);
+        let schema = schema_builder.build();
+
+        let (search_term, field_terms) = extract_field_terms(
+            "serde.version:1.0 title:report def:parseQuery name:\"Jane Doe\" -draft",
+            &schema,
+        );
+
+        assert_eq!(search_term, "title:report -draft");
+        assert_eq!(
+            field_terms,
+            vec![
+                ("key_values", "serde.version:1.0".to_string()),
+                ("symbols", "parsequery".to_string()),
+                ("key_values", "name:jane doe".to_string()),
+            ]
+        );
     }
 }
@@ -0,0 +1,162 @@
+use crate::file_indexer::{SortBy, SortOrder};
+use anyhow::{anyhow, Error};
+use tantivy::schema::{FieldType, Schema};
+use tantivy::{DateTime, DocId, SegmentReader};
+
+/// Sorts the hits by the value of a date or number field instead of their relevance, e.g. photos by `captured_at`.
+/// Files without a value come last in both orders.
+#[derive(Clone)]
+pub(crate) struct Sorting {
+    field_name: String,
+    field_type: FieldType,
+    order: SortOrder,
+}
+
+impl Sorting {
+    /// Fails if the field doesn't exist or isn't a fast date or number field.
+    pub(crate) fn new(schema: &Schema, sort_by: &SortBy) -> Result<Self, Error> {
+        let field = schema.get_field(&sort_by.field)?;
+        let field_type = schema.get_field_entry(field).field_type().clone();
+
+        let is_sortable = matches!(
+            field_type,
+            FieldType::U64(_) | FieldType::I64(_) | FieldType::F64(_) | FieldType::Date(_)
+        );
+        if !is_sortable || !field_type.is_fast() {
+            return Err(anyhow!(
+                "The hits can't be sorted by the field {:?}, because it isn't a fast date or number field.",
+                sort_by.field
+            ));
+        }
+
+        return Ok(Self {
+            field_name: sort_by.field.clone(),
+            field_type,
+            order: sort_by.order(),
+        });
+    }
+
+    /// Creates the function that computes the sort keys of the documents of a segment, for `TopDocs::custom_score`,
+    /// which collects the largest keys. A segment without any value of the field has no column for it, so unlike
+    /// `TopDocs::order_by_fast_field` this doesn't fail for such segments.
+    pub(crate) fn create_segment_scorer(
+        self: &Self,
+        segment_reader: &SegmentReader,
+    ) -> impl FnMut(DocId) -> (bool, f64) {
+        let fast_fields = segment_reader.fast_fields();
+        let field_name = self.field_name.as_str();
+        let get_value: Box<dyn Fn(DocId) -> Option<f64>> = match self.field_type {
+            FieldType::U64(_) => match fast_fields.column_opt::<u64>(field_name).ok().flatten() {
+                Some(column) => Box::new(move |doc| column.first(doc).map(|v| v as f64)),
+                None => Box::new(|_| None),
+            },
+            FieldType::I64(_) => match fast_fields.column_opt::<i64>(field_name).ok().flatten() {
+                Some(column) => Box::new(move |doc| column.first(doc).map(|v| v as f64)),
+                None => Box::new(|_| None),
+            },
+            FieldType::F64(_) => match fast_fields.column_opt::<f64>(field_name).ok().flatten() {
+                Some(column) => Box::new(move |doc| column.first(doc)),
+                None => Box::new(|_| None),
+            },
+            _ => match fast_fields
+                .column_opt::<DateTime>(field_name)
+                .ok()
+                .flatten()
+            {
+                Some(column) => Box::new(move |doc| {
+                    return column.first(doc).map(|v| v.into_timestamp_micros() as f64);
+                }),
+                None => Box::new(|_| None),
+            },
+        };
+        let order = self.order;
+
+        return move |doc| {
+            return match (get_value(doc), order) {
+                (Some(value), SortOrder::Descending) => (true, value),
+                (Some(value), SortOrder::Ascending) => (true, -value),
+                (None, _) => (false, 0.0),
+            };
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tantivy::collector::TopDocs;
+    use tantivy::query::AllQuery;
+    use tantivy::schema::{FAST, INDEXED, STORED};
+    use tantivy::{doc, Index};
+
+    #[test]
+    fn test_if_hits_are_sorted_by_field_with_missing_values_last() {
+        let mut schema_builder = Schema::builder();
+        let path_field = schema_builder.add_text_field("path", STORED);
+        let captured_at_field = schema_builder.add_date_field("captured_at", INDEXED | FAST);
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema.clone());
+
+        // Every commit creates a segment, the last one has no column for the field.
+        let mut index_writer = index.writer(15_000_000).unwrap();
+        for (path, captured_at) in [("2021.jpg", 2021), ("2023.jpg", 2023)] {
+            index_writer
+                .add_document(doc!(
+                    path_field => path,
+                    captured_at_field => DateTime::from_timestamp_secs((captured_at - 1970) * 365 * 24 * 60 * 60),
+                ))
+                .unwrap();
+        }
+        index_writer.commit().unwrap();
+        index_writer
+            .add_document(doc!(path_field => "report.pdf"))
+            .unwrap();
+        index_writer.commit().unwrap();
+
+        let searcher = index.reader().unwrap().searcher();
+        let search = |order: SortOrder| {
+            let sorting = Sorting::new(
+                &schema,
+                &SortBy {
+                    field: "captured_at".to_string(),
+                    order: order as i32,
+                },
+            )
+            .unwrap();
+            let collector =
+                TopDocs::with_limit(10).custom_score(move |segment_reader: &SegmentReader| {
+                    return sorting.create_segment_scorer(segment_reader);
+                });
+
+            return searcher
+                .search(&AllQuery, &collector)
+                .unwrap()
+                .into_iter()
+                .map(|(_, doc_address)| {
+                    let doc = searcher.doc(doc_address).unwrap();
+                    return doc
+                        .get_first(path_field)
+                        .unwrap()
+                        .as_text()
+                        .unwrap()
+                        .to_string();
+                })
+                .collect::<Vec<_>>();
+        };
+
+        assert_eq!(
+            search(SortOrder::Descending),
+            vec!["2023.jpg", "2021.jpg", "report.pdf"]
+        );
+        assert_eq!(
+            search(SortOrder::Ascending),
+            vec!["2021.jpg", "2023.jpg", "report.pdf"]
+        );
+
+        let sort_by_path = SortBy {
+            field: "path".to_string(),
+            order: SortOrder::Ascending as i32,
+        };
+        assert!(Sorting::new(&schema, &sort_by_path).is_err());
+    }
+}
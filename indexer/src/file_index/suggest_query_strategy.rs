@@ -0,0 +1,250 @@
+use levenshtein_automata::{Distance, LevenshteinAutomatonBuilder, DFA};
+use log::error;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use tantivy::schema::{Field, Schema};
+use tantivy::{IndexReader, Searcher, Term};
+use tantivy_fst::Automaton;
+
+/// The amount of corrected queries that are proposed at most.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Shorter words are mostly abbreviations, for which every correction would be a guess.
+const MIN_WORD_LENGTH: usize = 3;
+
+/// Proposes corrected queries from the terms of the index, e.g. `parse query` for `prase qurey`.
+pub(crate) trait SuggestQueryStrategy: Send + Sync {
+    /// Returns the corrections, best first, or nothing if every word of the query is known.
+    fn suggest_queries(self: &Self, query: &str) -> Vec<String>;
+}
+
+pub(crate) struct TantivySuggestQueryStrategy {
+    index_reader: IndexReader,
+    schema: Schema,
+}
+
+impl TantivySuggestQueryStrategy {
+    pub(crate) fn new(index_reader: IndexReader, schema: Schema) -> Self {
+        return Self {
+            index_reader,
+            schema,
+        };
+    }
+}
+
+impl SuggestQueryStrategy for TantivySuggestQueryStrategy {
+    fn suggest_queries(self: &Self, query: &str) -> Vec<String> {
+        let contents_field = match self.schema.get_field("contents") {
+            Ok(v) => v,
+            Err(e) => {
+                error!(
+                    "There was an error while trying to get field \"contents\" from index: {:?}",
+                    e
+                );
+                return Vec::new();
+            }
+        };
+        let searcher = self.index_reader.searcher();
+
+        let words = query.split_whitespace().collect::<Vec<_>>();
+        let corrections = words
+            .iter()
+            .map(|word| find_corrections(&searcher, contents_field, word))
+            .collect::<Vec<_>>();
+        if corrections.iter().all(|v| v.is_empty()) {
+            return Vec::new();
+        }
+
+        let mut suggestions = Vec::new();
+        for i in 0..MAX_SUGGESTIONS {
+            if i > 0 && corrections.iter().all(|v| v.len() <= i) {
+                break;
+            }
+
+            // The i-th suggestion uses the i-th best correction of every word that has that many.
+            let suggestion = words
+                .iter()
+                .zip(&corrections)
+                .map(|(word, corrections)| match corrections.is_empty() {
+                    true => word.to_string(),
+                    false => corrections[i.min(corrections.len() - 1)].text.clone(),
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            if !suggestions.contains(&suggestion) {
+                suggestions.push(suggestion);
+            }
+        }
+
+        return suggestions;
+    }
+}
+
+/// Words with query syntax, e.g. `title:report` or `"phrase`, and words that are in the index aren't corrected.
+fn find_corrections(searcher: &Searcher, field: Field, word: &str) -> Vec<SimilarTerm> {
+    let word = word.to_lowercase();
+    if word.chars().count() < MIN_WORD_LENGTH || !word.chars().all(char::is_alphanumeric) {
+        return Vec::new();
+    }
+
+    let is_known = searcher
+        .doc_freq(&Term::from_field_text(field, &word))
+        .map_or(false, |v| v > 0);
+    if is_known {
+        return Vec::new();
+    }
+
+    let distance = match word.chars().count() {
+        0..=4 => 1,
+        _ => 2,
+    };
+    let mut similar_terms = find_similar_terms(searcher, field, &word, distance);
+    similar_terms.sort_by(|a, b| {
+        return a
+            .distance
+            .cmp(&b.distance)
+            .then(b.doc_freq.cmp(&a.doc_freq))
+            .then(a.text.cmp(&b.text));
+    });
+    similar_terms.truncate(MAX_SUGGESTIONS);
+
+    return similar_terms;
+}
+
+pub(crate) struct SimilarTerm {
+    pub text: String,
+    pub distance: u8,
+    /// The amount of documents that contain the term, summed up over all segments.
+    pub doc_freq: u64,
+}
+
+/// Finds the terms of the field within the Levenshtein distance of the given term, where swapping two characters
+/// counts as one edit. The distance has to be 1 or 2, larger distances match too many terms to be useful.
+pub(crate) fn find_similar_terms(
+    searcher: &Searcher,
+    field: Field,
+    term: &str,
+    distance: u8,
+) -> Vec<SimilarTerm> {
+    let dfa = get_automaton_builder(distance).build_dfa(term);
+
+    let mut doc_freqs = HashMap::<String, u64>::new();
+    for segment_reader in searcher.segment_readers() {
+        let inverted_index = match segment_reader.inverted_index(field) {
+            Ok(v) => v,
+            Err(e) => {
+                error!(
+                    "There was an error while trying to read the terms of a segment: {:?}",
+                    e
+                );
+                continue;
+            }
+        };
+
+        let mut term_stream = match inverted_index
+            .terms()
+            .search(DfaAutomaton(&dfa))
+            .into_stream()
+        {
+            Ok(v) => v,
+            Err(e) => {
+                error!(
+                    "There was an error while trying to search the terms of a segment: {:?}",
+                    e
+                );
+                continue;
+            }
+        };
+        while term_stream.advance() {
+            if let Ok(text) = std::str::from_utf8(term_stream.key()) {
+                *doc_freqs.entry(text.to_string()).or_default() +=
+                    term_stream.value().doc_freq as u64;
+            }
+        }
+    }
+
+    return doc_freqs
+        .into_iter()
+        .map(|(text, doc_freq)| {
+            let distance = match dfa.eval(&text) {
+                Distance::Exact(v) | Distance::AtLeast(v) => v,
+            };
+            return SimilarTerm {
+                text,
+                distance,
+                doc_freq,
+            };
+        })
+        .collect();
+}
+
+/// Building the automata is expensive, so the builders are shared.
+fn get_automaton_builder(distance: u8) -> &'static LevenshteinAutomatonBuilder {
+    static BUILDERS: [OnceLock<LevenshteinAutomatonBuilder>; 2] =
+        [OnceLock::new(), OnceLock::new()];
+
+    let distance = distance.clamp(1, 2);
+    return BUILDERS[distance as usize - 1]
+        .get_or_init(|| LevenshteinAutomatonBuilder::new(distance, true));
+}
+
+/// Lets the term dictionary of tantivy walk a Levenshtein automaton.
+struct DfaAutomaton<'a>(&'a DFA);
+
+impl Automaton for DfaAutomaton<'_> {
+    type State = u32;
+
+    fn start(&self) -> Self::State {
+        return self.0.initial_state();
+    }
+
+    fn is_match(&self, state: &Self::State) -> bool {
+        return matches!(self.0.distance(*state), Distance::Exact(_));
+    }
+
+    fn can_match(&self, state: &Self::State) -> bool {
+        return *state != levenshtein_automata::SINK_STATE;
+    }
+
+    fn accept(&self, state: &Self::State, byte: u8) -> Self::State {
+        return self.0.transition(*state, byte);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tantivy::schema::TEXT;
+    use tantivy::{doc, Index};
+
+    #[test]
+    fn test_if_typos_are_corrected() {
+        let mut schema_builder = Schema::builder();
+        let contents_field = schema_builder.add_text_field("contents", TEXT);
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema.clone());
+
+        let mut index_writer = index.writer(15_000_000).unwrap();
+        index_writer
+            .add_document(doc!(contents_field => "parse the query"))
+            .unwrap();
+        index_writer
+            .add_document(doc!(contents_field => "parse the quarry"))
+            .unwrap();
+        index_writer
+            .add_document(doc!(contents_field => "parse the query again"))
+            .unwrap();
+        index_writer.commit().unwrap();
+
+        let suggest_query_strategy =
+            TantivySuggestQueryStrategy::new(index.reader().unwrap(), schema);
+
+        assert_eq!(
+            suggest_query_strategy.suggest_queries("prase the qurey"),
+            vec!["parse the query", "parse the quarry"]
+        );
+        assert!(suggest_query_strategy
+            .suggest_queries("parse the query")
+            .is_empty());
+    }
+}
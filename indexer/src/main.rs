@@ -1,33 +1,49 @@
-use anyhow::Error;
-use std::collections::HashMap;
 use std::fs::create_dir;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
 use log::info;
 use tantivy::directory::MmapDirectory;
-use tantivy::schema::{FAST, STORED, TEXT};
+use tantivy::schema::{
+    FacetOptions, IndexRecordOption, TextFieldIndexing, TextOptions, FAST, INDEXED, STORED, STRING,
+    TEXT,
+};
 use tantivy::Index;
 use tonic::transport::Server;
 
 use dscvr_common::config::{init_config, AppSettings};
 use dscvr_common::logger::init_logger;
 
-use crate::conversion::clear_text_conversion::ClearTextConversion;
-use crate::conversion::convert_to_clear_text_strategy::MimeType;
+use crate::conversion::conversion_registry::ConversionRegistry;
 use crate::conversion::determine_file_type::DefaultDetermineFileTypeFactory;
-use crate::conversion::pdf_conversion::PdfConversion;
-use crate::conversion::Conversion;
+use crate::conversion::sandbox::worker::{
+    get_conversion_worker_memory_limit, run_conversion_worker,
+};
+use crate::file_index::autocomplete_strategy::TantivyAutocompleteStrategy;
+use crate::file_index::conversion_cache_strategy::{
+    ConversionCacheStrategy, NoConversionCache, SqliteConversionCache,
+};
 use crate::file_index::file_hash_strategy::DefaultFileHash;
 use crate::file_index::find_duplicated_files_strategy::FindDuplicatedFiles;
 use crate::file_index::index_file_strategy::TantivyIndexStrategy;
-use crate::file_index::persist_metadata_strategy::SqlitePersistenceStrategy;
+use crate::file_index::persist_metadata_strategy::{
+    PersistMetadataStrategy, SqlitePersistenceStrategy,
+};
+use crate::file_index::query_history_strategy::SqliteQueryHistory;
+use crate::file_index::schema_version::{is_index_outdated, remove_index, write_schema_version};
 use crate::file_index::search_file_strategy::TantivySearchStrategy;
+use crate::file_index::suggest_query_strategy::TantivySuggestQueryStrategy;
 use crate::file_index::FileIndexService;
 use crate::file_indexer::file_indexer_server::FileIndexerServer;
+use crate::tokenizer::content_language::ContentLanguage;
+use crate::tokenizer::{
+    register_tokenizers, CODE_TOKENIZER, FILE_NAME_TOKENIZER, KEY_VALUE_TOKENIZER,
+    TRIGRAM_TOKENIZER,
+};
 
 pub(crate) mod conversion;
 pub(crate) mod file_index;
+pub(crate) mod tokenizer;
 
 pub(crate) mod file_indexer {
     tonic::include_proto!("file_indexer");
@@ -37,91 +53,179 @@ pub(crate) mod proto_utils {
     tonic::include_proto!("proto_utils");
 }
 
+pub(crate) mod conversion_worker {
+    tonic::include_proto!("conversion_worker");
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     init_logger();
+
     let settings = init_config();
+
+    if let Some(memory_limit) = get_conversion_worker_memory_limit() {
+        return Ok(run_conversion_worker(memory_limit, &settings.indexer)?);
+    }
+
     let addr = format!("{}:{}", settings.indexer.host, settings.indexer.port)
         .parse()
         .unwrap();
 
     let mut schema_builder = tantivy::schema::Schema::builder();
-    schema_builder.add_text_field("contents", TEXT | STORED);
+    schema_builder.add_text_field(
+        "contents",
+        TextOptions::default().set_stored().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_tokenizer(CODE_TOKENIZER)
+                .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+        ),
+    );
     schema_builder.add_text_field("hash", STORED | FAST);
     schema_builder.add_text_field("path", STORED);
+    schema_builder.add_facet_field("path_facet", FacetOptions::default());
+    schema_builder.add_text_field("encoding", STRING | STORED);
+    schema_builder.add_text_field("title", TEXT | STORED);
+    schema_builder.add_text_field("author", TEXT | STORED);
+    schema_builder.add_text_field("subject", TEXT | STORED);
+    schema_builder.add_text_field("keywords", TEXT | STORED);
+    schema_builder.add_text_field("description", TEXT | STORED);
+    schema_builder.add_text_field("language", STRING | STORED);
+    schema_builder.add_date_field("created_at", INDEXED | STORED | FAST);
+    schema_builder.add_u64_field("page_count", INDEXED | STORED | FAST);
+    schema_builder.add_u64_field("page_offsets", STORED);
+    schema_builder.add_text_field("from", TEXT | STORED);
+    schema_builder.add_text_field("to", TEXT | STORED);
+    schema_builder.add_date_field("sent_at", INDEXED | STORED | FAST);
+    schema_builder.add_text_field("attachments", TEXT | STORED);
+    schema_builder.add_text_field("camera_model", TEXT | STORED);
+    schema_builder.add_text_field("lens", TEXT | STORED);
+    schema_builder.add_date_field("captured_at", INDEXED | STORED | FAST);
+    schema_builder.add_u64_field("orientation", INDEXED | STORED | FAST);
+    schema_builder.add_u64_field("width", INDEXED | STORED | FAST);
+    schema_builder.add_u64_field("height", INDEXED | STORED | FAST);
+    schema_builder.add_f64_field("gps_latitude", INDEXED | STORED | FAST);
+    schema_builder.add_f64_field("gps_longitude", INDEXED | STORED | FAST);
+    schema_builder.add_text_field("artist", TEXT | STORED);
+    schema_builder.add_text_field("album", TEXT | STORED);
+    schema_builder.add_u64_field("year", INDEXED | STORED | FAST);
+    schema_builder.add_f64_field("duration", INDEXED | STORED | FAST);
+    schema_builder.add_text_field("codec", STRING | STORED);
+    schema_builder.add_text_field("kernel_language", STRING | STORED);
+    schema_builder.add_text_field(
+        "key_values",
+        TextOptions::default().set_stored().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_tokenizer(KEY_VALUE_TOKENIZER)
+                .set_index_option(IndexRecordOption::Basic),
+        ),
+    );
+    schema_builder.add_text_field(
+        "symbols",
+        TextOptions::default().set_stored().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_tokenizer(CODE_TOKENIZER)
+                .set_index_option(IndexRecordOption::WithFreqs),
+        ),
+    );
+    schema_builder.add_text_field(
+        "contents_trigrams",
+        TextOptions::default().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_tokenizer(TRIGRAM_TOKENIZER)
+                .set_index_option(IndexRecordOption::Basic),
+        ),
+    );
+    schema_builder.add_text_field(
+        "file_name",
+        TextOptions::default().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_tokenizer(FILE_NAME_TOKENIZER)
+                .set_index_option(IndexRecordOption::Basic),
+        ),
+    );
+    schema_builder.add_text_field(
+        "file_name_terms",
+        TextOptions::default().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_tokenizer(CODE_TOKENIZER)
+                .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+        ),
+    );
+    schema_builder.add_date_field("modified_at", INDEXED | STORED | FAST);
+    schema_builder.add_u64_field("path_depth", FAST);
+    schema_builder.add_bool_field("in_demoted_directory", FAST);
+    for content_language in ContentLanguage::ALL {
+        schema_builder.add_text_field(
+            content_language.field_name(),
+            TextOptions::default().set_indexing_options(
+                TextFieldIndexing::default()
+                    .set_tokenizer(content_language.field_name())
+                    .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+            ),
+        );
+    }
     let file_schema = schema_builder.build();
 
+    let persist_metadata_strategy =
+        Arc::new(SqlitePersistenceStrategy::build_with_settings(&settings)?);
+
     let index_path = get_index_path(&settings);
+    // The files are forgotten first, so an interrupted rebuild is repeated on the next start.
+    if is_index_outdated(&index_path)? {
+        info!("The index was created with another schema, every file is indexed again");
+        persist_metadata_strategy.forget_indexed_files()?;
+        remove_index(&index_path)?;
+    }
     let index = Arc::new(Index::open_or_create(
         MmapDirectory::open(&index_path)?,
         file_schema.clone(),
     )?);
+    write_schema_version(&index_path)?;
+    register_tokenizers(&index);
 
     let writer = index.writer(1_000_000_000)?; // TODO: lower the memory budget of the writer.
     let reader = index.reader()?;
 
-    let determine_file_type_strategy = Arc::new(DefaultDetermineFileTypeFactory::create());
-
-    let mut conversions_map = HashMap::<MimeType, Arc<dyn Conversion>>::new();
-    conversions_map.insert(MimeType::TextPlain, Arc::new(ClearTextConversion::new()));
-    conversions_map.insert(MimeType::ApplicationPdf, Arc::new(PdfConversion::new()));
-    conversions_map.insert(MimeType::ApplicationMsWord, Arc::new(NoOpConversion {}));
-    conversions_map.insert(
-        MimeType::ApplicationVndOpenxmlformatsOfficedocumentWordprocessingmlDocument,
-        Arc::new(NoOpConversion {}),
-    );
-    conversions_map.insert(
-        MimeType::ApplicationVndOasisOpendocumentPresentation,
-        Arc::new(NoOpConversion {}),
+    let determine_file_type_strategy = Arc::new(
+        DefaultDetermineFileTypeFactory::create_with_settings(&settings.indexer.file_types),
     );
-    conversions_map.insert(
-        MimeType::ApplicationVndOasisOpendocumentSpreadsheet,
-        Arc::new(NoOpConversion {}),
-    );
-    conversions_map.insert(
-        MimeType::ApplicationVndOasisOpendocumentText,
-        Arc::new(NoOpConversion {}),
-    );
-    conversions_map.insert(
-        MimeType::ApplicationVndMsPowerpoint,
-        Arc::new(NoOpConversion {}),
-    );
-    conversions_map.insert(
-        MimeType::ApplicationVndOpenxmlformatsOfficedocumentPresentationmlPresentation,
-        Arc::new(NoOpConversion {}),
-    );
-    conversions_map.insert(MimeType::ApplicationRtf, Arc::new(NoOpConversion {}));
-    conversions_map.insert(MimeType::ApplicationVndMsExcel, Arc::new(NoOpConversion {}));
-    conversions_map.insert(
-        MimeType::ApplicationVndOpenxmlformatsOfficedocumentSpreadsheetmlSheet,
-        Arc::new(NoOpConversion {}),
-    );
-    conversions_map.insert(MimeType::ImageAvif, Arc::new(NoOpConversion {}));
-    conversions_map.insert(MimeType::ImageBmp, Arc::new(NoOpConversion {}));
-    conversions_map.insert(MimeType::ImageGif, Arc::new(NoOpConversion {}));
-    conversions_map.insert(MimeType::ImageIcon, Arc::new(NoOpConversion {}));
-    conversions_map.insert(MimeType::ImageJpeg, Arc::new(NoOpConversion {}));
-    conversions_map.insert(MimeType::ImagePng, Arc::new(NoOpConversion {}));
-    conversions_map.insert(MimeType::ImageSvgXml, Arc::new(NoOpConversion {}));
-    conversions_map.insert(MimeType::ImageTiff, Arc::new(NoOpConversion {}));
-    conversions_map.insert(MimeType::ImageWebp, Arc::new(NoOpConversion {}));
 
-    let persist_metadata_strategy =
-        Arc::new(SqlitePersistenceStrategy::build_with_settings(&settings)?);
+    let conversion_registry = Arc::new(ConversionRegistry::build_with_settings(&settings.indexer)?);
+
+    let conversion_cache_strategy: Arc<dyn ConversionCacheStrategy> =
+        if settings.indexer.conversion_cache.enabled {
+            Arc::new(SqliteConversionCache::build_with_settings(&settings)?)
+        } else {
+            Arc::new(NoConversionCache {})
+        };
+
     let file_hash_strategy = Arc::new(DefaultFileHash::new());
 
     let index_strategy = Arc::new(Mutex::new(TantivyIndexStrategy::new(
-        file_schema.clone(),
         writer,
         persist_metadata_strategy,
         determine_file_type_strategy,
-        conversions_map,
+        conversion_registry,
+        conversion_cache_strategy,
         file_hash_strategy,
+        &settings.indexer,
     )));
+    let suggest_query_strategy = Arc::new(TantivySuggestQueryStrategy::new(
+        reader.clone(),
+        file_schema.clone(),
+    ));
+    let query_history_strategy = Arc::new(SqliteQueryHistory::build_with_settings(&settings)?);
+    let autocomplete_strategy = Arc::new(TantivyAutocompleteStrategy::new(
+        reader.clone(),
+        file_schema.clone(),
+        query_history_strategy.clone(),
+    ));
     let search_strategy = Arc::new(TantivySearchStrategy::new(
         index,
         reader,
         file_schema.clone(),
+        &settings.indexer.search,
+        &settings.indexer.ranking,
     ));
     let find_duplicated_files_strategy =
         Arc::new(FindDuplicatedFiles::build_with_settings(&settings)?);
@@ -129,7 +233,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let file_indexer = FileIndexService::new(
         index_strategy,
         search_strategy,
+        suggest_query_strategy,
         find_duplicated_files_strategy,
+        query_history_strategy,
+        autocomplete_strategy,
     );
 
     info!("listening on {}", addr);
@@ -156,11 +263,3 @@ fn get_index_path(settings: &AppSettings) -> PathBuf {
 
     return index_path;
 }
-
-pub(crate) struct NoOpConversion;
-
-impl Conversion for NoOpConversion {
-    fn convert(&self, _: Vec<u8>) -> Result<String, Error> {
-        return Ok("".to_string());
-    }
-}
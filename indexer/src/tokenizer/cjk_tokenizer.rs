@@ -0,0 +1,160 @@
+use crate::tokenizer::code_tokenizer::tokenize_code;
+use tantivy::tokenizer::{Token, TokenStream, Tokenizer};
+
+/// Splits Chinese, Japanese and Korean text, which has no spaces between words, into every single character and every
+/// pair of consecutive characters. A pair takes the position of its first character, so a phrase of bigrams like
+/// `東京` `京都` finds `東京都`, while single characters still find the words they are part of. Text in other scripts
+/// is split like by the [`CodeTokenizer`](crate::tokenizer::code_tokenizer::CodeTokenizer).
+#[derive(Clone, Default)]
+pub(crate) struct CjkTokenizer;
+
+pub(crate) struct CjkTokenStream {
+    tokens: Vec<Token>,
+    /// The index of the current token plus one, so zero means that the stream wasn't advanced yet.
+    next_index: usize,
+}
+
+impl Tokenizer for CjkTokenizer {
+    type TokenStream<'a> = CjkTokenStream;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
+        return CjkTokenStream {
+            tokens: tokenize_cjk(text),
+            next_index: 0,
+        };
+    }
+}
+
+impl TokenStream for CjkTokenStream {
+    fn advance(&mut self) -> bool {
+        if self.next_index >= self.tokens.len() {
+            return false;
+        }
+
+        self.next_index += 1;
+        return true;
+    }
+
+    fn token(&self) -> &Token {
+        return &self.tokens[self.next_index - 1];
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        return &mut self.tokens[self.next_index - 1];
+    }
+}
+
+/// Han ideographs, kana and Hangul. Punctuation of these scripts isn't included, so it separates runs like spaces do.
+pub(crate) fn is_cjk_char(c: char) -> bool {
+    return matches!(c,
+        '\u{1100}'..='\u{11FF}'
+        | '\u{3040}'..='\u{30FF}'
+        | '\u{3130}'..='\u{318F}'
+        | '\u{31F0}'..='\u{31FF}'
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{AC00}'..='\u{D7AF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{FF66}'..='\u{FF9F}'
+        | '\u{20000}'..='\u{2FA1F}'
+    );
+}
+
+fn tokenize_cjk(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut position = 0;
+
+    for (run_start, run, is_cjk) in split_runs(text) {
+        if !is_cjk {
+            let run_tokens = tokenize_code(run);
+            let next_position = run_tokens.iter().map(|v| v.position + 1).max();
+            tokens.extend(run_tokens.into_iter().map(|v| Token {
+                offset_from: run_start + v.offset_from,
+                offset_to: run_start + v.offset_to,
+                position: position + v.position,
+                ..v
+            }));
+            position += next_position.unwrap_or_default();
+            continue;
+        }
+
+        let chars = run.char_indices().collect::<Vec<_>>();
+        for (i, &(offset, c)) in chars.iter().enumerate() {
+            let offset_from = run_start + offset;
+            tokens.push(Token {
+                offset_from,
+                offset_to: offset_from + c.len_utf8(),
+                position,
+                text: c.to_string(),
+                position_length: 1,
+            });
+
+            if let Some(&(next_offset, next_c)) = chars.get(i + 1) {
+                tokens.push(Token {
+                    offset_from,
+                    offset_to: run_start + next_offset + next_c.len_utf8(),
+                    position,
+                    text: format!("{}{}", c, next_c),
+                    position_length: 1,
+                });
+            }
+
+            position += 1;
+        }
+    }
+
+    return tokens;
+}
+
+/// Splits the text into runs of CJK characters and runs of everything else, with their byte offsets.
+fn split_runs(text: &str) -> Vec<(usize, &str, bool)> {
+    let mut runs = Vec::new();
+    let mut run_start = 0;
+    let mut is_cjk_run = false;
+
+    for (offset, c) in text.char_indices() {
+        let is_cjk = is_cjk_char(c);
+        if is_cjk != is_cjk_run && offset > run_start {
+            runs.push((run_start, &text[run_start..offset], is_cjk_run));
+            run_start = offset;
+        }
+        is_cjk_run = is_cjk;
+    }
+
+    if run_start < text.len() {
+        runs.push((run_start, &text[run_start..], is_cjk_run));
+    }
+
+    return runs;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_if_cjk_is_split_into_bigrams_and_latin_into_words() {
+        let tokens = tokenize_cjk("東京都のparseQuery");
+
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|v| (v.text.as_str(), v.position))
+                .collect::<Vec<_>>(),
+            vec![
+                ("東", 0),
+                ("東京", 0),
+                ("京", 1),
+                ("京都", 1),
+                ("都", 2),
+                ("都の", 2),
+                ("の", 3),
+                ("parseQuery", 4),
+                ("parse", 4),
+                ("Query", 5),
+            ]
+        );
+        assert_eq!((tokens[3].offset_from, tokens[3].offset_to), (3, 9));
+        assert_eq!((tokens[8].offset_from, tokens[8].offset_to), (12, 17));
+    }
+}
@@ -0,0 +1,169 @@
+use tantivy::tokenizer::{Token, TokenStream, Tokenizer};
+
+/// Splits text into words of letters, digits and underscores. Identifiers are additionally split into their
+/// `camelCase` and `snake_case` subwords, so `parseQuery` is found by `parsequery`, `parse` and `query`. The
+/// subwords take the positions of consecutive words, which keeps phrase searches like `parse query` working.
+#[derive(Clone, Default)]
+pub(crate) struct CodeTokenizer;
+
+pub(crate) struct CodeTokenStream {
+    tokens: Vec<Token>,
+    /// The index of the current token plus one, so zero means that the stream wasn't advanced yet.
+    next_index: usize,
+}
+
+impl Tokenizer for CodeTokenizer {
+    type TokenStream<'a> = CodeTokenStream;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
+        return CodeTokenStream {
+            tokens: tokenize_code(text),
+            next_index: 0,
+        };
+    }
+}
+
+impl TokenStream for CodeTokenStream {
+    fn advance(&mut self) -> bool {
+        if self.next_index >= self.tokens.len() {
+            return false;
+        }
+
+        self.next_index += 1;
+        return true;
+    }
+
+    fn token(&self) -> &Token {
+        return &self.tokens[self.next_index - 1];
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        return &mut self.tokens[self.next_index - 1];
+    }
+}
+
+pub(crate) fn tokenize_code(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut position = 0;
+
+    for (word_start, word) in split_words(text) {
+        tokens.push(Token {
+            offset_from: word_start,
+            offset_to: word_start + word.len(),
+            position,
+            text: word.to_string(),
+            position_length: 1,
+        });
+
+        let subwords = split_subwords(word);
+        let has_subwords = !(subwords.len() == 1 && subwords[0].1 == word);
+        if has_subwords {
+            for (i, (subword_start, subword)) in subwords.iter().enumerate() {
+                tokens.push(Token {
+                    offset_from: word_start + subword_start,
+                    offset_to: word_start + subword_start + subword.len(),
+                    position: position + i,
+                    text: subword.to_string(),
+                    position_length: 1,
+                });
+            }
+        }
+
+        position += subwords.len().max(1);
+    }
+
+    return tokens;
+}
+
+/// Returns the words with their byte offsets.
+fn split_words(text: &str) -> Vec<(usize, &str)> {
+    let mut words = Vec::new();
+    let mut word_start = None;
+
+    for (offset, c) in text.char_indices() {
+        let is_word_char = c.is_alphanumeric() || c == '_';
+        match (is_word_char, word_start) {
+            (true, None) => word_start = Some(offset),
+            (false, Some(start)) => {
+                words.push((start, &text[start..offset]));
+                word_start = None;
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(start) = word_start {
+        words.push((start, &text[start..]));
+    }
+
+    return words;
+}
+
+/// Splits at underscores and changes of case, e.g. `HTTPServer_config` into `HTTP`, `Server` and `config`. Digits
+/// stay with the preceding letters, e.g. `utf8`.
+fn split_subwords(word: &str) -> Vec<(usize, &str)> {
+    let chars = word.char_indices().collect::<Vec<_>>();
+    let mut subwords = Vec::new();
+    let mut subword_start: Option<usize> = None;
+
+    for (i, &(offset, c)) in chars.iter().enumerate() {
+        if c == '_' {
+            if let Some(start) = subword_start.take() {
+                subwords.push((start, &word[start..offset]));
+            }
+            continue;
+        }
+
+        let start = match subword_start {
+            Some(v) => v,
+            None => {
+                subword_start = Some(offset);
+                continue;
+            }
+        };
+
+        let previous = chars[i - 1].1;
+        let next = chars.get(i + 1).map(|v| v.1);
+        let is_boundary = c.is_uppercase()
+            && (previous.is_lowercase()
+                || previous.is_numeric()
+                || (previous.is_uppercase() && next.map_or(false, char::is_lowercase)));
+        if is_boundary {
+            subwords.push((start, &word[start..offset]));
+            subword_start = Some(offset);
+        }
+    }
+
+    if let Some(start) = subword_start {
+        subwords.push((start, &word[start..]));
+    }
+
+    return subwords;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_if_identifiers_are_split_into_subwords() {
+        let tokens = tokenize_code("fn parse_query(HTTPServer) {}");
+
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|v| (v.text.as_str(), v.position))
+                .collect::<Vec<_>>(),
+            vec![
+                ("fn", 0),
+                ("parse_query", 1),
+                ("parse", 1),
+                ("query", 2),
+                ("HTTPServer", 3),
+                ("HTTP", 3),
+                ("Server", 4),
+            ]
+        );
+        assert_eq!((tokens[5].offset_from, tokens[5].offset_to), (15, 19));
+    }
+}
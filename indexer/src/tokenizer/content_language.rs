@@ -0,0 +1,167 @@
+use crate::tokenizer::cjk_tokenizer::is_cjk_char;
+use tantivy::tokenizer::Language;
+use whatlang::{Detector, Script};
+
+/// The beginning of a text is enough to tell its language, so longer texts aren't detected as a whole.
+const MAX_DETECTION_LENGTH: usize = 16 * 1024;
+
+/// The languages whose contents are indexed a second time in a field of their own. Words are stemmed, without stop
+/// words and with accents folded, e.g. to find "Häuser" by "Haus" or "résumé" by "resume". Chinese, Japanese and
+/// Korean share a field, which is split into characters and bigrams, see
+/// [`CjkTokenizer`](crate::tokenizer::cjk_tokenizer::CjkTokenizer).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ContentLanguage {
+    English,
+    German,
+    Cjk,
+}
+
+impl ContentLanguage {
+    pub(crate) const ALL: [ContentLanguage; 3] = [
+        ContentLanguage::English,
+        ContentLanguage::German,
+        ContentLanguage::Cjk,
+    ];
+
+    /// Accepts ISO 639-1 and ISO 639-3 codes as well as language tags, e.g. `en`, `eng` or `en-US`.
+    pub(crate) fn from_code(code: &str) -> Option<Self> {
+        let primary_code = code.split(['-', '_']).next().unwrap_or_default();
+
+        return match primary_code.to_lowercase().as_str() {
+            "en" | "eng" => Some(ContentLanguage::English),
+            "de" | "deu" | "ger" => Some(ContentLanguage::German),
+            "zh" | "zho" | "chi" | "cmn" | "ja" | "jpn" | "ko" | "kor" => {
+                Some(ContentLanguage::Cjk)
+            }
+            _ => None,
+        };
+    }
+
+    /// The name of both the field and its tokenizer.
+    pub(crate) fn field_name(self: &Self) -> &'static str {
+        return match self {
+            ContentLanguage::English => "contents_en",
+            ContentLanguage::German => "contents_de",
+            ContentLanguage::Cjk => "contents_cjk",
+        };
+    }
+
+    /// Returns nothing for languages that aren't stemmed.
+    pub(crate) fn stemmer_language(self: &Self) -> Option<Language> {
+        return match self {
+            ContentLanguage::English => Some(Language::English),
+            ContentLanguage::German => Some(Language::German),
+            ContentLanguage::Cjk => None,
+        };
+    }
+}
+
+/// Returns the ISO 639-3 code of the language of the text, e.g. `eng` or `deu`, if it can be told reliably. Texts
+/// in a CJK script always get a language, even if it's unsure which one, because they share their analyzer.
+pub(crate) fn detect_language(text: &str) -> Option<&'static str> {
+    let end = text
+        .char_indices()
+        .nth(MAX_DETECTION_LENGTH)
+        .map_or(text.len(), |(i, _)| i);
+
+    return Detector::new()
+        .detect(&text[..end])
+        .filter(|v| {
+            let is_cjk_script = matches!(
+                v.script(),
+                Script::Mandarin | Script::Hiragana | Script::Katakana | Script::Hangul
+            );
+            return v.is_reliable() || is_cjk_script;
+        })
+        .map(|v| v.lang().code());
+}
+
+/// Returns the languages whose fields the contents are indexed in, for the language of the document. Contents in
+/// another language can still contain a passage in a CJK script, which would otherwise only be indexed as a single
+/// long word, so the CJK field is added whenever there is a CJK character.
+pub(crate) fn get_content_languages(
+    language: Option<&str>,
+    contents: &str,
+) -> Vec<ContentLanguage> {
+    let mut content_languages = language
+        .and_then(ContentLanguage::from_code)
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    if !content_languages.contains(&ContentLanguage::Cjk) && contents.chars().any(is_cjk_char) {
+        content_languages.push(ContentLanguage::Cjk);
+    }
+
+    return content_languages;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::register_tokenizers;
+    use tantivy::schema::Schema;
+    use tantivy::Index;
+
+    #[test]
+    fn test_if_language_is_detected() {
+        let english =
+            "The quarterly report shows that the houses were sold faster than expected this year.";
+        let german = "Der Bericht zeigt, dass die Häuser in diesem Jahr schneller verkauft wurden als erwartet.";
+
+        assert_eq!(
+            detect_language(english).and_then(ContentLanguage::from_code),
+            Some(ContentLanguage::English)
+        );
+        assert_eq!(
+            detect_language(german).and_then(ContentLanguage::from_code),
+            Some(ContentLanguage::German)
+        );
+        assert_eq!(
+            ContentLanguage::from_code("de-AT"),
+            Some(ContentLanguage::German)
+        );
+        assert_eq!(
+            detect_language("東京都は日本の首都です。").and_then(ContentLanguage::from_code),
+            Some(ContentLanguage::Cjk)
+        );
+        assert_eq!(ContentLanguage::from_code("fr"), None);
+
+        let mixed =
+            "The quarterly report of the Tokyo office was sent to the whole team last week. \
+            東京都の事務所の四半期報告書です。";
+        assert_eq!(
+            get_content_languages(detect_language(mixed), mixed),
+            vec![ContentLanguage::English, ContentLanguage::Cjk]
+        );
+        assert_eq!(
+            get_content_languages(Some("jpn"), mixed),
+            vec![ContentLanguage::Cjk]
+        );
+    }
+
+    #[test]
+    fn test_if_words_are_stemmed_and_folded() {
+        let index = Index::create_in_ram(Schema::builder().build());
+        register_tokenizers(&index);
+        let analyze = |content_language: ContentLanguage, text: &str| {
+            let mut text_analyzer = index
+                .tokenizers()
+                .get(content_language.field_name())
+                .unwrap();
+            let mut tokens = Vec::new();
+            text_analyzer
+                .token_stream(text)
+                .process(&mut |v| tokens.push(v.text.clone()));
+            return tokens;
+        };
+
+        assert_eq!(
+            analyze(ContentLanguage::German, "die Häuser"),
+            analyze(ContentLanguage::German, "Haus")
+        );
+        assert_eq!(
+            analyze(ContentLanguage::English, "the résumés"),
+            analyze(ContentLanguage::English, "resume")
+        );
+    }
+}
@@ -0,0 +1,98 @@
+use tantivy::tokenizer::{PreTokenizedStream, PreTokenizedString, Token, Tokenizer};
+
+/// Separates the key path from the value in the stored `key_values` of structured data.
+pub(crate) const KEY_VALUE_SEPARATOR: &str = ": ";
+
+/// Long values like descriptions are also indexed word by word, up to this amount of words.
+const MAX_VALUE_WORDS: usize = 32;
+
+/// Tokenizes `dependencies.serde.version: 1.0` into `key:value` terms for every suffix of the key path, i.e.
+/// `dependencies.serde.version:1.0`, `serde.version:1.0` and `version:1.0`, so options can be searched without
+/// their full path.
+#[derive(Clone, Default)]
+pub(crate) struct KeyValueTokenizer;
+
+impl Tokenizer for KeyValueTokenizer {
+    type TokenStream<'a> = PreTokenizedStream;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
+        let tokens = create_key_value_terms(text)
+            .into_iter()
+            .enumerate()
+            .map(|(position, term)| Token {
+                offset_from: 0,
+                offset_to: text.len(),
+                position,
+                text: term,
+                position_length: 1,
+            })
+            .collect();
+
+        return PreTokenizedStream::from(PreTokenizedString {
+            text: text.to_string(),
+            tokens,
+        });
+    }
+}
+
+fn create_key_value_terms(text: &str) -> Vec<String> {
+    let (key, value) = match text.split_once(KEY_VALUE_SEPARATOR) {
+        Some(v) => v,
+        None => return Vec::new(),
+    };
+
+    let value = value.trim().to_lowercase();
+    let mut values = vec![value.clone()];
+    if value.contains(char::is_whitespace) {
+        values.extend(
+            value
+                .split_whitespace()
+                .take(MAX_VALUE_WORDS)
+                .map(|v| v.to_string()),
+        );
+    }
+    values.sort();
+    values.dedup();
+
+    let key = key.trim().to_lowercase();
+    let mut terms = Vec::new();
+    let mut key_suffix = Some(key.as_str());
+    while let Some(suffix) = key_suffix.filter(|v| !v.is_empty()) {
+        for value in &values {
+            terms.push(create_key_value_term(suffix, value));
+        }
+        key_suffix = suffix.split_once('.').map(|(_, v)| v);
+    }
+
+    return terms;
+}
+
+/// Creates the term that a `key:value` search has to match, the same way as the tokenizer does.
+pub(crate) fn create_key_value_term(key: &str, value: &str) -> String {
+    return format!(
+        "{}:{}",
+        key.trim().to_lowercase(),
+        value.trim().to_lowercase()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_if_terms_are_created_for_every_key_suffix() {
+        assert_eq!(
+            create_key_value_terms("dependencies.Serde.version: 1.0"),
+            vec![
+                "dependencies.serde.version:1.0",
+                "serde.version:1.0",
+                "version:1.0",
+            ]
+        );
+        assert_eq!(
+            create_key_value_terms("name: Jane Doe"),
+            vec!["name:doe", "name:jane", "name:jane doe"]
+        );
+    }
+}
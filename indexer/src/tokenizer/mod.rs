@@ -0,0 +1,75 @@
+pub(crate) mod cjk_tokenizer;
+pub(crate) mod code_tokenizer;
+pub(crate) mod content_language;
+pub(crate) mod key_value_tokenizer;
+
+use crate::tokenizer::cjk_tokenizer::CjkTokenizer;
+use crate::tokenizer::code_tokenizer::CodeTokenizer;
+use crate::tokenizer::content_language::ContentLanguage;
+use crate::tokenizer::key_value_tokenizer::KeyValueTokenizer;
+use tantivy::tokenizer::{
+    AsciiFoldingFilter, LowerCaser, NgramTokenizer, RawTokenizer, RemoveLongFilter,
+    SimpleTokenizer, Stemmer, StopWordFilter, TextAnalyzer,
+};
+use tantivy::Index;
+
+pub(crate) const CODE_TOKENIZER: &str = "code";
+pub(crate) const KEY_VALUE_TOKENIZER: &str = "key_value";
+pub(crate) const TRIGRAM_TOKENIZER: &str = "trigram";
+pub(crate) const FILE_NAME_TOKENIZER: &str = "file_name";
+
+/// Tokens longer than this are most likely encoded data, the same limit as tantivy's default tokenizer.
+const MAX_TOKEN_LENGTH: usize = 40;
+
+/// Registers the tokenizers of the schema, which have to be known before documents are added or searched.
+pub(crate) fn register_tokenizers(index: &Index) {
+    index.tokenizers().register(
+        CODE_TOKENIZER,
+        TextAnalyzer::builder(CodeTokenizer)
+            .filter(RemoveLongFilter::limit(MAX_TOKEN_LENGTH))
+            .filter(LowerCaser)
+            .build(),
+    );
+    index
+        .tokenizers()
+        .register(KEY_VALUE_TOKENIZER, TextAnalyzer::from(KeyValueTokenizer));
+    // Every sequence of three characters, including whitespace and punctuation, for substring and regex searches.
+    index.tokenizers().register(
+        TRIGRAM_TOKENIZER,
+        TextAnalyzer::builder(
+            NgramTokenizer::all_ngrams(3, 3).expect("The size of the trigrams has to be valid."),
+        )
+        .filter(LowerCaser)
+        .build(),
+    );
+    // The whole name as one token, so names can be completed by their prefix.
+    index.tokenizers().register(
+        FILE_NAME_TOKENIZER,
+        TextAnalyzer::builder(RawTokenizer::default())
+            .filter(LowerCaser)
+            .build(),
+    );
+    // Stemming works on words, so identifiers aren't split here. Accents are folded before stemming, otherwise e.g.
+    // "résumé" and "resume" get different stems, but after removing stop words, which contain accents.
+    for content_language in ContentLanguage::ALL {
+        let text_analyzer = match content_language.stemmer_language() {
+            Some(language) => TextAnalyzer::builder(SimpleTokenizer::default())
+                .filter(RemoveLongFilter::limit(MAX_TOKEN_LENGTH))
+                .filter(LowerCaser)
+                .filter(
+                    StopWordFilter::new(language)
+                        .expect("There have to be stop words for every stemmed content language."),
+                )
+                .filter(AsciiFoldingFilter)
+                .filter(Stemmer::new(language))
+                .build(),
+            None => TextAnalyzer::builder(CjkTokenizer)
+                .filter(RemoveLongFilter::limit(MAX_TOKEN_LENGTH))
+                .filter(LowerCaser)
+                .build(),
+        };
+        index
+            .tokenizers()
+            .register(content_language.field_name(), text_analyzer);
+    }
+}